@@ -1,4 +1,5 @@
 use reqwest::Client;
+#[cfg(not(feature = "debug-mock"))]
 use sentry::{Breadcrumb, Level, add_breadcrumb, capture_message};
 use std::borrow::Cow;
 use std::collections::HashMap;
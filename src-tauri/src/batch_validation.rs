@@ -0,0 +1,176 @@
+use crate::file_utils::{FileStability, has_image_extension, unfinished_files};
+use crate::model::{BatchProblem, BatchProblemKind, BatchRepresentation, BatchValidationReport};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const DEFAULT_MAX_BATCH_UPLOAD_BYTES: u64 = 200 * 1024 * 1024 * 1024; // 200 GiB
+
+static MAX_BATCH_UPLOAD_BYTES: Lazy<Mutex<u64>> =
+	Lazy::new(|| Mutex::new(DEFAULT_MAX_BATCH_UPLOAD_BYTES));
+
+pub fn set_max_batch_upload_size(max_bytes: u64) -> Result<(), String> {
+	if max_bytes == 0 {
+		return Err("Invalid maximum upload size. Must be greater than 0.".to_string());
+	}
+	let mut max_batch_upload_bytes = MAX_BATCH_UPLOAD_BYTES.lock().map_err(|e| e.to_string())?;
+	*max_batch_upload_bytes = max_bytes;
+	Ok(())
+}
+
+pub(crate) fn get_max_batch_upload_size() -> u64 {
+	MAX_BATCH_UPLOAD_BYTES
+		.lock()
+		.map(|max| *max)
+		.unwrap_or(DEFAULT_MAX_BATCH_UPLOAD_BYTES)
+}
+
+/// Checks every file of every batch before an upload starts, and reports all problems found
+/// instead of stopping at the first one. Access copies are paired with primary pages, so a
+/// batch with access copies must have exactly one per primary page. Files a scanner is still
+/// writing, according to `stability`, are problems too.
+pub(crate) fn validate_batch(
+	batch_map: &HashMap<String, BatchRepresentation>,
+	max_total_bytes: u64,
+	stability: &FileStability,
+) -> BatchValidationReport {
+	let mut problems = Vec::new();
+	let mut file_count = 0;
+	let mut total_bytes = 0;
+
+	if batch_map.is_empty() {
+		problems.push(BatchProblem {
+			kind: BatchProblemKind::EmptyBatchMap,
+			batch_id: None,
+			path: None,
+			message: "Nothing was selected for upload".to_string(),
+		});
+	}
+
+	// Sorted, so the report is the same every time for the same batch map
+	let mut batch_ids: Vec<&String> = batch_map.keys().collect();
+	batch_ids.sort();
+
+	let mut seen_paths = HashSet::new();
+	let mut readable_files = Vec::new();
+	for batch_id in batch_ids {
+		let batch = &batch_map[batch_id];
+		let problem = |kind, path: Option<&str>, message: String| BatchProblem {
+			kind,
+			batch_id: Some(batch_id.clone()),
+			path: path.map(str::to_string),
+			message,
+		};
+
+		if batch.primary.is_empty() && batch.access.is_empty() {
+			problems.push(problem(
+				BatchProblemKind::EmptyBatch,
+				None,
+				format!("Object {batch_id} has no pages"),
+			));
+			continue;
+		}
+		if !batch.access.is_empty() && batch.access.len() != batch.primary.len() {
+			problems.push(problem(
+				BatchProblemKind::RepresentationCountMismatch,
+				None,
+				format!(
+					"Object {batch_id} has {} primary pages but {} access pages",
+					batch.primary.len(),
+					batch.access.len()
+				),
+			));
+		}
+
+		for path in batch.primary.iter().chain(batch.access.iter()) {
+			file_count += 1;
+			if !seen_paths.insert(path.as_str()) {
+				problems.push(problem(
+					BatchProblemKind::DuplicateFile,
+					Some(path),
+					format!("{path} is included more than once"),
+				));
+				continue;
+			}
+			match check_file(Path::new(path)) {
+				Ok(size) => {
+					total_bytes += size;
+					readable_files.push((batch_id, path));
+				}
+				Err((kind, message)) => problems.push(problem(kind, Some(path), message)),
+			}
+		}
+	}
+
+	// Checked together, so the upload waits for at most one stability interval
+	let paths: Vec<&str> = readable_files
+		.iter()
+		.map(|(_, path)| path.as_str())
+		.collect();
+	let unfinished: HashMap<PathBuf, String> =
+		unfinished_files(&paths, stability).into_iter().collect();
+	for (batch_id, path) in readable_files {
+		if let Some(message) = unfinished.get(Path::new(path)) {
+			problems.push(BatchProblem {
+				kind: BatchProblemKind::UnfinishedFile,
+				batch_id: Some(batch_id.clone()),
+				path: Some(path.clone()),
+				message: message.clone(),
+			});
+		}
+	}
+
+	if total_bytes > max_total_bytes {
+		problems.push(BatchProblem {
+			kind: BatchProblemKind::TooLarge,
+			batch_id: None,
+			path: None,
+			message: format!(
+				"The upload is {total_bytes} bytes, which exceeds the limit of {max_total_bytes} bytes"
+			),
+		});
+	}
+
+	BatchValidationReport {
+		valid: problems.is_empty(),
+		file_count,
+		total_bytes,
+		problems,
+	}
+}
+
+/// Returns the size of the file, or why it cannot be uploaded
+fn check_file(path: &Path) -> Result<u64, (BatchProblemKind, String)> {
+	if !has_image_extension(path) {
+		return Err((
+			BatchProblemKind::UnsupportedExtension,
+			format!("{} is not a supported image file", path.display()),
+		));
+	}
+	let metadata = fs::metadata(path).map_err(|e| match e.kind() {
+		io::ErrorKind::NotFound => (
+			BatchProblemKind::MissingFile,
+			format!("{} does not exist", path.display()),
+		),
+		_ => (
+			BatchProblemKind::Unreadable,
+			format!("Failed to read {}: {e}", path.display()),
+		),
+	})?;
+	if !metadata.is_file() {
+		return Err((
+			BatchProblemKind::NotAFile,
+			format!("{} is not a file", path.display()),
+		));
+	}
+	File::open(path).map_err(|e| {
+		(
+			BatchProblemKind::Unreadable,
+			format!("Failed to read {}: {e}", path.display()),
+		)
+	})?;
+	Ok(metadata.len())
+}
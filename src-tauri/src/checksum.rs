@@ -0,0 +1,73 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+pub(crate) type Sha256Digest = [u8; 32];
+
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+pub(crate) fn sha256(bytes: &[u8]) -> Sha256Digest {
+	Sha256::digest(bytes).into()
+}
+
+/// Computes the SHA-256 of a file without reading it all into memory
+pub(crate) fn sha256_file(path: &Path) -> io::Result<Sha256Digest> {
+	let mut file = File::open(path)?;
+	let mut hasher = Sha256::new();
+	let mut buf = vec![0u8; READ_BUFFER_SIZE];
+	loop {
+		let n = file.read(&mut buf)?;
+		if n == 0 {
+			break;
+		}
+		hasher.update(&buf[..n]);
+	}
+	Ok(hasher.finalize().into())
+}
+
+/// Checksum S3 reports for the file once uploaded by `s3`: the plain SHA-256 if it fits in a
+/// single part, otherwise the composite checksum of its `part_size` parts
+pub(crate) fn s3_sha256_file(path: &Path, part_size: usize) -> io::Result<String> {
+	let mut file = File::open(path)?;
+	if file.metadata()?.len() <= part_size as u64 {
+		return Ok(to_base64(&sha256_file(path)?));
+	}
+
+	let mut part_digests = Vec::new();
+	loop {
+		let mut hasher = Sha256::new();
+		let part_length = io::copy(&mut (&mut file).take(part_size as u64), &mut hasher)?;
+		if part_length == 0 {
+			break;
+		}
+		part_digests.push(hasher.finalize().into());
+	}
+	Ok(composite_sha256_base64(&part_digests))
+}
+
+/// Checksum of a multipart upload as S3 reports it: the SHA-256 of the concatenated part
+/// checksums, followed by the number of parts
+pub(crate) fn composite_sha256_base64(part_digests: &[Sha256Digest]) -> String {
+	let mut hasher = Sha256::new();
+	for digest in part_digests {
+		hasher.update(digest);
+	}
+	let digest: Sha256Digest = hasher.finalize().into();
+	format!("{}-{}", to_base64(&digest), part_digests.len())
+}
+
+/// S3 sends and reports checksums as base64
+pub(crate) fn to_base64(digest: &Sha256Digest) -> String {
+	STANDARD.encode(digest)
+}
+
+pub(crate) fn from_base64(checksum: &str) -> Option<Sha256Digest> {
+	STANDARD.decode(checksum).ok()?.try_into().ok()
+}
+
+pub(crate) fn to_hex(digest: &Sha256Digest) -> String {
+	digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
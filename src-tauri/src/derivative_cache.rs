@@ -0,0 +1,338 @@
+use once_cell::sync::Lazy;
+use sentry::{Breadcrumb, Level, add_breadcrumb};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::file_utils;
+use crate::page_analysis::PageAnalysis;
+
+pub(crate) const THUMBNAIL_FOLDER_NAME: &str = ".thumbnails";
+pub(crate) const PREVIEW_FOLDER_NAME: &str = ".previews";
+pub(crate) const WEBP_EXTENSION: &str = "webp";
+pub(crate) const DERIVATIVE_CACHE_FOLDER_NAME: &str = "derivatives";
+const STAMP_EXTENSION: &str = "webp.json";
+const CENTRAL_THUMBNAIL_FOLDER_NAME: &str = "thumbnails";
+const CENTRAL_PREVIEW_FOLDER_NAME: &str = "previews";
+const DEFAULT_MAX_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024; // 2 GiB
+const MIN_MAX_CACHE_BYTES: u64 = 16 * 1024 * 1024; // 16 MiB
+/// The central cache is checked against its size limit after this many derivatives are written
+const WRITES_BETWEEN_EVICTIONS: usize = 64;
+
+/// Where derivatives are stored. Next to the scans by default, or in an app-level cache
+/// directory so the scan folders stay untouched until they are handed off for archiving.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DerivativeLocation {
+	/// `.thumbnails` and `.previews` folders next to every image
+	BesideSource,
+	/// Files named by a hash of the source path, evicted least recently used first once the
+	/// cache grows beyond `max_bytes`
+	Central { directory: PathBuf, max_bytes: u64 },
+}
+
+static DERIVATIVE_LOCATION: Lazy<Mutex<DerivativeLocation>> =
+	Lazy::new(|| Mutex::new(DerivativeLocation::BesideSource));
+
+static WRITES_SINCE_EVICTION: AtomicUsize = AtomicUsize::new(0);
+
+/// Stores derivatives in `directory` if given, or next to the scans if `None`
+pub fn set_derivative_cache(directory: Option<PathBuf>, max_bytes: u64) -> Result<(), String> {
+	let location = match directory {
+		None => DerivativeLocation::BesideSource,
+		Some(_) if max_bytes < MIN_MAX_CACHE_BYTES => {
+			return Err(format!(
+				"Invalid cache size. Must be at least {MIN_MAX_CACHE_BYTES} bytes."
+			));
+		}
+		Some(directory) => DerivativeLocation::Central {
+			directory,
+			max_bytes,
+		},
+	};
+	let mut current = DERIVATIVE_LOCATION.lock().map_err(|e| e.to_string())?;
+	*current = location;
+	drop(current);
+
+	evict_if_over_limit();
+	Ok(())
+}
+
+pub(crate) fn get_derivative_location() -> DerivativeLocation {
+	DERIVATIVE_LOCATION
+		.lock()
+		.map(|location| location.clone())
+		.unwrap_or(DerivativeLocation::BesideSource)
+}
+
+/// Default size limit of the central cache, used when the frontend does not give one
+pub(crate) fn default_max_cache_bytes() -> u64 {
+	DEFAULT_MAX_CACHE_BYTES
+}
+
+/// Where the thumbnail, or the preview if `high_res`, of an image is stored
+pub(crate) fn derivative_path(image_path: &Path, high_res: bool) -> Result<PathBuf, String> {
+	match get_derivative_location() {
+		DerivativeLocation::BesideSource => derivative_path_beside_source(image_path, high_res),
+		DerivativeLocation::Central { directory, .. } => {
+			Ok(central_derivative_path(&directory, image_path, high_res))
+		}
+	}
+}
+
+fn derivative_path_beside_source(image_path: &Path, high_res: bool) -> Result<PathBuf, String> {
+	let parent_directory = file_utils::get_parent_directory(image_path)?;
+	let filename_original_image = file_utils::get_file_name(image_path)?;
+
+	let mut path = parent_directory.to_owned();
+	path.push(if high_res {
+		PREVIEW_FOLDER_NAME
+	} else {
+		THUMBNAIL_FOLDER_NAME
+	});
+	path.push(filename_original_image);
+	path.set_extension(WEBP_EXTENSION);
+	Ok(path)
+}
+
+/// `<directory>/thumbnails/<hash of source path>.webp`, or `previews` if `high_res`
+pub(crate) fn central_derivative_path(
+	directory: &Path,
+	image_path: &Path,
+	high_res: bool,
+) -> PathBuf {
+	let mut path = directory.join(if high_res {
+		CENTRAL_PREVIEW_FOLDER_NAME
+	} else {
+		CENTRAL_THUMBNAIL_FOLDER_NAME
+	});
+	path.push(central_name(image_path));
+	path.set_extension(WEBP_EXTENSION);
+	path
+}
+
+/// Name of the files kept in the central cache for an image: a hash of its canonical path
+pub(crate) fn central_name(image_path: &Path) -> String {
+	let source = fs::canonicalize(image_path).unwrap_or_else(|_| image_path.to_path_buf());
+	let hash = Sha256::digest(source.to_string_lossy().as_bytes());
+	hash[..16].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// What a derivative was made from, stored next to it as `<name>.webp.json`. The derivative is
+/// stale once the source file or the conversion settings no longer match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DerivativeStamp {
+	pub(crate) source_size: u64,
+	/// Modification time of the source in milliseconds since the Unix epoch
+	pub(crate) source_modified_ms: u64,
+	/// Conversion settings the derivative was made with
+	pub(crate) profile: String,
+	/// Measured on the page while its thumbnail was made
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(crate) analysis: Option<PageAnalysis>,
+}
+
+impl DerivativeStamp {
+	pub(crate) fn new(source: &fs::Metadata, profile: String) -> Self {
+		Self {
+			source_size: source.len(),
+			source_modified_ms: modified_ms(source),
+			profile,
+			analysis: None,
+		}
+	}
+
+	/// Whether the stamp was written for this version of the source and `profile`
+	fn matches(&self, source: &fs::Metadata, profile: &str) -> bool {
+		self.source_size == source.len()
+			&& self.source_modified_ms == modified_ms(source)
+			&& self.profile == profile
+	}
+}
+
+fn modified_ms(metadata: &fs::Metadata) -> u64 {
+	metadata
+		.modified()
+		.ok()
+		.and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+		.map(|since_epoch| since_epoch.as_millis() as u64)
+		.unwrap_or_default()
+}
+
+fn stamp_path(derivative: &Path) -> PathBuf {
+	derivative.with_extension(STAMP_EXTENSION)
+}
+
+pub(crate) fn write_stamp(derivative: &Path, stamp: &DerivativeStamp) -> io::Result<()> {
+	file_utils::write_atomically(stamp_path(derivative), &serde_json::to_vec(stamp)?)?;
+	if WRITES_SINCE_EVICTION.fetch_add(1, Ordering::Relaxed) + 1 >= WRITES_BETWEEN_EVICTIONS {
+		evict_if_over_limit();
+	}
+	Ok(())
+}
+
+fn read_stamp(derivative: &Path) -> Option<DerivativeStamp> {
+	let contents = fs::read(stamp_path(derivative)).ok()?;
+	serde_json::from_slice(&contents).ok()
+}
+
+/// Whether `derivative` exists and was made from the current version of `image_path` with the
+/// current `profile`. Derivatives made before stamps were written are current if they are newer
+/// than the source.
+pub(crate) fn is_current(image_path: &Path, derivative: &Path, profile: &str) -> io::Result<bool> {
+	let Ok(derivative_metadata) = fs::metadata(derivative) else {
+		return Ok(false);
+	};
+	let source = fs::metadata(image_path)?;
+	let current = match read_stamp(derivative) {
+		Some(stamp) => stamp.matches(&source, profile),
+		None => modified_ms(&derivative_metadata) >= modified_ms(&source),
+	};
+	if current
+		&& matches!(
+			get_derivative_location(),
+			DerivativeLocation::Central { .. }
+		) {
+		// The stamp's modification time records when the derivative was last used
+		let _ = fs::File::options()
+			.append(true)
+			.open(stamp_path(derivative))
+			.and_then(|stamp| stamp.set_modified(SystemTime::now()));
+	}
+	Ok(current)
+}
+
+/// The stamp of `derivative` if it exists and was made from the current version of `image_path`
+/// with the current `profile`
+pub(crate) fn current_stamp(
+	image_path: &Path,
+	derivative: &Path,
+	profile: &str,
+) -> Option<DerivativeStamp> {
+	if !derivative.is_file() {
+		return None;
+	}
+	let source = fs::metadata(image_path).ok()?;
+	read_stamp(derivative).filter(|stamp| stamp.matches(&source, profile))
+}
+
+/// Removes the least recently used derivatives from the central cache until it fits within its
+/// size limit. Does nothing if derivatives are stored next to the scans.
+pub(crate) fn evict_if_over_limit() {
+	WRITES_SINCE_EVICTION.store(0, Ordering::Relaxed);
+	let DerivativeLocation::Central {
+		directory,
+		max_bytes,
+	} = get_derivative_location()
+	else {
+		return;
+	};
+	// Only one eviction at a time, so two threads do not evict the same entries
+	static EVICTION: Mutex<()> = Mutex::new(());
+	let Ok(_eviction) = EVICTION.try_lock() else {
+		return;
+	};
+
+	match evict(&directory, max_bytes) {
+		Ok(0) => {}
+		Ok(evicted) => add_breadcrumb(Breadcrumb {
+			category: Some("derivative_cache".into()),
+			message: Some(format!("Evicted {evicted} derivatives from the cache")),
+			level: Level::Info,
+			..Default::default()
+		}),
+		Err(e) => add_breadcrumb(Breadcrumb {
+			category: Some("derivative_cache".into()),
+			message: Some(format!("Failed to evict derivatives from the cache: {e}")),
+			level: Level::Warning,
+			..Default::default()
+		}),
+	}
+}
+
+/// A derivative in the central cache together with its stamp
+struct CacheEntry {
+	derivative: PathBuf,
+	bytes: u64,
+	last_used: SystemTime,
+}
+
+/// Returns the number of derivatives removed
+pub(crate) fn evict(directory: &Path, max_bytes: u64) -> io::Result<usize> {
+	let mut entries = Vec::new();
+	for folder in [CENTRAL_THUMBNAIL_FOLDER_NAME, CENTRAL_PREVIEW_FOLDER_NAME] {
+		let Ok(read_dir) = fs::read_dir(directory.join(folder)) else {
+			continue;
+		};
+		for entry in read_dir {
+			let derivative = entry?.path();
+			if derivative.extension().is_none_or(|e| e != WEBP_EXTENSION) {
+				continue;
+			}
+			let metadata = fs::metadata(&derivative)?;
+			let stamp = fs::metadata(stamp_path(&derivative)).ok();
+			entries.push(CacheEntry {
+				bytes: metadata.len() + stamp.as_ref().map_or(0, |s| s.len()),
+				last_used: stamp
+					.and_then(|s| s.modified().ok())
+					.or_else(|| metadata.modified().ok())
+					.unwrap_or(UNIX_EPOCH),
+				derivative,
+			});
+		}
+	}
+
+	let mut total_bytes: u64 = entries.iter().map(|e| e.bytes).sum();
+	entries.sort_by_key(|e| e.last_used);
+	let mut evicted = 0;
+	for entry in entries {
+		if total_bytes <= max_bytes {
+			break;
+		}
+		fs::remove_file(&entry.derivative)?;
+		let _ = fs::remove_file(stamp_path(&entry.derivative));
+		total_bytes = total_bytes.saturating_sub(entry.bytes);
+		evicted += 1;
+	}
+	Ok(evicted)
+}
+
+/// Removes the thumbnail and preview of an image, and their stamps, wherever they are stored.
+/// Returns how many derivatives were removed.
+pub(crate) fn remove_derivatives(image_path: &Path) -> io::Result<u32> {
+	let mut removed = 0;
+	for high_res in [false, true] {
+		let derivative = derivative_path(image_path, high_res).map_err(io::Error::other)?;
+		match fs::remove_file(&derivative) {
+			Ok(()) => removed += 1,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+			Err(e) => return Err(e),
+		}
+		let _ = fs::remove_file(stamp_path(&derivative));
+	}
+	Ok(removed)
+}
+
+/// Removes the centrally cached derivatives of `image_paths`. Returns how many were removed.
+pub(crate) fn remove_central_derivatives(image_paths: &[PathBuf]) -> io::Result<u32> {
+	let DerivativeLocation::Central { directory, .. } = get_derivative_location() else {
+		return Ok(0);
+	};
+	let mut removed = 0;
+	for image_path in image_paths {
+		for high_res in [false, true] {
+			let derivative = central_derivative_path(&directory, image_path, high_res);
+			if derivative.exists() {
+				fs::remove_file(&derivative)?;
+				let _ = fs::remove_file(stamp_path(&derivative));
+				removed += 1;
+			}
+		}
+	}
+	Ok(removed)
+}
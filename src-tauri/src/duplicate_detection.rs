@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::ImageConversionError;
+use crate::model::BatchRepresentation;
+use crate::page_analysis::{self, Blankness, PageAnalysis};
+
+/// Pages at least this similar are likely duplicates, at most 9 of the 64 hash bits differ
+const MIN_DUPLICATE_SIMILARITY: f32 = 0.85;
+const HASH_BITS: f32 = u64::BITS as f32;
+
+/// Pages that are likely the same page scanned more than once, returned by
+/// `find_duplicate_pages` and `find_duplicate_pages_in_batches`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct DuplicateGroup {
+	/// Object the pages belong to, `None` for pages in a directory
+	pub(crate) batch_id: Option<String>,
+	/// In the order the pages are listed
+	pub(crate) pages: Vec<String>,
+	/// Similarity of the two least similar pages in the group, from 0 to 1
+	pub(crate) similarity: f32,
+}
+
+/// A page that may have duplicates. Pages with the same `page` number are expected to look
+/// alike, like a primary page and its access copy, and are never reported as duplicates.
+struct Candidate {
+	path: PathBuf,
+	page: usize,
+	analysis: PageAnalysis,
+}
+
+/// Similarity of two perceptual hashes, from 0 for opposite to 1 for equal
+pub(crate) fn similarity(first: u64, second: u64) -> f32 {
+	1.0 - (first ^ second).count_ones() as f32 / HASH_BITS
+}
+
+/// Groups of likely duplicates among the images in the directory
+pub fn find_duplicate_pages<P: AsRef<Path>>(
+	directory_path: P,
+) -> Result<Vec<DuplicateGroup>, ImageConversionError> {
+	let candidates = page_analysis::analyze_directory(directory_path)?
+		.into_iter()
+		.enumerate()
+		.map(|(page, (path, analysis))| Candidate {
+			path,
+			page,
+			analysis,
+		})
+		.collect::<Vec<_>>();
+	Ok(group_duplicates(&candidates, None))
+}
+
+/// Groups of likely duplicates within each batch, among its primary pages, its access pages and
+/// across the two. A primary page and the access copy at the same position are not duplicates.
+pub fn find_duplicate_pages_in_batches(
+	batch_map: &HashMap<String, BatchRepresentation>,
+) -> Result<Vec<DuplicateGroup>, ImageConversionError> {
+	// Sorted, so the groups are in the same order every time for the same batch map
+	let mut batch_ids: Vec<&String> = batch_map.keys().collect();
+	batch_ids.sort();
+
+	let mut groups = Vec::new();
+	for batch_id in batch_ids {
+		let batch = &batch_map[batch_id];
+		let pages: Vec<(usize, &String)> = batch
+			.primary
+			.iter()
+			.enumerate()
+			.chain(batch.access.iter().enumerate())
+			.collect();
+		let paths: Vec<&str> = pages.iter().map(|(_, path)| path.as_str()).collect();
+		let analyses = page_analysis::analyze_files(&paths)?;
+
+		let candidates = pages
+			.into_iter()
+			.zip(analyses)
+			.filter_map(|((page, path), analysis)| {
+				Some(Candidate {
+					path: PathBuf::from(path),
+					page,
+					analysis: analysis?,
+				})
+			})
+			.collect::<Vec<_>>();
+		groups.extend(group_duplicates(&candidates, Some(batch_id)));
+	}
+	Ok(groups)
+}
+
+/// Groups the pages that are similar enough to each other, see `group_similar`. Pages without
+/// content all look alike, so they are left out.
+fn group_duplicates(candidates: &[Candidate], batch_id: Option<&String>) -> Vec<DuplicateGroup> {
+	let candidates: Vec<&Candidate> = candidates
+		.iter()
+		.filter(|candidate| candidate.analysis.blankness() == Blankness::Content)
+		.collect();
+	let pages: Vec<(usize, u64)> = candidates
+		.iter()
+		.map(|candidate| (candidate.page, candidate.analysis.perceptual_hash))
+		.collect();
+
+	group_similar(&pages)
+		.into_iter()
+		.map(|group| {
+			let similarity = group
+				.iter()
+				.enumerate()
+				.flat_map(|(i, &first)| group[i + 1..].iter().map(move |&second| (first, second)))
+				.filter_map(|(first, second)| pair_similarity(&pages, first, second))
+				.fold(1.0, f32::min);
+			DuplicateGroup {
+				batch_id: batch_id.cloned(),
+				pages: group
+					.iter()
+					.map(|&index| candidates[index].path.to_string_lossy().to_string())
+					.collect(),
+				similarity,
+			}
+		})
+		.collect()
+}
+
+/// Groups pages, given as page number and perceptual hash, where every page is similar enough
+/// to every other page in the group. Pages are never grouped through a chain of pages that each
+/// look a bit like the next. Each page joins the first group it fits, in the order given. Pages
+/// with the same number are not compared, but a page must be similar to at least one other.
+/// Returns the indices of the pages in each group of more than one page.
+pub(crate) fn group_similar(pages: &[(usize, u64)]) -> Vec<Vec<usize>> {
+	let fits = |group: &[usize], index: usize| {
+		let mut linked = false;
+		for &member in group {
+			match pair_similarity(pages, member, index) {
+				Some(similarity) if similarity >= MIN_DUPLICATE_SIMILARITY => linked = true,
+				Some(_) => return false,
+				None => {}
+			}
+		}
+		linked
+	};
+
+	let mut groups: Vec<Vec<usize>> = Vec::new();
+	for index in 0..pages.len() {
+		match groups.iter_mut().find(|group| fits(group, index)) {
+			Some(group) => group.push(index),
+			None => groups.push(vec![index]),
+		}
+	}
+	groups.retain(|group| group.len() > 1);
+	groups
+}
+
+/// Similarity of two pages, `None` for pages with the same number
+fn pair_similarity(pages: &[(usize, u64)], first: usize, second: usize) -> Option<f32> {
+	let ((first_page, first_hash), (second_page, second_hash)) = (pages[first], pages[second]);
+	(first_page != second_page).then(|| similarity(first_hash, second_hash))
+}
@@ -10,9 +10,24 @@ pub enum ImageConversionError {
 	#[error("Failed to encode WebP: {0}")]
 	WebPEncodingError(#[from] WebPEncodingErrorWrapper),
 	#[error("{0}")]
+	UnfinishedFile(String),
+	#[error("{0}")]
 	StrError(String),
 }
 
+impl ImageConversionError {
+	/// Short name of the error variant, reported to the frontend next to the message
+	pub fn kind(&self) -> &'static str {
+		match self {
+			ImageConversionError::ImageError(_) => "image",
+			ImageConversionError::IoError(_) => "io",
+			ImageConversionError::WebPEncodingError(_) => "webpEncoding",
+			ImageConversionError::UnfinishedFile(_) => "unfinishedFile",
+			ImageConversionError::StrError(_) => "other",
+		}
+	}
+}
+
 pub struct WebPEncodingErrorWrapper(pub webp::WebPEncodingError);
 
 impl From<webp::WebPEncodingError> for ImageConversionError {
@@ -1,5 +1,9 @@
 // WebPEncodingErrorWrapper is marked by clippy as unused import but it is used in the tests
 #[allow(unused_imports)]
 pub use image_conversion_error::{ImageConversionError, WebPEncodingErrorWrapper};
+#[cfg(not(feature = "debug-mock"))]
+pub use upload_error::UploadError;
 
 mod image_conversion_error;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_error;
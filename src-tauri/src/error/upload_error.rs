@@ -0,0 +1,59 @@
+use crate::model::BatchValidationReport;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+/// Outcome of an upload that did not finish. Serialized as `{ kind, message }` so the
+/// frontend can tell a cancelled upload apart from a failed one. Invalid uploads also carry
+/// the validation `report`.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum UploadError {
+	#[error("Upload was cancelled")]
+	Cancelled,
+	#[error("{0}")]
+	Failed(String),
+	#[error("{}", invalid_message(.0))]
+	Invalid(BatchValidationReport),
+}
+
+fn invalid_message(report: &BatchValidationReport) -> String {
+	match report.problems.as_slice() {
+		[] => "The upload failed validation".to_string(),
+		[problem] => problem.message.clone(),
+		[problem, rest @ ..] => format!("{} (and {} more problems)", problem.message, rest.len()),
+	}
+}
+
+impl UploadError {
+	pub fn kind(&self) -> &'static str {
+		match self {
+			UploadError::Cancelled => "cancelled",
+			UploadError::Failed(_) => "failed",
+			UploadError::Invalid(_) => "invalid",
+		}
+	}
+}
+
+impl From<String> for UploadError {
+	fn from(message: String) -> Self {
+		UploadError::Failed(message)
+	}
+}
+
+impl From<&str> for UploadError {
+	fn from(message: &str) -> Self {
+		UploadError::Failed(message.to_string())
+	}
+}
+
+impl Serialize for UploadError {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut state = serializer.serialize_struct("UploadError", 3)?;
+		state.serialize_field("kind", self.kind())?;
+		state.serialize_field("message", &self.to_string())?;
+		if let UploadError::Invalid(report) = self {
+			state.serialize_field("report", report)?;
+		}
+		state.end()
+	}
+}
@@ -1,9 +1,18 @@
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use tauri_plugin_dialog::DialogExt;
 
+use crate::derivative_cache;
+
 pub(crate) fn directory_picker<R: tauri::Runtime, P: AsRef<Path>>(
 	start_path: P,
 	app_handle: tauri::AppHandle<R>,
@@ -35,6 +44,19 @@ pub(crate) fn get_file_paths_in_directory(directory_path: &str) -> Result<Vec<Pa
 	Ok(file_paths)
 }
 
+const IMAGE_EXTENSIONS: &[&str] = &["tif", "tiff", "jpg", "jpeg", "png", "webp"];
+
+/// Whether the path has one of the image extensions `list_image_files` accepts
+pub fn has_image_extension(path: &Path) -> bool {
+	path.extension()
+		.and_then(|e| e.to_str())
+		.is_some_and(|ext| {
+			IMAGE_EXTENSIONS
+				.iter()
+				.any(|&x| x.eq_ignore_ascii_case(ext))
+		})
+}
+
 /// Lists image files in a directory
 /// Supports: tif, tiff, jpg, jpeg, png, webp
 ///
@@ -45,8 +67,6 @@ pub fn list_image_files<P: AsRef<Path>>(
 	directory_path: P,
 	recursive: bool,
 ) -> Result<Vec<PathBuf>, std::io::Error> {
-	const IMAGE_EXTENSIONS: &[&str] = &["tif", "tiff", "jpg", "jpeg", "png", "webp"];
-
 	let mut files = Vec::new();
 
 	fn visit_dirs(
@@ -61,12 +81,7 @@ pub fn list_image_files<P: AsRef<Path>>(
 				if recursive {
 					visit_dirs(&path, recursive, files)?;
 				}
-			} else if path.is_file()
-				&& let Some(ext) = path.extension().and_then(|e| e.to_str())
-				&& IMAGE_EXTENSIONS
-					.iter()
-					.any(|&x| x.eq_ignore_ascii_case(ext))
-			{
+			} else if path.is_file() && has_image_extension(&path) {
 				files.push(path);
 			}
 		}
@@ -78,10 +93,191 @@ pub fn list_image_files<P: AsRef<Path>>(
 	Ok(files)
 }
 
-pub fn directory_exists<P: AsRef<Path>>(path: P) -> bool {
-	fs::metadata(path)
-		.map(|metadata| metadata.is_dir())
-		.unwrap_or(false)
+/// How `unfinished_files` decides whether whoever writes a file is done with it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileStability {
+	/// How long size and modification time must stay the same
+	pub interval: Duration,
+	/// Whether the header of an image must also be readable
+	pub parse_header: bool,
+}
+
+const DEFAULT_FILE_STABILITY: FileStability = FileStability {
+	interval: Duration::from_secs(2),
+	parse_header: true,
+};
+const MAX_STABILITY_INTERVAL: Duration = Duration::from_secs(60);
+/// How often `wait_until_finished` checks files again
+const FINISHED_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static FILE_STABILITY: Lazy<Mutex<FileStability>> =
+	Lazy::new(|| Mutex::new(DEFAULT_FILE_STABILITY));
+
+pub fn set_file_stability(interval_ms: u64, parse_header: bool) -> Result<(), String> {
+	let interval = Duration::from_millis(interval_ms);
+	if interval > MAX_STABILITY_INTERVAL {
+		return Err(format!(
+			"Invalid stability interval. Must be at most {} ms.",
+			MAX_STABILITY_INTERVAL.as_millis()
+		));
+	}
+	let mut stability = FILE_STABILITY.lock().map_err(|e| e.to_string())?;
+	*stability = FileStability {
+		interval,
+		parse_header,
+	};
+	Ok(())
+}
+
+pub fn get_file_stability() -> FileStability {
+	FILE_STABILITY
+		.lock()
+		.map(|stability| *stability)
+		.unwrap_or(DEFAULT_FILE_STABILITY)
+}
+
+#[derive(Debug, PartialEq)]
+struct FileSnapshot {
+	size: u64,
+	modified: Option<SystemTime>,
+}
+
+impl FileSnapshot {
+	fn read(path: &Path) -> io::Result<Self> {
+		let metadata = fs::metadata(path)?;
+		Ok(Self {
+			size: metadata.len(),
+			modified: metadata.modified().ok(),
+		})
+	}
+
+	/// How much longer the file must stay unchanged to have been unchanged for `interval`.
+	/// Files modified in the future, or without a modification time, get the whole interval.
+	fn remaining_wait(&self, interval: Duration, now: SystemTime) -> Duration {
+		let age = self
+			.modified
+			.and_then(|modified| now.duration_since(modified).ok())
+			.unwrap_or_default();
+		interval.saturating_sub(age)
+	}
+}
+
+/// Finds the files that whoever writes them is not done with, and why: files whose size or
+/// modification time changed within the stability interval, and, if `parse_header` is set,
+/// images whose header cannot be read. All files are checked during the same wait, which is
+/// skipped when no file was modified within the interval.
+pub fn unfinished_files<P: AsRef<Path>>(
+	files: &[P],
+	stability: &FileStability,
+) -> Vec<(PathBuf, String)> {
+	let now = SystemTime::now();
+	let before: Vec<_> = files
+		.iter()
+		.map(|file| FileSnapshot::read(file.as_ref()))
+		.collect();
+	let wait = before
+		.iter()
+		.flatten()
+		.map(|snapshot| snapshot.remaining_wait(stability.interval, now))
+		.max()
+		.unwrap_or_default();
+	if !wait.is_zero() {
+		thread::sleep(wait);
+	}
+
+	let mut unfinished = Vec::new();
+	for (file, before) in files.iter().map(AsRef::as_ref).zip(before) {
+		let reason = match (before, FileSnapshot::read(file)) {
+			(Err(e), _) | (_, Err(e)) => Some(format!("Failed to read {}: {e}", file.display())),
+			(Ok(before), Ok(after)) if before != after => {
+				Some(format!("{} is still being written", file.display()))
+			}
+			_ if stability.parse_header && has_image_extension(file) => read_image_header(file)
+				.err()
+				.map(|e| format!("Failed to read the image header of {}: {e}", file.display())),
+			_ => None,
+		};
+		if let Some(reason) = reason {
+			unfinished.push((file.to_path_buf(), reason));
+		}
+	}
+	unfinished
+}
+
+fn read_image_header(path: &Path) -> Result<(), image::ImageError> {
+	image::ImageReader::open(path)?
+		.with_guessed_format()?
+		.into_dimensions()
+		.map(|_| ())
+}
+
+/// Waits for whoever writes the files to be done with them, for at most `timeout`. Returns the
+/// finished files, sorted. Files removed while waiting are left out, as are files still
+/// unfinished when the wait times out.
+pub fn wait_until_finished(
+	files: Vec<PathBuf>,
+	stability: &FileStability,
+	timeout: Duration,
+) -> Vec<PathBuf> {
+	let started = Instant::now();
+	let mut pending = files;
+	let mut finished = Vec::new();
+	loop {
+		pending.retain(|file| file.is_file());
+		let unfinished: HashSet<PathBuf> = unfinished_files(&pending, stability)
+			.into_iter()
+			.map(|(file, _)| file)
+			.collect();
+		let (done, still_pending) = pending
+			.into_iter()
+			.partition::<Vec<_>, _>(|file| !unfinished.contains(file));
+		finished.extend(done);
+		pending = still_pending;
+		if pending.is_empty() || started.elapsed() >= timeout {
+			break;
+		}
+		thread::sleep(FINISHED_POLL_INTERVAL);
+	}
+	finished.sort();
+	finished
+}
+
+/// Suffix of the temporary files `write_atomically` writes before renaming them into place
+pub const TEMPORARY_FILE_SUFFIX: &str = ".tmp";
+
+static TEMPORARY_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `contents` to a hidden temporary file next to `path` and renames it into place, so
+/// anyone watching the folder never sees a half-written file. Creates the parent folder if needed.
+pub fn write_atomically<P: AsRef<Path>>(path: P, contents: &[u8]) -> io::Result<()> {
+	let path = path.as_ref();
+	let directory = get_parent_directory(path).map_err(io::Error::other)?;
+	let file_name = get_file_name(path).map_err(io::Error::other)?;
+	fs::create_dir_all(directory)?;
+
+	let temporary_path = directory.join(format!(
+		".{}.{}-{}{TEMPORARY_FILE_SUFFIX}",
+		file_name.to_string_lossy(),
+		std::process::id(),
+		TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+	));
+	let result = fs::File::create(&temporary_path)
+		.and_then(|mut file| {
+			file.write_all(contents)?;
+			file.sync_all()
+		})
+		.and_then(|_| fs::rename(&temporary_path, path));
+	if result.is_err() {
+		let _ = fs::remove_file(&temporary_path);
+	}
+	result
+}
+
+/// Whether the path is a temporary file left by `write_atomically`
+pub fn is_temporary_file(path: &Path) -> bool {
+	path.file_name()
+		.and_then(|n| n.to_str())
+		.is_some_and(|name| name.starts_with('.') && name.ends_with(TEMPORARY_FILE_SUFFIX))
 }
 
 pub fn get_parent_directory(path_reference: &Path) -> Result<&Path, String> {
@@ -99,8 +295,10 @@ pub fn get_file_name(path_reference: &Path) -> Result<&OsStr, String> {
 		.ok_or_else(|| format!("Failed to get file name for: {:?}", path_reference.to_str()))
 }
 
-/// Deletes all .previews folders recursively in a directory
-/// Returns the number of preview folders deleted
+/// Deletes the thumbnails and previews of every image in a directory and its subdirectories:
+/// the .previews and .thumbnails folders next to the scans, and the images' derivatives in the
+/// central cache if it is used.
+/// Returns the number of folders and cached derivatives deleted
 pub fn delete_all_previews_and_thumbnails<P: AsRef<Path>>(
 	directory_path: P,
 ) -> Result<u32, std::io::Error> {
@@ -108,7 +306,8 @@ pub fn delete_all_previews_and_thumbnails<P: AsRef<Path>>(
 	const THUMBNAIL_FOLDER_NAME: &str = ".thumbnails";
 
 	let path_reference = directory_path.as_ref();
-	let mut deleted_count = 0;
+	let image_files = list_image_files(path_reference, true)?;
+	let mut deleted_count = derivative_cache::remove_central_derivatives(&image_files)?;
 
 	// Recursively walk through all directories
 	fn walk_and_delete(dir: &Path, count: &mut u32) -> Result<(), std::io::Error> {
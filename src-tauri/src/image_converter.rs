@@ -1,48 +1,283 @@
-use image::ImageReader;
+use image::imageops::FilterType;
 use image::metadata::Orientation;
+use image::{DynamicImage, ImageReader};
 use little_exif::exif_tag::ExifTag;
 use little_exif::metadata::Metadata;
 use sentry::{Breadcrumb, Level, add_breadcrumb, capture_message};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::Duration;
 use std::{fs, thread};
 use webp::Encoder;
 
+use crate::derivative_cache::{self, DerivativeStamp, PREVIEW_FOLDER_NAME, THUMBNAIL_FOLDER_NAME};
 use crate::error::ImageConversionError;
 use crate::file_utils;
+use crate::model::{ConversionProgress, DerivativeReady};
+use crate::orientation_sidecar;
+use crate::page_analysis::{self, PageAnalysis};
 use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 
-const THUMBNAIL_FOLDER_NAME: &str = ".thumbnails";
-const PREVIEW_FOLDER_NAME: &str = ".previews";
-const WEBP_EXTENSION: &str = "webp";
-const WEBP_QUALITY: f32 = 25.0;
+const DEFAULT_THUMBNAIL_QUALITY: f32 = 25.0;
+const DEFAULT_PREVIEW_QUALITY: f32 = 75.0;
+const MIN_WEBP_QUALITY: f32 = 0.0;
+const MAX_WEBP_QUALITY: f32 = 100.0;
 const DEFAULT_THUMBNAIL_FRACTION: u32 = 8;
 const DEFAULT_PREVIEW_FRACTION: u32 = 4;
 const MIN_SIZE_FRACTION: u32 = 1;
 const MAX_SIZE_FRACTION: u32 = 16;
+const MIN_TARGET_PIXELS: u32 = 16;
+const MAX_TARGET_PIXELS: u32 = 16384;
+/// Upper bound on conversion threads, since every thread holds a full decoded image in memory
+const MAX_CONVERSION_THREADS: usize = 8;
+
+/// Resampling filter used when scaling an image down, from fastest to sharpest
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResizeFilter {
+	Nearest,
+	Triangle,
+	CatmullRom,
+	Gaussian,
+	Lanczos3,
+}
+
+impl ResizeFilter {
+	pub fn parse(name: &str) -> Result<Self, String> {
+		match name {
+			"nearest" => Ok(ResizeFilter::Nearest),
+			"triangle" => Ok(ResizeFilter::Triangle),
+			"catmullRom" => Ok(ResizeFilter::CatmullRom),
+			"gaussian" => Ok(ResizeFilter::Gaussian),
+			"lanczos3" => Ok(ResizeFilter::Lanczos3),
+			_ => Err(format!(
+				"Invalid filter '{name}'. Use 'nearest', 'triangle', 'catmullRom', 'gaussian' or 'lanczos3'"
+			)),
+		}
+	}
+
+	fn filter_type(&self) -> FilterType {
+		match self {
+			ResizeFilter::Nearest => FilterType::Nearest,
+			ResizeFilter::Triangle => FilterType::Triangle,
+			ResizeFilter::CatmullRom => FilterType::CatmullRom,
+			ResizeFilter::Gaussian => FilterType::Gaussian,
+			ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+		}
+	}
+}
+
+/// How large a thumbnail or preview is. Images are never scaled up.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum TargetSize {
+	/// The original size divided by `fraction`
+	Fraction { fraction: u32 },
+	/// The longest edge is at most `pixels`, so derivatives are uniform across scanners
+	LongEdge { pixels: u32 },
+	/// Fits within `width` x `height`, keeping the aspect ratio
+	BoundingBox { width: u32, height: u32 },
+}
+
+impl TargetSize {
+	fn validate(&self) -> Result<(), String> {
+		let pixels_valid = |pixels: u32| (MIN_TARGET_PIXELS..=MAX_TARGET_PIXELS).contains(&pixels);
+		match *self {
+			TargetSize::Fraction { fraction } => validate_size_fraction(fraction),
+			TargetSize::LongEdge { pixels } if !pixels_valid(pixels) => Err(format!(
+				"Invalid long edge. Must be between {MIN_TARGET_PIXELS} and {MAX_TARGET_PIXELS} pixels."
+			)),
+			TargetSize::BoundingBox { width, height }
+				if !pixels_valid(width) || !pixels_valid(height) =>
+			{
+				Err(format!(
+					"Invalid bounding box. Width and height must be between {MIN_TARGET_PIXELS} and {MAX_TARGET_PIXELS} pixels."
+				))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Replaces the fraction of a `Fraction` size, leaving the other modes as they are
+	pub(crate) fn with_fraction(self, fraction: u32) -> Self {
+		match self {
+			TargetSize::Fraction { .. } => TargetSize::Fraction { fraction },
+			size => size,
+		}
+	}
+
+	/// Size of the derivative of a `width` x `height` image, at least 1 x 1
+	pub(crate) fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+		let (max_width, max_height) = match *self {
+			TargetSize::Fraction { fraction } => {
+				let fraction = fraction.max(1);
+				return ((width / fraction).max(1), (height / fraction).max(1));
+			}
+			TargetSize::LongEdge { pixels } => (pixels, pixels),
+			TargetSize::BoundingBox { width, height } => (width, height),
+		};
+		let scale = (max_width as f64 / width.max(1) as f64)
+			.min(max_height as f64 / height.max(1) as f64)
+			.min(1.0);
+		(
+			((width as f64 * scale).round() as u32).max(1),
+			((height as f64 * scale).round() as u32).max(1),
+		)
+	}
+}
+
+/// How a thumbnail or preview is scaled and encoded
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionProfile {
+	pub(crate) filter: ResizeFilter,
+	/// WebP quality from 0 to 100, ignored if `lossless`
+	pub(crate) quality: f32,
+	pub(crate) lossless: bool,
+	pub(crate) size: TargetSize,
+}
+
+impl ConversionProfile {
+	pub fn new(
+		filter: &str,
+		quality: f32,
+		lossless: bool,
+		size: TargetSize,
+	) -> Result<Self, String> {
+		if !(MIN_WEBP_QUALITY..=MAX_WEBP_QUALITY).contains(&quality) {
+			return Err(format!(
+				"Invalid quality. Must be between {MIN_WEBP_QUALITY} and {MAX_WEBP_QUALITY}."
+			));
+		}
+		size.validate()?;
+		Ok(Self {
+			filter: ResizeFilter::parse(filter)?,
+			quality,
+			lossless,
+			size,
+		})
+	}
+
+	/// Identifies the settings in a derivative's stamp, so changing them regenerates it
+	pub(crate) fn fingerprint(&self) -> String {
+		serde_json::to_string(self).unwrap_or_default()
+	}
+}
 
 #[derive(Debug, Clone, Copy)]
-struct ImageSizeFractions {
-	thumbnail_fraction: u32,
-	preview_fraction: u32,
+struct ConversionProfiles {
+	thumbnail: ConversionProfile,
+	preview: ConversionProfile,
 }
 
-static IMAGE_SIZE_FRACTIONS: Lazy<Mutex<ImageSizeFractions>> = Lazy::new(|| {
-	Mutex::new(ImageSizeFractions {
-		thumbnail_fraction: DEFAULT_THUMBNAIL_FRACTION,
-		preview_fraction: DEFAULT_PREVIEW_FRACTION,
-	})
-});
+impl Default for ConversionProfiles {
+	fn default() -> Self {
+		Self {
+			// Thumbnails are only used for the grid, so they favour speed
+			thumbnail: ConversionProfile {
+				filter: ResizeFilter::Nearest,
+				quality: DEFAULT_THUMBNAIL_QUALITY,
+				lossless: false,
+				size: TargetSize::Fraction {
+					fraction: DEFAULT_THUMBNAIL_FRACTION,
+				},
+			},
+			// Previews are used to check the scan, so fine print must stay readable
+			preview: ConversionProfile {
+				filter: ResizeFilter::CatmullRom,
+				quality: DEFAULT_PREVIEW_QUALITY,
+				lossless: false,
+				size: TargetSize::Fraction {
+					fraction: DEFAULT_PREVIEW_FRACTION,
+				},
+			},
+		}
+	}
+}
+
+static CONVERSION_PROFILES: Lazy<Mutex<ConversionProfiles>> =
+	Lazy::new(|| Mutex::new(ConversionProfiles::default()));
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConversionCount {
 	pub(crate) converted: u32,
 	pub(crate) already_converted: u32,
+	pub(crate) failed: u32,
+	/// Result for every image, in the order the images are listed
+	pub(crate) files: Vec<FileConversionResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConversionOutcome {
+	Converted,
+	AlreadyConverted,
+	Failed,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileConversionResult {
+	pub(crate) path: String,
+	pub(crate) outcome: ConversionOutcome,
+	/// `ImageConversionError::kind` if the conversion failed
+	pub(crate) error_kind: Option<String>,
+	pub(crate) error_message: Option<String>,
+}
+
+impl FileConversionResult {
+	fn new(path: &Path, result: Result<bool, ImageConversionError>) -> Self {
+		let path = path.to_string_lossy().to_string();
+		match result {
+			Ok(true) => Self {
+				path,
+				outcome: ConversionOutcome::Converted,
+				error_kind: None,
+				error_message: None,
+			},
+			Ok(false) => Self {
+				path,
+				outcome: ConversionOutcome::AlreadyConverted,
+				error_kind: None,
+				error_message: None,
+			},
+			Err(e) => Self {
+				path,
+				outcome: ConversionOutcome::Failed,
+				error_kind: Some(e.kind().to_string()),
+				error_message: Some(e.to_string()),
+			},
+		}
+	}
+}
+
+impl ConversionCount {
+	pub(crate) fn from_results(files: Vec<FileConversionResult>) -> Self {
+		let count = |outcome| files.iter().filter(|f| f.outcome == outcome).count() as u32;
+		Self {
+			converted: count(ConversionOutcome::Converted),
+			already_converted: count(ConversionOutcome::AlreadyConverted),
+			failed: count(ConversionOutcome::Failed),
+			files,
+		}
+	}
+}
+
+fn validate_size_fraction(fraction: u32) -> Result<(), String> {
+	if !(MIN_SIZE_FRACTION..=MAX_SIZE_FRACTION).contains(&fraction) {
+		return Err(format!(
+			"Invalid size fraction. Must be between {} and {}.",
+			MIN_SIZE_FRACTION, MAX_SIZE_FRACTION
+		));
+	}
+	Ok(())
+}
+
+/// Sets the fractions of profiles sized by fraction. Profiles sized by pixels keep their size, so
+/// this older command cannot undo a size mode chosen with `set_conversion_profile`.
 pub fn set_image_size_fractions(
 	thumbnail_fraction: u32,
 	preview_fraction: u32,
@@ -55,35 +290,65 @@ pub fn set_image_size_fractions(
 		));
 	}
 
-	let mut fractions = IMAGE_SIZE_FRACTIONS.lock().map_err(|e| e.to_string())?;
-	fractions.thumbnail_fraction = thumbnail_fraction;
-	fractions.preview_fraction = preview_fraction;
+	let mut profiles = CONVERSION_PROFILES.lock().map_err(|e| e.to_string())?;
+	profiles.thumbnail.size = profiles.thumbnail.size.with_fraction(thumbnail_fraction);
+	profiles.preview.size = profiles.preview.size.with_fraction(preview_fraction);
 	Ok(())
 }
 
-fn get_fraction(high_res: bool) -> u32 {
-	let fractions = IMAGE_SIZE_FRACTIONS
-		.lock()
-		.map(|f| *f)
-		.unwrap_or(ImageSizeFractions {
-			thumbnail_fraction: DEFAULT_THUMBNAIL_FRACTION,
-			preview_fraction: DEFAULT_PREVIEW_FRACTION,
-		});
+/// Sets the profile used for thumbnails (`target` "thumbnail") or previews ("preview")
+pub fn set_conversion_profile(target: &str, profile: ConversionProfile) -> Result<(), String> {
+	let mut profiles = CONVERSION_PROFILES.lock().map_err(|e| e.to_string())?;
+	match target {
+		"thumbnail" => profiles.thumbnail = profile,
+		"preview" => profiles.preview = profile,
+		_ => {
+			return Err(format!(
+				"Invalid target '{target}'. Use 'thumbnail' or 'preview'"
+			));
+		}
+	}
+	Ok(())
+}
+
+pub(crate) fn get_conversion_profile(high_res: bool) -> ConversionProfile {
+	let profiles = CONVERSION_PROFILES.lock().map(|p| *p).unwrap_or_default();
 	if high_res {
-		fractions.preview_fraction
+		profiles.preview
 	} else {
-		fractions.thumbnail_fraction
+		profiles.thumbnail
 	}
 }
 
+/// Thread pool shared by all directory conversions, so that converting several directories at
+/// once does not start more threads than there are cores
+static CONVERSION_POOL: Lazy<Result<ThreadPool, String>> = Lazy::new(|| {
+	let threads = thread::available_parallelism()
+		.map(|n| n.get())
+		.unwrap_or(1)
+		.min(MAX_CONVERSION_THREADS);
+	ThreadPoolBuilder::new()
+		.num_threads(threads)
+		.thread_name(|index| format!("image-conversion-{index}"))
+		.build()
+		.map_err(|e| e.to_string())
+});
+
+pub(crate) fn conversion_pool() -> Result<&'static ThreadPool, ImageConversionError> {
+	CONVERSION_POOL
+		.as_ref()
+		.map_err(|e| ImageConversionError::StrError(format!("Failed to start thread pool: {e}")))
+}
+
+/// Creates missing thumbnails for the images in the directory. An image that fails to convert
+/// is reported in the result and does not stop the others. `on_progress` is called after every
+/// image, from the thread that converted it.
 pub fn convert_directory_to_webp<P: AsRef<Path>>(
 	directory_path: P,
+	on_progress: impl Fn(ConversionProgress) + Sync,
 ) -> Result<ConversionCount, ImageConversionError> {
+	let directory = directory_path.as_ref().to_string_lossy().to_string();
 	let files = file_utils::list_image_files(directory_path, false)?;
-	let mut count = ConversionCount {
-		converted: 0,
-		already_converted: 0,
-	};
 
 	add_breadcrumb(Breadcrumb {
 		category: Some("convert_directory".into()),
@@ -95,20 +360,47 @@ pub fn convert_directory_to_webp<P: AsRef<Path>>(
 		..Default::default()
 	});
 
-	for file in files {
-		if check_if_thumbnail_exists(&file)? {
-			count.already_converted += 1;
-		} else {
-			convert_to_webp(&file, false)?;
-			count.converted += 1;
-		}
-	}
+	let progress = Mutex::new((0, 0));
+	let results = conversion_pool()?.install(|| {
+		files
+			.par_iter()
+			.map(|file| {
+				let result = match check_if_thumbnail_exists(file) {
+					Ok(true) => Ok(false),
+					Ok(false) => convert_to_webp(file, false).map(|_| true),
+					Err(e) => Err(e),
+				};
+				let result = FileConversionResult::new(file, result);
+
+				// Reported under the lock, so `done` never goes backwards in the frontend
+				if let Ok(mut progress) = progress.lock() {
+					let (done, failures) = &mut *progress;
+					*done += 1;
+					*failures += usize::from(result.outcome == ConversionOutcome::Failed);
+					on_progress(ConversionProgress {
+						directory: directory.clone(),
+						current_file: file
+							.file_name()
+							.map(|name| name.to_string_lossy().to_string())
+							.unwrap_or_default(),
+						done: *done,
+						total: files.len(),
+						failures: *failures,
+					});
+				}
+				result
+			})
+			.collect::<Vec<_>>()
+	});
+	let count = ConversionCount::from_results(results);
+	report_failures(&count.files);
+	derivative_cache::evict_if_over_limit();
 
 	add_breadcrumb(Breadcrumb {
 		category: Some("convert_directory".into()),
 		message: Some(format!(
-			"Done converting images to thumbnails. Converted: {}, already_converted: {}",
-			count.converted, count.already_converted
+			"Done converting images to thumbnails. Converted: {}, already_converted: {}, failed: {}",
+			count.converted, count.already_converted, count.failed
 		)),
 		level: Level::Info,
 		..Default::default()
@@ -119,20 +411,117 @@ pub fn convert_directory_to_webp<P: AsRef<Path>>(
 	Ok(count)
 }
 
+/// Creates missing thumbnails and previews for every image in the directory and its
+/// subdirectories. An image that fails to convert is reported in the result and does not stop
+/// the others.
+pub fn ensure_all_previews_and_thumbnails<P: AsRef<Path>>(
+	directory_path: P,
+) -> Result<ConversionCount, ImageConversionError> {
+	let image_files = file_utils::list_image_files(directory_path, true)?;
+
+	add_breadcrumb(Breadcrumb {
+		category: Some("ensure_files".into()),
+		message: Some(format!(
+			"Converting images to thumbnails and previews. Total files: {}",
+			image_files.len()
+		)),
+		level: Level::Info,
+		..Default::default()
+	});
+
+	let results = conversion_pool()?.install(|| {
+		image_files
+			.par_iter()
+			.map(|file_path| {
+				let thumbnail = !check_if_thumbnail_exists(file_path).unwrap_or(false);
+				let preview = !check_if_preview_exists(file_path).unwrap_or(false);
+				let result = convert_to_webp_derivatives(file_path, thumbnail, preview)
+					.map(|_| thumbnail || preview);
+				FileConversionResult::new(file_path, result)
+			})
+			.collect::<Vec<_>>()
+	});
+	let count = ConversionCount::from_results(results);
+	report_failures(&count.files);
+	derivative_cache::evict_if_over_limit();
+
+	capture_message(
+		"Finished converting images to thumbnails and previews",
+		Level::Info,
+	);
+
+	Ok(count)
+}
+
+/// Adds a breadcrumb for every image that could not be converted
+fn report_failures(files: &[FileConversionResult]) {
+	for file in files
+		.iter()
+		.filter(|f| f.outcome == ConversionOutcome::Failed)
+	{
+		add_breadcrumb(Breadcrumb {
+			category: Some("convert_image".into()),
+			message: Some(format!(
+				"Failed to convert {}: {}",
+				file.path,
+				file.error_message.as_deref().unwrap_or_default()
+			)),
+			level: Level::Error,
+			..Default::default()
+		});
+	}
+}
+
 pub fn convert_to_webp<P: AsRef<Path>>(
 	image_path: P,
 	high_res: bool,
 ) -> Result<PathBuf, ImageConversionError> {
 	let path_reference = image_path.as_ref();
+	let paths = convert_to_webp_derivatives(path_reference, !high_res, high_res)?;
+	Ok(paths
+		.into_iter()
+		.next()
+		.unwrap_or_else(|| path_reference.to_path_buf()))
+}
+
+/// Writes the requested thumbnail and/or preview of the image, unless the image is still being
+/// written. Returns the paths of the written files, thumbnail first.
+pub fn convert_to_webp_derivatives<P: AsRef<Path>>(
+	image_path: P,
+	thumbnail: bool,
+	preview: bool,
+) -> Result<Vec<PathBuf>, ImageConversionError> {
+	let path_reference = image_path.as_ref();
 
 	//Skip conversion if path contains .thumbnails or .previews
 	if path_reference
 		.components()
 		.any(|c| c.as_os_str() == PREVIEW_FOLDER_NAME || c.as_os_str() == THUMBNAIL_FOLDER_NAME)
 	{
-		return Ok(path_reference.to_path_buf());
+		return Ok(vec![path_reference.to_path_buf()]);
 	}
+	if !thumbnail && !preview {
+		return Ok(Vec::new());
+	}
+
+	// The header is not parsed here, decoding the image reports a broken file more precisely
+	let stability = file_utils::FileStability {
+		parse_header: false,
+		..file_utils::get_file_stability()
+	};
+	if let Some((_, reason)) = file_utils::unfinished_files(&[path_reference], &stability).pop() {
+		return Err(ImageConversionError::UnfinishedFile(reason));
+	}
+
+	write_derivatives(path_reference, thumbnail, preview)
+}
 
+/// Decodes the image once and writes the requested derivatives of it
+fn write_derivatives(
+	path_reference: &Path,
+	thumbnail: bool,
+	preview: bool,
+) -> Result<Vec<PathBuf>, ImageConversionError> {
 	add_breadcrumb(Breadcrumb {
 		category: Some("convert_image".into()),
 		message: Some("Converting image to WEBP".into()),
@@ -140,9 +529,42 @@ pub fn convert_to_webp<P: AsRef<Path>>(
 		..Default::default()
 	});
 
+	// Read before decoding, so a page overwritten during conversion is converted again later
+	let source = fs::metadata(path_reference)?;
+	let image = decode_oriented(path_reference)?;
+	// Measured while the full image is decoded anyway, and stored with the thumbnail
+	let analysis = thumbnail.then(|| page_analysis::analyze(&image));
+
+	let mut paths = Vec::with_capacity(2);
+	for (wanted, high_res) in [(thumbnail, false), (preview, true)] {
+		if wanted {
+			let analysis = analysis.filter(|_| !high_res);
+			paths.push(write_webp(
+				&image,
+				path_reference,
+				&source,
+				high_res,
+				analysis,
+			)?);
+		}
+	}
+
+	// Functions that invoke this function must capture its breadcrumbs
+	add_breadcrumb(Breadcrumb {
+		category: Some("convert_image".into()),
+		message: Some("Finished encoding and saving image".into()),
+		level: Level::Info,
+		..Default::default()
+	});
+
+	Ok(paths)
+}
+
+/// Decodes the image and applies its EXIF orientation
+fn decode_oriented(path_reference: &Path) -> Result<DynamicImage, ImageConversionError> {
 	// Load image
 	let reader = ImageReader::open(path_reference)?.with_guessed_format()?;
-	let mut image: image::DynamicImage = reader.decode()?;
+	let mut image: DynamicImage = reader.decode()?;
 
 	add_breadcrumb(Breadcrumb {
 		category: Some("convert_image".into()),
@@ -154,37 +576,41 @@ pub fn convert_to_webp<P: AsRef<Path>>(
 		..Default::default()
 	});
 
-	// Apply EXIF Orientation (if missing/invalid, do nothing)
-	let orientation = Metadata::new_from_path(path_reference)
-		.ok()
-		.and_then(|m| {
-			m.get_tag(&ExifTag::Orientation(vec![]))
-				.next()
-				.and_then(|t| match t {
-					ExifTag::Orientation(v) => v.first().copied(),
-					_ => None,
-				})
+	image.apply_orientation(read_orientation(path_reference));
+	Ok(image)
+}
+
+/// Orientation recorded in the image's sidecar, or else in its EXIF metadata. Missing or invalid
+/// orientations mean no transform.
+pub(crate) fn read_orientation(path_reference: &Path) -> Orientation {
+	let orientation = orientation_sidecar::read_orientation(path_reference)
+		.or_else(|| {
+			Metadata::new_from_path(path_reference).ok().and_then(|m| {
+				m.get_tag(&ExifTag::Orientation(vec![]))
+					.next()
+					.and_then(|t| match t {
+						ExifTag::Orientation(v) => v.first().map(|o| *o as u8),
+						_ => None,
+					})
+			})
 		})
 		.unwrap_or(1);
 
-	let orientation =
-		Orientation::from_exif(orientation as u8).unwrap_or(Orientation::NoTransforms);
+	Orientation::from_exif(orientation).unwrap_or(Orientation::NoTransforms)
+}
 
-	image = match orientation {
-		Orientation::NoTransforms => image,
-		Orientation::Rotate90 => image.rotate90(),
-		Orientation::Rotate180 => image.rotate180(),
-		Orientation::Rotate270 => image.rotate270(),
-		_ => image,
-	};
-	let fraction = get_fraction(high_res);
-	let resized_width = (image.width() / fraction).max(1);
-	let resized_height = (image.height() / fraction).max(1);
-	let image = image.resize(
-		resized_width,
-		resized_height,
-		image::imageops::FilterType::Nearest,
-	);
+/// Resizes the decoded image and saves it as a thumbnail, or a preview if `high_res`, stamped
+/// with the `source` metadata read before the image was decoded
+fn write_webp(
+	image: &DynamicImage,
+	path_reference: &Path,
+	source: &fs::Metadata,
+	high_res: bool,
+	analysis: Option<PageAnalysis>,
+) -> Result<PathBuf, ImageConversionError> {
+	let profile = get_conversion_profile(high_res);
+	let (resized_width, resized_height) = profile.size.dimensions(image.width(), image.height());
+	let image = image.resize(resized_width, resized_height, profile.filter.filter_type());
 
 	add_breadcrumb(Breadcrumb {
 		category: Some("convert_image".into()),
@@ -198,24 +624,10 @@ pub fn convert_to_webp<P: AsRef<Path>>(
 
 	let encoder: Encoder =
 		Encoder::from_image(&image).map_err(|e| ImageConversionError::StrError(e.to_string()))?;
-	let encoded_webp = encoder.encode_simple(false, WEBP_QUALITY)?;
-
-	let parent_directory =
-		file_utils::get_parent_directory(path_reference).map_err(ImageConversionError::StrError)?;
-	let filename_original_image =
-		file_utils::get_file_name(path_reference).map_err(ImageConversionError::StrError)?;
+	let encoded_webp = encoder.encode_simple(profile.lossless, profile.quality)?;
 
-	let mut path = parent_directory.to_owned();
-	path.push(if high_res {
-		PREVIEW_FOLDER_NAME
-	} else {
-		THUMBNAIL_FOLDER_NAME
-	});
-
-	if !file_utils::directory_exists(&path) {
-		fs::create_dir_all(&path)?;
-		thread::sleep(Duration::from_millis(500)); // Sleep here a bit so the file watcher can catch up
-	}
+	let path = derivative_cache::derivative_path(path_reference, high_res)
+		.map_err(ImageConversionError::StrError)?;
 
 	add_breadcrumb(Breadcrumb {
 		category: Some("convert_image".into()),
@@ -227,51 +639,72 @@ pub fn convert_to_webp<P: AsRef<Path>>(
 		..Default::default()
 	});
 
-	path.push(filename_original_image);
-	path.set_extension(WEBP_EXTENSION);
-	fs::write(&path, &*encoded_webp)?;
-
-	// Functions that invoke this function must capture its breadcrumbs
-	add_breadcrumb(Breadcrumb {
-		category: Some("convert_image".into()),
-		message: Some("Finished encoding and saving image".into()),
-		level: Level::Info,
-		..Default::default()
+	// Written to a temporary file and renamed into place, so watchers never load half a WebP
+	file_utils::write_atomically(&path, &encoded_webp)?;
+	let stamp = DerivativeStamp {
+		analysis,
+		..DerivativeStamp::new(source, profile.fingerprint())
+	};
+	derivative_cache::write_stamp(&path, &stamp)?;
+	notify_derivative_ready(DerivativeReady {
+		source: path_reference.to_path_buf(),
+		path: path.clone(),
+		high_res,
 	});
 
 	Ok(path)
 }
 
+pub(crate) type DerivativeListener = Box<dyn Fn(DerivativeReady) + Send + Sync>;
+
+static DERIVATIVE_LISTENER: Lazy<Mutex<Option<DerivativeListener>>> =
+	Lazy::new(|| Mutex::new(None));
+
+/// Registers the function told about every derivative once it is complete on disk, replacing
+/// any previously registered one. The app uses it to emit `derivative_ready` to the frontend.
+pub fn set_derivative_listener(listener: impl Fn(DerivativeReady) + Send + Sync + 'static) {
+	replace_derivative_listener(Some(Box::new(listener)));
+}
+
+/// Registers `listener`, or none, and returns the one registered before, so it can be restored
+pub(crate) fn replace_derivative_listener(
+	listener: Option<DerivativeListener>,
+) -> Option<DerivativeListener> {
+	std::mem::replace(&mut *DERIVATIVE_LISTENER.lock().unwrap(), listener)
+}
+
+fn notify_derivative_ready(ready: DerivativeReady) {
+	if let Some(listener) = DERIVATIVE_LISTENER.lock().unwrap().as_ref() {
+		listener(ready);
+	}
+}
+
+/// Whether the image has a thumbnail that is up to date with the image and the thumbnail profile
 pub fn check_if_thumbnail_exists<P: AsRef<Path>>(
 	image_path: P,
 ) -> Result<bool, ImageConversionError> {
-	let path_reference = image_path.as_ref();
-	let parent_directory =
-		file_utils::get_parent_directory(path_reference).map_err(ImageConversionError::StrError)?;
-	let filename_original_image =
-		file_utils::get_file_name(path_reference).map_err(ImageConversionError::StrError)?;
-
-	let mut path = parent_directory.to_owned();
-	path.push(THUMBNAIL_FOLDER_NAME);
-	path.push(filename_original_image);
-	path.set_extension(WEBP_EXTENSION);
-	Ok(path.exists())
+	check_if_derivative_is_current(image_path.as_ref(), false)
 }
 
+/// Whether the image has a preview that is up to date with the image and the preview profile
 pub fn check_if_preview_exists<P: AsRef<Path>>(
 	image_path: P,
 ) -> Result<bool, ImageConversionError> {
-	let path_reference = image_path.as_ref();
-	let parent_directory =
-		file_utils::get_parent_directory(path_reference).map_err(ImageConversionError::StrError)?;
-	let filename_original_image =
-		file_utils::get_file_name(path_reference).map_err(ImageConversionError::StrError)?;
+	check_if_derivative_is_current(image_path.as_ref(), true)
+}
 
-	let mut path = parent_directory.to_owned();
-	path.push(PREVIEW_FOLDER_NAME);
-	path.push(filename_original_image);
-	path.set_extension(WEBP_EXTENSION);
-	Ok(path.exists())
+fn check_if_derivative_is_current(
+	path_reference: &Path,
+	high_res: bool,
+) -> Result<bool, ImageConversionError> {
+	let derivative = derivative_cache::derivative_path(path_reference, high_res)
+		.map_err(ImageConversionError::StrError)?;
+	let profile = get_conversion_profile(high_res).fingerprint();
+	Ok(derivative_cache::is_current(
+		path_reference,
+		&derivative,
+		&profile,
+	)?)
 }
 
 /// Rotates an image in clockwise or counterclockwise direction
@@ -280,17 +713,83 @@ pub fn rotate_image<P: AsRef<Path>>(
 	image_path: P,
 	direction: &str,
 ) -> Result<(), ImageConversionError> {
-	let path_reference = image_path.as_ref();
+	let change = match direction.to_lowercase().as_str() {
+		"clockwise" => rotate_clockwise,
+		"counterclockwise" => rotate_counter_clockwise,
+		_ => {
+			return Err(ImageConversionError::StrError(format!(
+				"Invalid direction '{}'. Use 'clockwise' or 'counterclockwise'",
+				direction
+			)));
+		}
+	};
+	transform_image(image_path.as_ref(), change)
+}
 
-	rotate_image_by_exif(path_reference, direction)?;
+/// Mirrors an image horizontally or vertically, the same way `rotate_image` rotates it
+pub fn flip_image<P: AsRef<Path>>(
+	image_path: P,
+	direction: &str,
+) -> Result<(), ImageConversionError> {
+	let change = match direction.to_lowercase().as_str() {
+		"horizontal" => flip_horizontal,
+		"vertical" => flip_vertical,
+		_ => {
+			return Err(ImageConversionError::StrError(format!(
+				"Invalid direction '{}'. Use 'horizontal' or 'vertical'",
+				direction
+			)));
+		}
+	};
+	transform_image(image_path.as_ref(), change)
+}
+
+fn transform_image(
+	path_reference: &Path,
+	change: fn(Orientation) -> Orientation,
+) -> Result<(), ImageConversionError> {
+	transform_image_by_exif(path_reference, change)?;
 	rerender_webp_files(path_reference)?;
 
 	Ok(())
 }
 
-fn rotate_image_by_exif(
+/// Writes the new orientation to the image's EXIF metadata. If the format has no writable
+/// EXIF, or the image already has a sidecar, the orientation is recorded in the sidecar instead.
+fn transform_image_by_exif(
 	path_reference: &Path,
-	direction: &str,
+	change: fn(Orientation) -> Orientation,
+) -> Result<(), ImageConversionError> {
+	let new_orientation = change(read_orientation(path_reference)).to_exif();
+
+	let written_to_exif = orientation_sidecar::read_orientation(path_reference).is_none()
+		&& match write_exif_orientation(path_reference, new_orientation) {
+			Ok(()) => true,
+			Err(e) => {
+				add_breadcrumb(Breadcrumb {
+					category: Some("rotate_image".into()),
+					message: Some(format!("{e}. Recording the orientation in a sidecar")),
+					level: Level::Warning,
+					..Default::default()
+				});
+				false
+			}
+		};
+	if !written_to_exif {
+		orientation_sidecar::write_orientation(path_reference, new_orientation).map_err(|e| {
+			ImageConversionError::StrError(format!(
+				"Error writing orientation sidecar for {}: {}",
+				path_reference.display(),
+				e
+			))
+		})?;
+	}
+	Ok(())
+}
+
+fn write_exif_orientation(
+	path_reference: &Path,
+	orientation: u8,
 ) -> Result<(), ImageConversionError> {
 	let mut metadata = Metadata::new_from_path(path_reference).map_err(|e| {
 		ImageConversionError::StrError(format!(
@@ -299,28 +798,7 @@ fn rotate_image_by_exif(
 			e
 		))
 	})?;
-
-	let current_orientation_u16 = match metadata.get_tag(&ExifTag::Orientation(vec![])).next() {
-		Some(ExifTag::Orientation(v)) => v.first().copied().unwrap_or(1),
-		_ => 1,
-	};
-
-	let current_orientation =
-		Orientation::from_exif(current_orientation_u16 as u8).unwrap_or(Orientation::NoTransforms);
-
-	let new_orientation = match direction.to_lowercase().as_str() {
-		"clockwise" => rotate_clockwise(current_orientation),
-		"counterclockwise" => rotate_counter_clockwise(current_orientation),
-		_ => {
-			return Err(ImageConversionError::StrError(format!(
-				"Invalid direction '{}'. Use 'clockwise' or 'counterclockwise'",
-				direction
-			)));
-		}
-	};
-
-	let new_orientation_u16 = new_orientation.to_exif() as u16;
-	metadata.set_tag(ExifTag::Orientation(vec![new_orientation_u16]));
+	metadata.set_tag(ExifTag::Orientation(vec![orientation as u16]));
 
 	// Write back to file
 	metadata.write_to_file(path_reference).map_err(|e| {
@@ -329,29 +807,60 @@ fn rotate_image_by_exif(
 			path_reference.display(),
 			e
 		))
-	})?;
-	Ok(())
+	})
 }
 
-fn rotate_clockwise(current: Orientation) -> Orientation {
-	match current {
-		Orientation::NoTransforms => Orientation::Rotate90,
-		Orientation::Rotate90 => Orientation::Rotate180,
-		Orientation::Rotate180 => Orientation::Rotate270,
-		_ => Orientation::NoTransforms,
+/// Splits an orientation into a number of clockwise quarter turns followed by an optional
+/// horizontal flip, which makes composing orientations simple arithmetic
+fn to_turns_and_flip(orientation: Orientation) -> (u8, bool) {
+	match orientation {
+		Orientation::NoTransforms => (0, false),
+		Orientation::Rotate90 => (1, false),
+		Orientation::Rotate180 => (2, false),
+		Orientation::Rotate270 => (3, false),
+		Orientation::FlipHorizontal => (0, true),
+		Orientation::Rotate90FlipH => (1, true),
+		Orientation::FlipVertical => (2, true),
+		Orientation::Rotate270FlipH => (3, true),
 	}
 }
 
-fn rotate_counter_clockwise(current: Orientation) -> Orientation {
-	match current {
-		Orientation::NoTransforms => Orientation::Rotate270,
-		Orientation::Rotate270 => Orientation::Rotate180,
-		Orientation::Rotate180 => Orientation::Rotate90,
-		_ => Orientation::NoTransforms,
+fn from_turns_and_flip(turns: u8, flip: bool) -> Orientation {
+	match (turns % 4, flip) {
+		(0, false) => Orientation::NoTransforms,
+		(1, false) => Orientation::Rotate90,
+		(2, false) => Orientation::Rotate180,
+		(3, false) => Orientation::Rotate270,
+		(0, true) => Orientation::FlipHorizontal,
+		(1, true) => Orientation::Rotate90FlipH,
+		(2, true) => Orientation::FlipVertical,
+		_ => Orientation::Rotate270FlipH,
 	}
 }
 
-/// Regenerates WebP thumbnail and preview files from the rotated original
+/// Turning a flipped image clockwise is the same as flipping an image turned counterclockwise
+pub(crate) fn rotate_clockwise(current: Orientation) -> Orientation {
+	let (turns, flip) = to_turns_and_flip(current);
+	from_turns_and_flip(if flip { turns + 3 } else { turns + 1 }, flip)
+}
+
+pub(crate) fn rotate_counter_clockwise(current: Orientation) -> Orientation {
+	let (turns, flip) = to_turns_and_flip(current);
+	from_turns_and_flip(if flip { turns + 1 } else { turns + 3 }, flip)
+}
+
+pub(crate) fn flip_horizontal(current: Orientation) -> Orientation {
+	let (turns, flip) = to_turns_and_flip(current);
+	from_turns_and_flip(turns, !flip)
+}
+
+/// A vertical flip is a half turn followed by a horizontal flip
+pub(crate) fn flip_vertical(current: Orientation) -> Orientation {
+	let (turns, flip) = to_turns_and_flip(current);
+	from_turns_and_flip(turns + 2, !flip)
+}
+
+/// Regenerates WebP thumbnail and preview files from the rotated or flipped original
 fn rerender_webp_files<P: AsRef<Path>>(image_path: P) -> Result<(), ImageConversionError> {
 	add_breadcrumb(Breadcrumb {
 		category: Some("rerender_images".into()),
@@ -362,25 +871,15 @@ fn rerender_webp_files<P: AsRef<Path>>(image_path: P) -> Result<(), ImageConvers
 
 	let path_reference = image_path.as_ref();
 
-	let parent_directory = file_utils::get_parent_directory(image_path.as_ref())
+	let preview_path = derivative_cache::derivative_path(path_reference, true)
 		.map_err(ImageConversionError::StrError)?;
 
-	let filename_original_image =
-		file_utils::get_file_name(path_reference).map_err(ImageConversionError::StrError)?;
-
-	let mut preview_path = parent_directory.to_owned();
-
-	preview_path.push(PREVIEW_FOLDER_NAME);
-	preview_path.push(filename_original_image);
-	preview_path.set_extension(WEBP_EXTENSION);
-
-	// Always regenerate thumbnail (needed for grid view)
-	convert_to_webp(image_path.as_ref(), false)?; // Thumbnail
-
 	if preview_path.exists() {
 		let _ = fs::remove_file(&preview_path); // Ignore errors; we'll regenerate it.
 	}
-	convert_to_webp(path_reference, true)?; // Preview
+	// Always regenerate both, the thumbnail is needed for grid view. The image was just written
+	// by the app itself, so there is no need to wait for it to be finished.
+	write_derivatives(path_reference, true, true)?;
 
 	capture_message(
 		"Finished regenerating thumbnail and preview files",
@@ -0,0 +1,360 @@
+use crate::manifest::MANIFEST_FILE_NAME;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use time::OffsetDateTime;
+
+const DEFAULT_OBJECT_ID_TEMPLATE: &str = "tekst_{batch_id}";
+const DEFAULT_BATCH_KEY_TEMPLATE: &str =
+	"{object_id}/representations/{representation}/data/{object_id}_{page:05}.{extension}";
+const DEFAULT_DIRECTORY_KEY_TEMPLATE: &str = "{object_id}/{object_id}_{page:05}.{extension}";
+const MAX_PAGE_WIDTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placeholder {
+	BatchId,
+	ObjectId,
+	Representation,
+	Page,
+	Extension,
+	MaterialType,
+	Date,
+}
+
+impl Placeholder {
+	fn parse(name: &str) -> Option<Self> {
+		match name {
+			"batch_id" => Some(Placeholder::BatchId),
+			"object_id" => Some(Placeholder::ObjectId),
+			"representation" => Some(Placeholder::Representation),
+			"page" => Some(Placeholder::Page),
+			"extension" => Some(Placeholder::Extension),
+			"material_type" => Some(Placeholder::MaterialType),
+			"date" => Some(Placeholder::Date),
+			_ => None,
+		}
+	}
+
+	fn name(&self) -> &'static str {
+		match self {
+			Placeholder::BatchId => "batch_id",
+			Placeholder::ObjectId => "object_id",
+			Placeholder::Representation => "representation",
+			Placeholder::Page => "page",
+			Placeholder::Extension => "extension",
+			Placeholder::MaterialType => "material_type",
+			Placeholder::Date => "date",
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+	Literal(String),
+	/// `width` is only used for `{page:05}`, which pads the page number with zeros
+	Placeholder {
+		placeholder: Placeholder,
+		width: usize,
+	},
+}
+
+/// A key template such as `{object_id}/{object_id}_{page:05}.{extension}`, parsed once when it
+/// is set so that mistakes are reported before an upload starts
+#[derive(Debug, Clone, PartialEq)]
+struct Template {
+	segments: Vec<Segment>,
+}
+
+impl Template {
+	fn parse(
+		source: &str,
+		allowed: &[Placeholder],
+		required: &[Placeholder],
+	) -> Result<Self, String> {
+		let mut segments = Vec::new();
+		let mut rest = source;
+		while let Some(start) = rest.find('{') {
+			if start > 0 {
+				segments.push(Segment::Literal(rest[..start].to_string()));
+			}
+			let end = rest[start..]
+				.find('}')
+				.map(|end| start + end)
+				.ok_or_else(|| format!("Unclosed placeholder in key template: {source}"))?;
+			segments.push(parse_placeholder(&rest[start + 1..end], allowed, source)?);
+			rest = &rest[end + 1..];
+		}
+		if rest.contains('}') {
+			return Err(format!("Unopened placeholder in key template: {source}"));
+		}
+		if !rest.is_empty() {
+			segments.push(Segment::Literal(rest.to_string()));
+		}
+
+		let template = Template { segments };
+		if let Some(missing) = required.iter().find(|p| !template.uses(**p)) {
+			return Err(format!(
+				"Key template must contain {{{}}}: {source}",
+				missing.name()
+			));
+		}
+		Ok(template)
+	}
+
+	fn uses(&self, placeholder: Placeholder) -> bool {
+		self.segments.iter().any(
+			|segment| matches!(segment, Segment::Placeholder { placeholder: p, .. } if *p == placeholder),
+		)
+	}
+
+	/// Segments up to and including the first `{object_id}/`, as long as nothing before it
+	/// differs between the pages of a batch. Every page of a batch is stored under this prefix.
+	fn object_prefix(&self) -> Option<Template> {
+		let mut segments = Vec::new();
+		for (index, segment) in self.segments.iter().enumerate() {
+			match segment {
+				Segment::Placeholder {
+					placeholder: Placeholder::ObjectId,
+					..
+				} if matches!(
+					self.segments.get(index + 1),
+					Some(Segment::Literal(next)) if next.starts_with('/')
+				) =>
+				{
+					segments.push(segment.clone());
+					segments.push(Segment::Literal("/".to_string()));
+					return Some(Template { segments });
+				}
+				Segment::Placeholder {
+					placeholder:
+						Placeholder::Representation | Placeholder::Page | Placeholder::Extension,
+					..
+				} => return None,
+				_ => segments.push(segment.clone()),
+			}
+		}
+		None
+	}
+
+	fn render(&self, value: impl Fn(Placeholder) -> Option<String>) -> Result<String, String> {
+		let mut rendered = String::new();
+		for segment in &self.segments {
+			match segment {
+				Segment::Literal(literal) => rendered.push_str(literal),
+				Segment::Placeholder { placeholder, width } => {
+					let value = value(*placeholder).ok_or_else(|| {
+						format!(
+							"The key template uses {{{}}}, but no value was given for it",
+							placeholder.name()
+						)
+					})?;
+					rendered.push_str(&format!("{value:0>width$}"));
+				}
+			}
+		}
+		Ok(rendered)
+	}
+}
+
+fn parse_placeholder(
+	placeholder: &str,
+	allowed: &[Placeholder],
+	source: &str,
+) -> Result<Segment, String> {
+	let (name, spec) = match placeholder.split_once(':') {
+		Some((name, spec)) => (name, Some(spec)),
+		None => (placeholder, None),
+	};
+	let parsed = Placeholder::parse(name)
+		.filter(|p| allowed.contains(p))
+		.ok_or_else(|| format!("Unknown placeholder {{{name}}} in key template: {source}"))?;
+
+	let width = match spec {
+		None => 0,
+		Some(spec) if parsed == Placeholder::Page => spec
+			.parse::<usize>()
+			.ok()
+			.filter(|width| *width <= MAX_PAGE_WIDTH)
+			.ok_or_else(|| {
+				format!("Invalid page padding {{page:{spec}}} in key template: {source}")
+			})?,
+		Some(_) => {
+			return Err(format!(
+				"Only {{page}} can be padded, found {{{placeholder}}} in key template: {source}"
+			));
+		}
+	};
+	Ok(Segment::Placeholder {
+		placeholder: parsed,
+		width,
+	})
+}
+
+/// Values a page key is rendered from
+pub(crate) struct PageKey<'a> {
+	pub(crate) object_id: &'a str,
+	pub(crate) representation: Option<&'a str>,
+	pub(crate) page_nr: usize,
+	pub(crate) extension: &'a str,
+	pub(crate) material_type: Option<&'a str>,
+	/// Date the upload started, so a resumed or verified upload gets the same keys
+	pub(crate) date: &'a str,
+}
+
+/// How object ids and S3 keys are built from a batch. Set from the frontend, so that other
+/// departments can use their own ingest conventions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyLayout {
+	object_id: Template,
+	batch_key: Template,
+	/// The start of `batch_key` up to `{object_id}/`, where the manifest is stored
+	batch_prefix: Template,
+	directory_key: Template,
+}
+
+impl KeyLayout {
+	pub(crate) fn new(
+		object_id_template: &str,
+		batch_key_template: &str,
+		directory_key_template: &str,
+	) -> Result<Self, String> {
+		use Placeholder::*;
+		let batch_key = Template::parse(
+			batch_key_template,
+			&[
+				ObjectId,
+				Representation,
+				Page,
+				Extension,
+				MaterialType,
+				Date,
+			],
+			&[Representation, Page],
+		)?;
+		// Verification lists this prefix, so it must hold every page of the batch and nothing else
+		let batch_prefix = batch_key.object_prefix().ok_or_else(|| {
+			format!(
+				"Key template must store each object in its own {{object_id}}/ folder: {batch_key_template}"
+			)
+		})?;
+		Ok(Self {
+			object_id: Template::parse(object_id_template, &[BatchId, MaterialType], &[BatchId])?,
+			batch_key,
+			batch_prefix,
+			directory_key: Template::parse(
+				directory_key_template,
+				&[ObjectId, Page, Extension, MaterialType, Date],
+				&[Page],
+			)?,
+		})
+	}
+
+	/// Object id a batch is stored under. It names the upload journal, so it cannot use `{date}`.
+	pub(crate) fn object_id(
+		&self,
+		batch_id: &str,
+		material_type: Option<&str>,
+	) -> Result<String, String> {
+		self.object_id.render(|placeholder| match placeholder {
+			Placeholder::BatchId => Some(batch_id.to_string()),
+			Placeholder::MaterialType => material_type.map(str::to_string),
+			_ => None,
+		})
+	}
+
+	/// Key of a page that belongs to a representation of a batch
+	pub(crate) fn batch_key(&self, page: &PageKey) -> Result<String, String> {
+		render_page_key(&self.batch_key, page)
+	}
+
+	/// Folder every page of the batch stored under `object_id` is uploaded to, such as
+	/// `tekst_0a1b2c/`
+	pub(crate) fn batch_prefix(
+		&self,
+		object_id: &str,
+		material_type: Option<&str>,
+		date: &str,
+	) -> Result<String, String> {
+		self.batch_prefix.render(|placeholder| match placeholder {
+			Placeholder::ObjectId => Some(object_id.to_string()),
+			Placeholder::MaterialType => material_type.map(str::to_string),
+			Placeholder::Date => Some(date.to_string()),
+			_ => None,
+		})
+	}
+
+	/// Key of the manifest of the batch stored under `object_id`, next to its pages
+	pub(crate) fn manifest_key(
+		&self,
+		object_id: &str,
+		material_type: Option<&str>,
+		date: &str,
+	) -> Result<String, String> {
+		Ok(format!(
+			"{}{MANIFEST_FILE_NAME}",
+			self.batch_prefix(object_id, material_type, date)?
+		))
+	}
+
+	/// Key of a page uploaded from a plain directory
+	pub(crate) fn directory_key(&self, page: &PageKey) -> Result<String, String> {
+		render_page_key(&self.directory_key, page)
+	}
+}
+
+impl Default for KeyLayout {
+	fn default() -> Self {
+		KeyLayout::new(
+			DEFAULT_OBJECT_ID_TEMPLATE,
+			DEFAULT_BATCH_KEY_TEMPLATE,
+			DEFAULT_DIRECTORY_KEY_TEMPLATE,
+		)
+		.expect("Default key templates are valid")
+	}
+}
+
+fn render_page_key(template: &Template, page: &PageKey) -> Result<String, String> {
+	template.render(|placeholder| match placeholder {
+		Placeholder::ObjectId => Some(page.object_id.to_string()),
+		Placeholder::Representation => page.representation.map(str::to_string),
+		Placeholder::Page => Some(page.page_nr.to_string()),
+		Placeholder::Extension => Some(page.extension.to_string()),
+		Placeholder::MaterialType => page.material_type.map(str::to_string),
+		Placeholder::Date => Some(page.date.to_string()),
+		Placeholder::BatchId => None,
+	})
+}
+
+static KEY_LAYOUT: Lazy<Mutex<KeyLayout>> = Lazy::new(|| Mutex::new(KeyLayout::default()));
+
+pub fn set_key_layout(
+	object_id_template: &str,
+	batch_key_template: &str,
+	directory_key_template: &str,
+) -> Result<(), String> {
+	let layout = KeyLayout::new(
+		object_id_template,
+		batch_key_template,
+		directory_key_template,
+	)?;
+	let mut key_layout = KEY_LAYOUT.lock().map_err(|e| e.to_string())?;
+	*key_layout = layout;
+	Ok(())
+}
+
+pub(crate) fn get_key_layout() -> KeyLayout {
+	KEY_LAYOUT
+		.lock()
+		.map(|layout| layout.clone())
+		.unwrap_or_default()
+}
+
+/// Today's date (UTC) as used for `{date}`, formatted as `YYYY-MM-DD`
+pub(crate) fn today() -> String {
+	let date = OffsetDateTime::now_utc().date();
+	format!(
+		"{:04}-{:02}-{:02}",
+		date.year(),
+		u8::from(date.month()),
+		date.day()
+	)
+}
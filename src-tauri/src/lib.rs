@@ -3,25 +3,54 @@ use once_cell::sync::Lazy;
 use sentry::{Breadcrumb, Level, add_breadcrumb, capture_message};
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::path::Path;
 use std::string::ToString;
 use std::sync::Mutex;
-use tauri::Window;
+use tauri::{AppHandle, Emitter, Window};
 use tokio::sync::OnceCell;
 
+use crate::duplicate_detection::DuplicateGroup;
+#[cfg(not(feature = "debug-mock"))]
+use crate::error::UploadError;
 use crate::image_converter::ConversionCount;
-use crate::model::BatchRepresentation;
 #[cfg(not(feature = "debug-mock"))]
 use crate::model::RequiredEnvironmentVariables;
+#[cfg(not(feature = "debug-mock"))]
+use crate::model::UploadSummary;
+#[cfg(not(feature = "debug-mock"))]
+use crate::model::UploadVerificationReport;
 use crate::model::{AuthenticationResponse, SecretVariables};
+use crate::model::{BatchRepresentation, BatchValidationReport};
+use crate::page_analysis::{PageBlankness, PageSharpness};
 
 mod auth;
+mod batch_validation;
+#[cfg(not(feature = "debug-mock"))]
+mod checksum;
+mod derivative_cache;
+mod duplicate_detection;
 mod error;
 mod file_utils;
 mod image_converter;
+#[cfg(not(feature = "debug-mock"))]
+mod key_layout;
+#[cfg(not(feature = "debug-mock"))]
+mod manifest;
 mod model;
+mod orientation_sidecar;
+mod page_analysis;
+#[cfg(not(feature = "debug-mock"))]
+mod retry;
 mod s3;
+mod scan_watcher;
 #[cfg(desktop)]
 mod tray;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_journal;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_progress;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_verification;
 mod vault;
 
 #[cfg(test)]
@@ -95,36 +124,58 @@ async fn refresh_token(refresh_token: String) -> AuthenticationResponse {
 }
 
 #[tauri::command]
-async fn ensure_all_previews_and_thumbnails(directory_path: String) -> Result<(), String> {
-	let image_files =
-		file_utils::list_image_files(&directory_path, true).map_err(|e| e.to_string())?;
+async fn ensure_all_previews_and_thumbnails(
+	directory_path: String,
+) -> Result<ConversionCount, String> {
+	tokio::task::spawn_blocking(move || {
+		image_converter::ensure_all_previews_and_thumbnails(directory_path)
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?
+	.map_err(|e| e.to_string())
+}
 
-	add_breadcrumb(Breadcrumb {
-		category: Some("ensure_files".into()),
-		message: Some(format!(
-			"Converting images to thumbnails and previews. Total files: {}",
-			image_files.len()
-		)),
-		level: Level::Info,
-		..Default::default()
-	});
+#[tauri::command]
+async fn analyze_blank_pages(directory_path: String) -> Result<Vec<PageBlankness>, String> {
+	tokio::task::spawn_blocking(move || page_analysis::analyze_blank_pages(directory_path))
+		.await
+		.map_err(|e| format!("Failed to run blocking task: {e}"))?
+		.map_err(|e| e.to_string())
+}
 
-	for file_path in image_files {
-		let file_path_str = file_path.to_string_lossy().to_string();
-		if !image_converter::check_if_preview_exists(&file_path_str).unwrap_or(false) {
-			image_converter::convert_to_webp(file_path_str.clone(), true).ok();
-		}
-		if !image_converter::check_if_thumbnail_exists(&file_path_str).unwrap_or(false) {
-			image_converter::convert_to_webp(file_path_str, false).ok();
-		}
-	}
+#[tauri::command]
+async fn analyze_sharpness(directory_path: String) -> Result<Vec<PageSharpness>, String> {
+	tokio::task::spawn_blocking(move || {
+		page_analysis::analyze_sharpness(directory_path, page_analysis::get_sharpness_threshold())
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?
+	.map_err(|e| e.to_string())
+}
 
-	capture_message(
-		"Finished converting images to thumbnails and previews",
-		Level::Info,
-	);
+#[tauri::command]
+async fn set_sharpness_threshold(threshold: f32) -> Result<(), String> {
+	page_analysis::set_sharpness_threshold(threshold)
+}
+
+#[tauri::command]
+async fn find_duplicate_pages(directory_path: String) -> Result<Vec<DuplicateGroup>, String> {
+	tokio::task::spawn_blocking(move || duplicate_detection::find_duplicate_pages(directory_path))
+		.await
+		.map_err(|e| format!("Failed to run blocking task: {e}"))?
+		.map_err(|e| e.to_string())
+}
 
-	Ok(())
+#[tauri::command]
+async fn find_duplicate_pages_in_batches(
+	batch_map: HashMap<String, BatchRepresentation>,
+) -> Result<Vec<DuplicateGroup>, String> {
+	tokio::task::spawn_blocking(move || {
+		duplicate_detection::find_duplicate_pages_in_batches(&batch_map)
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?
+	.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -147,7 +198,11 @@ async fn create_thumbnail_webp(file_path: String) -> Result<(), String> {
 		..Default::default()
 	});
 
-	match image_converter::convert_to_webp(file_path, false) {
+	let converted =
+		tokio::task::spawn_blocking(move || image_converter::convert_to_webp(file_path, false))
+			.await
+			.map_err(|e| format!("Failed to run blocking task: {e}"))?;
+	match converted {
 		Ok(_) => {
 			capture_message("Finished creating thumbnail", Level::Info);
 			Ok(())
@@ -179,7 +234,11 @@ async fn create_preview_webp(file_path: String) -> Result<(), String> {
 		..Default::default()
 	});
 
-	match image_converter::convert_to_webp(file_path, true) {
+	let converted =
+		tokio::task::spawn_blocking(move || image_converter::convert_to_webp(file_path, true))
+			.await
+			.map_err(|e| format!("Failed to run blocking task: {e}"))?;
+	match converted {
 		Ok(_) => {
 			capture_message("Finished creating preview image", Level::Info);
 			Ok(())
@@ -195,15 +254,15 @@ static CURRENT_DIRECTORIES_PROCESSING: Lazy<Mutex<Vec<String>>> =
 	Lazy::new(|| Mutex::new(Vec::new()));
 
 #[tauri::command]
-async fn convert_directory_to_webp(directory_path: String) -> Result<ConversionCount, String> {
+async fn convert_directory_to_webp(
+	directory_path: String,
+	window: Window,
+) -> Result<ConversionCount, String> {
 	{
 		// Lock the Mutex to safely access the shared state
 		let mut directories = CURRENT_DIRECTORIES_PROCESSING.lock().unwrap();
 		if directories.contains(&directory_path) {
-			return Ok(ConversionCount {
-				converted: 0,
-				already_converted: 0,
-			});
+			return Ok(ConversionCount::from_results(Vec::new()));
 		}
 
 		directories.push(directory_path.clone());
@@ -213,7 +272,9 @@ async fn convert_directory_to_webp(directory_path: String) -> Result<ConversionC
 
 	// Convert the directory to webp asynchronously
 	let result = tokio::task::spawn_blocking(move || {
-		image_converter::convert_directory_to_webp(directory_path_clone)
+		image_converter::convert_directory_to_webp(directory_path_clone, |progress| {
+			let _ = window.emit("conversion_progress", progress);
+		})
 	})
 	.await
 	.expect("Failed to run blocking task");
@@ -231,6 +292,26 @@ async fn convert_directory_to_webp(directory_path: String) -> Result<ConversionC
 	// Directories mutex unlocked at end of scope.
 }
 
+#[tauri::command]
+async fn watch_scan_folder(directory_path: String, window: Window) -> Result<(), String> {
+	// Stopping a previous watcher waits for its in-flight conversions, and starting lists the
+	// whole folder, so neither may run on the main thread
+	tokio::task::spawn_blocking(move || {
+		scan_watcher::watch(directory_path, move |kind, change| {
+			let _ = window.emit(kind.event_name(), change);
+		})
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?
+}
+
+#[tauri::command]
+async fn unwatch_scan_folder(directory_path: String) -> Result<bool, String> {
+	tokio::task::spawn_blocking(move || scan_watcher::unwatch(Path::new(&directory_path)))
+		.await
+		.map_err(|e| format!("Failed to run blocking task: {e}"))
+}
+
 #[tauri::command]
 async fn pick_directory<R: tauri::Runtime>(
 	start_path: String,
@@ -263,17 +344,93 @@ async fn get_papi_access_token() -> Result<String, String> {
 async fn upload_directory_to_s3(
 	directory_path: &str,
 	object_id: &str,
+	job_id: &str,
 	app_window: Window,
-) -> Result<usize, String> {
-	s3::upload_directory(directory_path, object_id, app_window).await
+) -> Result<UploadSummary, UploadError> {
+	s3::upload_directory(directory_path, object_id, job_id, app_window).await
 }
 
+#[cfg(not(feature = "debug-mock"))]
 #[tauri::command]
 async fn upload_batch_to_s3(
 	batch_map: HashMap<String, BatchRepresentation>,
+	material_type: Option<String>,
+	job_id: &str,
 	app_window: tauri::Window,
-) -> Result<usize, String> {
-	s3::upload_batch_to_s3(batch_map, app_window).await
+) -> Result<UploadSummary, UploadError> {
+	s3::upload_batch_to_s3(batch_map, material_type, job_id, app_window).await
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[tauri::command]
+async fn set_upload_retry_policy(
+	max_attempts: u32,
+	initial_backoff_ms: u64,
+	max_backoff_ms: u64,
+	jitter: bool,
+) -> Result<(), String> {
+	retry::set_retry_policy(max_attempts, initial_backoff_ms, max_backoff_ms, jitter)
+}
+
+#[tauri::command]
+async fn validate_batch_upload(
+	batch_map: HashMap<String, BatchRepresentation>,
+) -> Result<BatchValidationReport, String> {
+	tokio::task::spawn_blocking(move || {
+		batch_validation::validate_batch(
+			&batch_map,
+			batch_validation::get_max_batch_upload_size(),
+			&file_utils::get_file_stability(),
+		)
+	})
+	.await
+	.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_file_stability(interval_ms: u64, parse_header: bool) -> Result<(), String> {
+	file_utils::set_file_stability(interval_ms, parse_header)
+}
+
+#[tauri::command]
+async fn set_max_batch_upload_size(max_bytes: u64) -> Result<(), String> {
+	batch_validation::set_max_batch_upload_size(max_bytes)
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[tauri::command]
+async fn verify_batch_upload(
+	batch_map: HashMap<String, BatchRepresentation>,
+	material_type: Option<String>,
+	upload_dates: HashMap<String, String>,
+) -> Result<UploadVerificationReport, String> {
+	upload_verification::verify_batch_upload(batch_map, material_type, upload_dates).await
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[tauri::command]
+async fn set_key_layout(
+	object_id_template: &str,
+	batch_key_template: &str,
+	directory_key_template: &str,
+) -> Result<(), String> {
+	key_layout::set_key_layout(
+		object_id_template,
+		batch_key_template,
+		directory_key_template,
+	)
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[tauri::command]
+async fn discard_upload_journals(directory_path: String, app: AppHandle) -> Result<usize, String> {
+	s3::discard_upload_journals_in(&app, Path::new(&directory_path)).await
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[tauri::command]
+async fn cancel_upload(job_id: &str) -> Result<bool, String> {
+	s3::cancel_upload(job_id)
 }
 
 #[tauri::command]
@@ -284,6 +441,14 @@ async fn rotate_image(file_path: String, direction: String) -> Result<(), String
 		.map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn flip_image(file_path: String, direction: String) -> Result<(), String> {
+	tokio::task::spawn_blocking(move || image_converter::flip_image(file_path, &direction))
+		.await
+		.expect("Failed to run blocking task")
+		.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn delete_all_previews_and_thumbnails(directory_path: String) -> Result<u32, String> {
 	tokio::task::spawn_blocking(move || {
@@ -294,6 +459,60 @@ async fn delete_all_previews_and_thumbnails(directory_path: String) -> Result<u3
 	.map_err(|e| e.to_string())
 }
 
+/// Deletes the thumbnail and preview of an image, wherever derivatives are stored
+#[tauri::command]
+async fn delete_derivatives(file_path: String) -> Result<u32, String> {
+	tokio::task::spawn_blocking(move || derivative_cache::remove_derivatives(Path::new(&file_path)))
+		.await
+		.map_err(|e| format!("Failed to run blocking task: {e}"))?
+		.map_err(|e| e.to_string())
+}
+
+/// Deletes the orientation sidecar of a deleted image. Returns whether it had one.
+#[tauri::command]
+async fn delete_orientation_sidecar(file_path: String) -> Result<bool, String> {
+	tokio::task::spawn_blocking(move || {
+		orientation_sidecar::remove_orientation(Path::new(&file_path))
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?
+	.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_derivative_cache(
+	app_handle: AppHandle,
+	enabled: bool,
+	max_bytes: Option<u64>,
+) -> Result<(), String> {
+	use tauri::Manager;
+
+	let directory = if enabled {
+		Some(
+			app_handle
+				.path()
+				.app_cache_dir()
+				.map_err(|e| format!("Failed to find app cache directory: {e}"))?
+				.join(derivative_cache::DERIVATIVE_CACHE_FOLDER_NAME),
+		)
+	} else {
+		None
+	};
+	let max_bytes = max_bytes.unwrap_or_else(derivative_cache::default_max_cache_bytes);
+	tokio::task::spawn_blocking(move || {
+		derivative_cache::set_derivative_cache(directory, max_bytes)
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?
+}
+
+/// Path of the thumbnail, or the preview if `high_res`, wherever derivatives are stored
+#[tauri::command]
+fn get_derivative_path(file_path: String, high_res: bool) -> Result<String, String> {
+	derivative_cache::derivative_path(Path::new(&file_path), high_res)
+		.map(|path| path.to_string_lossy().to_string())
+}
+
 #[tauri::command]
 async fn set_image_size_fractions(
 	thumbnail_fraction: u32,
@@ -302,6 +521,27 @@ async fn set_image_size_fractions(
 	image_converter::set_image_size_fractions(thumbnail_fraction, preview_fraction)
 }
 
+#[tauri::command]
+async fn set_conversion_profile(
+	target: String,
+	filter: String,
+	quality: f32,
+	lossless: bool,
+	size: image_converter::TargetSize,
+) -> Result<(), String> {
+	let profile = image_converter::ConversionProfile::new(&filter, quality, lossless, size)?;
+	image_converter::set_conversion_profile(&target, profile)
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[tauri::command]
+async fn set_upload_concurrency(
+	max_concurrent_objects: usize,
+	max_concurrent_parts: usize,
+) -> Result<(), String> {
+	s3::set_upload_concurrency(max_concurrent_objects, max_concurrent_parts)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
 	tauri::async_runtime::set(tokio::runtime::Handle::current());
@@ -330,6 +570,26 @@ pub fn run() {
 				let handle = app.handle();
 				tray::create_tray(handle)?;
 			}
+			let handle = app.handle().clone();
+			image_converter::set_derivative_listener(move |ready| {
+				let _ = handle.emit("derivative_ready", ready);
+			});
+			#[cfg(not(feature = "debug-mock"))]
+			{
+				let handle = app.handle().clone();
+				tauri::async_runtime::spawn(async move {
+					if let Err(e) = s3::discard_expired_upload_journals(&handle).await {
+						add_breadcrumb(Breadcrumb {
+							category: Some("s3".into()),
+							message: Some(format!(
+								"Failed to discard expired upload journals: {e}"
+							)),
+							level: Level::Warning,
+							..Default::default()
+						});
+					}
+				});
+			}
 			Ok(())
 		})
 		.invoke_handler(tauri::generate_handler![
@@ -341,16 +601,44 @@ pub fn run() {
 			ensure_all_previews_and_thumbnails,
 			create_preview_webp,
 			convert_directory_to_webp,
+			analyze_blank_pages,
+			analyze_sharpness,
+			set_sharpness_threshold,
+			find_duplicate_pages,
+			find_duplicate_pages_in_batches,
+			watch_scan_folder,
+			unwatch_scan_folder,
 			pick_directory,
 			rotate_image,
+			flip_image,
 			delete_all_previews_and_thumbnails,
+			delete_derivatives,
+			delete_orientation_sidecar,
+			set_derivative_cache,
+			get_derivative_path,
 			set_image_size_fractions,
+			set_conversion_profile,
+			validate_batch_upload,
+			set_max_batch_upload_size,
+			set_file_stability,
 			#[cfg(not(feature = "debug-mock"))]
 			get_papi_access_token,
 			#[cfg(not(feature = "debug-mock"))]
 			upload_directory_to_s3,
 			#[cfg(not(feature = "debug-mock"))]
 			upload_batch_to_s3,
+			#[cfg(not(feature = "debug-mock"))]
+			set_upload_concurrency,
+			#[cfg(not(feature = "debug-mock"))]
+			set_upload_retry_policy,
+			#[cfg(not(feature = "debug-mock"))]
+			set_key_layout,
+			#[cfg(not(feature = "debug-mock"))]
+			discard_upload_journals,
+			#[cfg(not(feature = "debug-mock"))]
+			cancel_upload,
+			#[cfg(not(feature = "debug-mock"))]
+			verify_batch_upload,
 		])
 		.on_window_event(|window, event| {
 			if let tauri::WindowEvent::CloseRequested { api, .. } = event {
@@ -0,0 +1,130 @@
+use crate::checksum;
+use crate::error::UploadError;
+use crate::model::{BatchManifest, BatchPage, ManifestPage, SecretVariables, UploadedObject};
+use crate::retry::{AttemptError, get_retry_policy, with_retry};
+use crate::s3::CancellationFlag;
+use aws_sdk_s3::Client;
+use aws_sdk_s3::primitives::ByteStream;
+use bytes::Bytes;
+use sentry::{Breadcrumb, Level, add_breadcrumb};
+use std::collections::HashMap;
+use std::path::Path;
+use time::{OffsetDateTime, UtcOffset};
+
+pub(crate) const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Batch-wide values written to a manifest
+pub(crate) struct ManifestBatch<'a> {
+	pub(crate) object_id: &'a str,
+	pub(crate) batch_id: &'a str,
+	pub(crate) material_type: Option<&'a str>,
+	pub(crate) hostname: &'a str,
+	pub(crate) uploaded_at: &'a str,
+}
+
+/// Lists every page of a batch with the size and checksum S3 confirmed for it. Fails if a page
+/// is not among the `uploaded` objects, since the manifest must never list a page that is missing.
+pub(crate) fn build_manifest(
+	batch: ManifestBatch,
+	pages: &[BatchPage],
+	uploaded: &[UploadedObject],
+) -> Result<BatchManifest, String> {
+	let uploaded: HashMap<&str, &UploadedObject> = uploaded
+		.iter()
+		.map(|object| (object.key.as_str(), object))
+		.collect();
+
+	let pages = pages
+		.iter()
+		.map(|page| {
+			let object = uploaded
+				.get(page.key.as_str())
+				.ok_or_else(|| format!("Missing upload of {} for the manifest", page.key))?;
+			Ok(ManifestPage {
+				key: page.key.clone(),
+				representation: page.representation.to_string(),
+				page_nr: page.page_nr,
+				size: object.size,
+				sha256: object.sha256.clone(),
+				original_filename: file_name(&page.path),
+			})
+		})
+		.collect::<Result<Vec<_>, String>>()?;
+
+	Ok(BatchManifest {
+		object_id: batch.object_id.to_string(),
+		batch_id: batch.batch_id.to_string(),
+		material_type: batch.material_type.map(str::to_string),
+		hostname: batch.hostname.to_string(),
+		uploaded_at: batch.uploaded_at.to_string(),
+		pages,
+	})
+}
+
+fn file_name(path: &Path) -> String {
+	path.file_name()
+		.map(|name| name.to_string_lossy().to_string())
+		.unwrap_or_default()
+}
+
+/// Uploads the manifest to `key`, which the key layout places next to the pages
+pub(crate) async fn upload_manifest(
+	client: &Client,
+	secret_variables: &SecretVariables,
+	key: &str,
+	manifest: &BatchManifest,
+	cancellation: &CancellationFlag,
+) -> Result<(), UploadError> {
+	let body = serde_json::to_vec_pretty(manifest)
+		.map_err(|e| format!("Failed to serialize manifest: {e}"))?;
+	let checksum_sha256 = checksum::to_base64(&checksum::sha256(&body));
+	let body = Bytes::from(body);
+
+	with_retry(
+		&get_retry_policy(),
+		&format!("upload of {key}"),
+		cancellation,
+		|| async {
+			client
+				.put_object()
+				.bucket(&secret_variables.s3_bucket_name)
+				.key(key)
+				.content_type("application/json")
+				.checksum_sha256(&checksum_sha256)
+				.body(ByteStream::from(body.clone()))
+				.send()
+				.await
+				.map_err(|e| AttemptError::from_sdk("Failed to upload manifest", e))
+		},
+	)
+	.await?;
+
+	add_breadcrumb(Breadcrumb {
+		category: Some("s3".into()),
+		message: Some(format!(
+			"Uploaded manifest {key} with {} pages",
+			manifest.pages.len()
+		)),
+		level: Level::Info,
+		..Default::default()
+	});
+	Ok(())
+}
+
+/// Current time (UTC) formatted as RFC 3339, e.g. `2024-05-17T08:30:00Z`
+pub(crate) fn now_rfc3339() -> String {
+	format_rfc3339(OffsetDateTime::now_utc())
+}
+
+pub(crate) fn format_rfc3339(time: OffsetDateTime) -> String {
+	let time = time.to_offset(UtcOffset::UTC);
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+		time.year(),
+		u8::from(time.month()),
+		time.day(),
+		time.hour(),
+		time.minute(),
+		time.second()
+	)
+}
@@ -1,8 +1,17 @@
 #[cfg(not(feature = "debug-mock"))]
+use crate::s3::CancellationFlag;
+#[cfg(not(feature = "debug-mock"))]
+use crate::upload_journal::{FileVersion, UploadJournal};
+#[cfg(not(feature = "debug-mock"))]
+use crate::upload_progress::UploadProgress;
+#[cfg(not(feature = "debug-mock"))]
 use aws_sdk_s3::Client;
 use serde::{Deserialize, Serialize};
 #[cfg(not(feature = "debug-mock"))]
+use std::collections::HashMap;
 use std::path::PathBuf;
+#[cfg(not(feature = "debug-mock"))]
+use std::sync::{Arc, Mutex};
 
 #[derive(Deserialize)]
 pub(crate) struct BatchRepresentation {
@@ -89,6 +98,7 @@ impl UserInfo {
 	}
 }
 
+#[cfg(not(feature = "debug-mock"))]
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub(crate) struct TokenResponseWithoutRefresh {
 	#[serde(rename(serialize = "accessToken", deserialize = "access_token"))]
@@ -139,6 +149,7 @@ pub struct SecretVariables {
 	pub(crate) s3_region: String,
 }
 
+#[cfg(not(feature = "debug-mock"))]
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all(serialize = "camelCase"))]
 // We send "camelCase" to frontend
@@ -146,6 +157,173 @@ pub struct TransferProgress {
 	pub(crate) directory: String,
 	pub(crate) page_nr: usize,
 	pub(crate) total_pages: usize,
+	pub(crate) bytes_sent: u64,
+	pub(crate) total_bytes: u64,
+	/// File name of the object or part that was confirmed last
+	pub(crate) current_file: String,
+	/// Part number if the last confirmed upload was a multipart part
+	pub(crate) current_part: Option<i32>,
+	/// Average transfer rate of this upload so far
+	pub(crate) bytes_per_second: u64,
+}
+
+/// Emitted as `conversion_progress` after every image of a directory conversion
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ConversionProgress {
+	pub(crate) directory: String,
+	/// File name of the image that finished last
+	pub(crate) current_file: String,
+	pub(crate) done: usize,
+	pub(crate) total: usize,
+	pub(crate) failures: usize,
+}
+
+/// Emitted as `file_added`, `file_removed` or `folder_added` by the watcher of a scan folder
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ScanFolderChange {
+	/// The scan folder being watched
+	pub(crate) root: PathBuf,
+	pub(crate) path: PathBuf,
+	/// Thumbnail of an added image, unless it could not be made
+	pub(crate) thumbnail: Option<PathBuf>,
+}
+
+/// Emitted as `derivative_ready` once a thumbnail or preview has been renamed into place
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct DerivativeReady {
+	/// The image the derivative was made from
+	pub(crate) source: PathBuf,
+	pub(crate) path: PathBuf,
+	pub(crate) high_res: bool,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct UploadedObject {
+	pub(crate) key: String,
+	pub(crate) path: String,
+	pub(crate) size: usize,
+	/// Hex encoded SHA-256 of the file, verified against the checksum S3 reports
+	pub(crate) sha256: String,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct UploadSummary {
+	pub(crate) uploaded_count: usize,
+	pub(crate) objects: Vec<UploadedObject>,
+	/// Date (`YYYY-MM-DD`) each object was first uploaded, by object id. Keys that use `{date}`
+	/// are rendered with it, so the upload can only be verified with these dates.
+	pub(crate) upload_dates: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub enum BatchProblemKind {
+	EmptyBatchMap,
+	EmptyBatch,
+	MissingFile,
+	NotAFile,
+	Unreadable,
+	UnsupportedExtension,
+	DuplicateFile,
+	RepresentationCountMismatch,
+	UnfinishedFile,
+	TooLarge,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct BatchProblem {
+	pub(crate) kind: BatchProblemKind,
+	pub(crate) batch_id: Option<String>,
+	pub(crate) path: Option<String>,
+	pub(crate) message: String,
+}
+
+/// Result of checking a batch map before anything is uploaded
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct BatchValidationReport {
+	pub(crate) valid: bool,
+	pub(crate) file_count: usize,
+	pub(crate) total_bytes: u64,
+	pub(crate) problems: Vec<BatchProblem>,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ObjectDiscrepancy {
+	pub(crate) key: String,
+	/// Local file the object was uploaded from, `None` for objects not in the batch
+	pub(crate) path: Option<String>,
+	pub(crate) reason: String,
+}
+
+/// Comparison of the objects in the bucket with the local files of a batch
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct UploadVerificationReport {
+	/// True if every page is in the bucket with the right size and checksum, and nothing else is
+	pub(crate) complete: bool,
+	pub(crate) verified_count: usize,
+	pub(crate) missing: Vec<ObjectDiscrepancy>,
+	pub(crate) extra: Vec<ObjectDiscrepancy>,
+	pub(crate) mismatched: Vec<ObjectDiscrepancy>,
+}
+
+/// One page of a batch, as listed in the manifest uploaded next to the batch
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ManifestPage {
+	pub(crate) key: String,
+	pub(crate) representation: String,
+	pub(crate) page_nr: usize,
+	pub(crate) size: usize,
+	/// Hex encoded SHA-256 of the file, as confirmed by S3
+	pub(crate) sha256: String,
+	pub(crate) original_filename: String,
+}
+
+/// Machine-readable description of an uploaded batch, so the ingest pipeline can check that
+/// every page arrived without asking Papi
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct BatchManifest {
+	pub(crate) object_id: String,
+	pub(crate) batch_id: String,
+	pub(crate) material_type: Option<String>,
+	/// Hostname of the scanner computer that uploaded the batch
+	pub(crate) hostname: String,
+	/// RFC 3339 timestamp (UTC) of when the pages were confirmed uploaded
+	pub(crate) uploaded_at: String,
+	pub(crate) pages: Vec<ManifestPage>,
+}
+
+/// A file of a batch and the key it is uploaded to
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) struct BatchPage {
+	pub(crate) key: String,
+	pub(crate) path: PathBuf,
+	pub(crate) representation: &'static str,
+	pub(crate) page_nr: usize,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) struct UploadJob {
+	pub(crate) path: PathBuf,
+	pub(crate) key: String,
+	pub(crate) version: FileVersion,
+	pub(crate) journal: Arc<Mutex<UploadJournal>>,
 }
 
 #[cfg(not(feature = "debug-mock"))]
@@ -153,8 +331,37 @@ pub struct PutObjectRequest<'a> {
 	pub client: &'a Client,
 	pub secret_variables: &'a SecretVariables,
 	pub path: &'a PathBuf,
-	pub object_id: &'a str,
-	pub page_nr: usize,
+	pub key: &'a str,
+	pub version: FileVersion,
+	pub journal: &'a Mutex<UploadJournal>,
+	pub max_concurrent_parts: usize,
+	pub cancellation: &'a CancellationFlag,
+	pub progress: &'a Arc<UploadProgress>,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+pub struct MultipartUploadRequest<'a> {
+	pub client: &'a Client,
+	pub secret_variables: &'a SecretVariables,
+	pub path: &'a PathBuf,
+	pub key: &'a str,
+	pub version: FileVersion,
+	pub journal: &'a Mutex<UploadJournal>,
+	pub max_concurrent_parts: usize,
+	pub cancellation: &'a CancellationFlag,
+	pub progress: &'a Arc<UploadProgress>,
+}
+
+// Owned, since every part is uploaded from its own task
+#[cfg(not(feature = "debug-mock"))]
+pub struct UploadPartRequest {
+	pub client: Client,
+	pub bucket: String,
+	pub key: String,
+	pub upload_id: String,
+	pub path: PathBuf,
 	pub file_size: usize,
-	pub representation_type: Option<&'a str>,
+	pub part_number: i32,
+	pub cancellation: CancellationFlag,
+	pub progress: Arc<UploadProgress>,
 }
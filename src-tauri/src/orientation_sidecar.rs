@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "debug-mock"))]
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::derivative_cache::{self, DerivativeLocation};
+use crate::file_utils;
+
+pub(crate) const ORIENTATION_FOLDER_NAME: &str = ".orientation";
+/// Folder of the sidecars in the central derivative cache. Eviction leaves it alone, since a
+/// sidecar cannot be made again from the image.
+const CENTRAL_ORIENTATION_FOLDER_NAME: &str = "orientation";
+/// S3 user metadata (`x-amz-meta-orientation`) the orientation is uploaded as, since the
+/// sidecar itself is not uploaded
+#[cfg(not(feature = "debug-mock"))]
+const ORIENTATION_METADATA_KEY: &str = "orientation";
+
+/// Orientation of an image whose EXIF metadata cannot be written, such as many PNGs and some
+/// TIFF variants. Stored as `.orientation/<file name>.json` next to the image, or in the central
+/// derivative cache while it is used, so the scan folder stays untouched. Sidecars in the cache
+/// are keyed like its derivatives and no longer apply once the cache is turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct OrientationSidecar {
+	/// EXIF orientation value, 1 to 8
+	orientation: u8,
+}
+
+/// Where the sidecar of an image is written, next to it or in the central derivative cache
+pub(crate) fn sidecar_path(image_path: &Path) -> Result<PathBuf, String> {
+	match derivative_cache::get_derivative_location() {
+		DerivativeLocation::BesideSource => sidecar_path_beside_source(image_path),
+		DerivativeLocation::Central { directory, .. } => {
+			Ok(central_sidecar_path(&directory, image_path))
+		}
+	}
+}
+
+fn sidecar_path_beside_source(image_path: &Path) -> Result<PathBuf, String> {
+	let parent_directory = file_utils::get_parent_directory(image_path)?;
+	let mut file_name = file_utils::get_file_name(image_path)?.to_os_string();
+	file_name.push(".json");
+	Ok(parent_directory
+		.join(ORIENTATION_FOLDER_NAME)
+		.join(file_name))
+}
+
+/// `<directory>/orientation/<hash of source path>.json`
+pub(crate) fn central_sidecar_path(directory: &Path, image_path: &Path) -> PathBuf {
+	let mut path = directory
+		.join(CENTRAL_ORIENTATION_FOLDER_NAME)
+		.join(derivative_cache::central_name(image_path));
+	path.set_extension("json");
+	path
+}
+
+/// Every sidecar the image may have, the one written to first. A sidecar recorded next to the
+/// image before the central cache was turned on still applies.
+fn sidecar_paths(image_path: &Path) -> Result<Vec<PathBuf>, String> {
+	let current = sidecar_path(image_path)?;
+	let beside_source = sidecar_path_beside_source(image_path)?;
+	if current == beside_source {
+		Ok(vec![current])
+	} else {
+		Ok(vec![current, beside_source])
+	}
+}
+
+/// EXIF orientation value recorded for the image, if it has a sidecar
+pub(crate) fn read_orientation(image_path: &Path) -> Option<u8> {
+	sidecar_paths(image_path)
+		.ok()?
+		.into_iter()
+		.find_map(|path| {
+			let contents = fs::read(path).ok()?;
+			serde_json::from_slice::<OrientationSidecar>(&contents)
+				.ok()
+				.map(|sidecar| sidecar.orientation)
+		})
+}
+
+pub(crate) fn write_orientation(image_path: &Path, orientation: u8) -> io::Result<()> {
+	let path = sidecar_path(image_path).map_err(io::Error::other)?;
+	let contents = serde_json::to_vec(&OrientationSidecar { orientation })?;
+	file_utils::write_atomically(path, &contents)
+}
+
+/// Deletes the sidecars of a deleted image. Returns whether there were any.
+pub(crate) fn remove_orientation(image_path: &Path) -> io::Result<bool> {
+	let mut removed = false;
+	for path in sidecar_paths(image_path).map_err(io::Error::other)? {
+		match fs::remove_file(path) {
+			Ok(()) => removed = true,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+			Err(e) => return Err(e),
+		}
+	}
+	Ok(removed)
+}
+
+/// S3 object metadata carrying the orientation of an image that has a sidecar, so the
+/// uploaded object is shown the same way as in the app
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) fn object_metadata(image_path: &Path) -> Option<HashMap<String, String>> {
+	read_orientation(image_path).map(|orientation| {
+		HashMap::from([(
+			ORIENTATION_METADATA_KEY.to_string(),
+			orientation.to_string(),
+		)])
+	})
+}
@@ -0,0 +1,327 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use sentry::{Breadcrumb, Level, add_breadcrumb};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::derivative_cache;
+use crate::error::ImageConversionError;
+use crate::file_utils;
+use crate::image_converter::{self, TargetSize};
+
+/// Long edge of the grayscale copy pages are analysed on, large enough to keep thin strokes
+const ANALYSIS_LONG_EDGE: u32 = 1024;
+/// Share of each edge left out of the analysis, where scanner beds and book edges show
+const MARGIN_FRACTION: f32 = 0.1;
+/// Share of the page at most as bright as the paper, the brightest spots are glare and holes
+const PAPER_PERCENTILE: f32 = 0.9;
+/// How much darker than the paper a pixel must be to count as ink
+const INK_CONTRAST: u8 = 64;
+const BLANK_INK_COVERAGE: f32 = 0.001;
+const NEARLY_BLANK_INK_COVERAGE: f32 = 0.01;
+/// Pages varying this much in luminance have content, even when little of it is dark enough to
+/// count as ink, like pencil drawings and faded photos
+const CONTENT_LUMINANCE_STD_DEV: f32 = 0.1;
+/// Pages with content and a sharpness below this are reported as blurry. Printed text on the
+/// analysis copy of a page in focus is usually well above it.
+const DEFAULT_SHARPNESS_THRESHOLD: f32 = 100.0;
+
+static SHARPNESS_THRESHOLD: Lazy<Mutex<f32>> =
+	Lazy::new(|| Mutex::new(DEFAULT_SHARPNESS_THRESHOLD));
+
+/// What is measured on a page while its thumbnail is made, stored in the thumbnail's stamp
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageAnalysis {
+	/// Share of the page, inside the margins, that is ink, from 0 to 1
+	pub(crate) ink_coverage: f32,
+	/// Standard deviation of the luminance inside the margins, from 0 to 1
+	pub(crate) luminance_std_dev: f32,
+	/// Variance of the Laplacian inside the margins. Higher is sharper, pages out of focus have
+	/// soft edges and score low.
+	pub(crate) sharpness: f32,
+	/// Difference hash of the page, pages that look alike differ in few bits
+	pub(crate) perceptual_hash: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Blankness {
+	Blank,
+	NearlyBlank,
+	Content,
+}
+
+impl PageAnalysis {
+	pub fn blankness(&self) -> Blankness {
+		if self.ink_coverage >= NEARLY_BLANK_INK_COVERAGE
+			|| self.luminance_std_dev >= CONTENT_LUMINANCE_STD_DEV
+		{
+			Blankness::Content
+		} else if self.ink_coverage >= BLANK_INK_COVERAGE {
+			Blankness::NearlyBlank
+		} else {
+			Blankness::Blank
+		}
+	}
+
+	/// Whether the page has content and is less sharp than `threshold`. Blank pages have no
+	/// edges to be sharp, so they are never blurry.
+	pub fn is_blurry(&self, threshold: f32) -> bool {
+		self.blankness() == Blankness::Content && self.sharpness < threshold
+	}
+}
+
+/// Blankness of a page, returned by `analyze_blank_pages`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct PageBlankness {
+	pub(crate) path: String,
+	pub(crate) ink_coverage: f32,
+	pub(crate) luminance_std_dev: f32,
+	pub(crate) blankness: Blankness,
+}
+
+impl PageBlankness {
+	pub fn new(path: &Path, analysis: &PageAnalysis) -> Self {
+		Self {
+			path: path.to_string_lossy().to_string(),
+			ink_coverage: analysis.ink_coverage,
+			luminance_std_dev: analysis.luminance_std_dev,
+			blankness: analysis.blankness(),
+		}
+	}
+}
+
+/// Sharpness of a page, returned by `analyze_sharpness`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct PageSharpness {
+	pub(crate) path: String,
+	pub(crate) sharpness: f32,
+	pub(crate) blurry: bool,
+}
+
+impl PageSharpness {
+	pub fn new(path: &Path, analysis: &PageAnalysis, threshold: f32) -> Self {
+		Self {
+			path: path.to_string_lossy().to_string(),
+			sharpness: analysis.sharpness,
+			blurry: analysis.is_blurry(threshold),
+		}
+	}
+}
+
+pub fn set_sharpness_threshold(threshold: f32) -> Result<(), String> {
+	if !threshold.is_finite() || threshold < 0.0 {
+		return Err("Invalid sharpness threshold. Must be 0 or greater.".to_string());
+	}
+	let mut sharpness_threshold = SHARPNESS_THRESHOLD.lock().map_err(|e| e.to_string())?;
+	*sharpness_threshold = threshold;
+	Ok(())
+}
+
+pub(crate) fn get_sharpness_threshold() -> f32 {
+	SHARPNESS_THRESHOLD
+		.lock()
+		.map(|threshold| *threshold)
+		.unwrap_or(DEFAULT_SHARPNESS_THRESHOLD)
+}
+
+/// Blankness of every image in the directory, in the order the images are listed
+pub fn analyze_blank_pages<P: AsRef<Path>>(
+	directory_path: P,
+) -> Result<Vec<PageBlankness>, ImageConversionError> {
+	Ok(analyze_directory(directory_path)?
+		.iter()
+		.map(|(file, analysis)| PageBlankness::new(file, analysis))
+		.collect())
+}
+
+/// Sharpness of every image in the directory, in the order the images are listed, with pages
+/// less sharp than `threshold` marked as blurry
+pub fn analyze_sharpness<P: AsRef<Path>>(
+	directory_path: P,
+	threshold: f32,
+) -> Result<Vec<PageSharpness>, ImageConversionError> {
+	Ok(analyze_directory(directory_path)?
+		.iter()
+		.map(|(file, analysis)| PageSharpness::new(file, analysis, threshold))
+		.collect())
+}
+
+/// Analysis of every image in the directory, in the order the images are listed. Images that
+/// cannot be analysed are left out.
+pub(crate) fn analyze_directory<P: AsRef<Path>>(
+	directory_path: P,
+) -> Result<Vec<(PathBuf, PageAnalysis)>, ImageConversionError> {
+	let files = file_utils::list_image_files(directory_path, false)?;
+	let analyses = analyze_files(&files)?;
+	Ok(files
+		.into_iter()
+		.zip(analyses)
+		.filter_map(|(file, analysis)| Some((file, analysis?)))
+		.collect())
+}
+
+/// Analysis of every file, `None` for files that cannot be analysed. Uses the analysis stored
+/// with the thumbnails, and makes the thumbnails that are missing or stale.
+pub(crate) fn analyze_files<P: AsRef<Path> + Sync>(
+	files: &[P],
+) -> Result<Vec<Option<PageAnalysis>>, ImageConversionError> {
+	let results = image_converter::conversion_pool()?
+		.install(|| files.par_iter().map(analyze_page).collect::<Vec<_>>());
+
+	Ok(results
+		.into_iter()
+		.zip(files)
+		.map(|(result, file)| {
+			result
+				.map_err(|e| {
+					add_breadcrumb(Breadcrumb {
+						category: Some("page_analysis".into()),
+						message: Some(format!("Failed to analyse {:?}: {e}", file.as_ref())),
+						level: Level::Error,
+						..Default::default()
+					});
+				})
+				.ok()
+		})
+		.collect())
+}
+
+/// The analysis stored with the image's thumbnail, made along with the thumbnail if it is
+/// missing or stale
+pub fn analyze_page<P: AsRef<Path>>(image_path: P) -> Result<PageAnalysis, ImageConversionError> {
+	let path_reference = image_path.as_ref();
+	if let Some(analysis) = stored_analysis(path_reference)? {
+		return Ok(analysis);
+	}
+	image_converter::convert_to_webp_derivatives(path_reference, true, false)?;
+	stored_analysis(path_reference)?.ok_or_else(|| {
+		ImageConversionError::StrError(format!(
+			"No page analysis stored for {}",
+			path_reference.display()
+		))
+	})
+}
+
+fn stored_analysis(image_path: &Path) -> Result<Option<PageAnalysis>, ImageConversionError> {
+	let thumbnail = derivative_cache::derivative_path(image_path, false)
+		.map_err(ImageConversionError::StrError)?;
+	let profile = image_converter::get_conversion_profile(false).fingerprint();
+	Ok(
+		derivative_cache::current_stamp(image_path, &thumbnail, &profile)
+			.and_then(|stamp| stamp.analysis),
+	)
+}
+
+pub(crate) fn analyze(image: &DynamicImage) -> PageAnalysis {
+	let copy = analysis_copy(image);
+	let (ink_coverage, luminance_std_dev) = measure_ink(&copy);
+	PageAnalysis {
+		ink_coverage,
+		luminance_std_dev,
+		sharpness: laplacian_variance(&copy),
+		perceptual_hash: difference_hash(&copy),
+	}
+}
+
+/// Small grayscale copy of the page, without its margins, that the measurements are made on
+fn analysis_copy(image: &DynamicImage) -> GrayImage {
+	let size = TargetSize::LongEdge {
+		pixels: ANALYSIS_LONG_EDGE,
+	};
+	let (width, height) = size.dimensions(image.width(), image.height());
+	let copy = image.thumbnail_exact(width, height).to_luma8();
+
+	let margin_x = (width as f32 * MARGIN_FRACTION) as u32;
+	let margin_y = (height as f32 * MARGIN_FRACTION) as u32;
+	imageops::crop_imm(
+		&copy,
+		margin_x,
+		margin_y,
+		(width - 2 * margin_x).max(1),
+		(height - 2 * margin_y).max(1),
+	)
+	.to_image()
+}
+
+/// Share of the page that is ink, and the standard deviation of its luminance
+fn measure_ink(image: &GrayImage) -> (f32, f32) {
+	let mut histogram = [0u64; 256];
+	for pixel in image.pixels() {
+		histogram[pixel.0[0] as usize] += 1;
+	}
+	let total = image.pixels().len().max(1) as u64;
+
+	let paper_rank = (total as f32 * PAPER_PERCENTILE) as u64;
+	let mut below = 0;
+	let paper = histogram
+		.iter()
+		.position(|&count| {
+			below += count;
+			below > paper_rank
+		})
+		.unwrap_or(u8::MAX as usize) as u8;
+	let ink: u64 = histogram[..paper.saturating_sub(INK_CONTRAST) as usize]
+		.iter()
+		.sum();
+
+	let mean = histogram
+		.iter()
+		.enumerate()
+		.map(|(level, &count)| level as f64 * count as f64)
+		.sum::<f64>()
+		/ total as f64;
+	let variance = histogram
+		.iter()
+		.enumerate()
+		.map(|(level, &count)| (level as f64 - mean).powi(2) * count as f64)
+		.sum::<f64>()
+		/ total as f64;
+
+	(
+		ink as f32 / total as f32,
+		(variance.sqrt() / u8::MAX as f64) as f32,
+	)
+}
+
+/// Variance of the 4-neighbour Laplacian over the inner pixels of the image
+fn laplacian_variance(image: &GrayImage) -> f32 {
+	let (width, height) = image.dimensions();
+	if width < 3 || height < 3 {
+		return 0.0;
+	}
+	let luma = |x: u32, y: u32| image.get_pixel(x, y).0[0] as f64;
+
+	let (mut sum, mut sum_of_squares) = (0.0, 0.0);
+	for y in 1..height - 1 {
+		for x in 1..width - 1 {
+			let laplacian = luma(x - 1, y) + luma(x + 1, y) + luma(x, y - 1) + luma(x, y + 1)
+				- 4.0 * luma(x, y);
+			sum += laplacian;
+			sum_of_squares += laplacian * laplacian;
+		}
+	}
+	let count = ((width - 2) * (height - 2)) as f64;
+	let mean = sum / count;
+	(sum_of_squares / count - mean * mean) as f32
+}
+
+/// 64-bit difference hash: the image shrunk to 9 by 8 pixels, one bit per pixel that is darker
+/// than its right neighbour. Robust to scaling, compression and small changes in exposure.
+fn difference_hash(image: &GrayImage) -> u64 {
+	let small = imageops::resize(image, 9, 8, FilterType::Triangle);
+	let mut hash = 0;
+	for y in 0..8 {
+		for x in 0..8 {
+			let darker = small.get_pixel(x, y).0[0] < small.get_pixel(x + 1, y).0[0];
+			hash = (hash << 1) | u64::from(darker);
+		}
+	}
+	hash
+}
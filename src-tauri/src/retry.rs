@@ -0,0 +1,221 @@
+use crate::error::UploadError;
+use crate::s3::CancellationFlag;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use once_cell::sync::Lazy;
+use sentry::{Breadcrumb, Level, add_breadcrumb};
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_ATTEMPTS_LIMIT: u32 = 10;
+const MAX_BACKOFF_LIMIT: Duration = Duration::from_secs(300);
+/// How often a sleeping retry checks whether the upload has been cancelled
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Error codes S3 (and compatible stores) use for requests that may succeed if sent again
+const RETRYABLE_ERROR_CODES: &[&str] = &[
+	"InternalError",
+	"RequestTimeout",
+	"ServiceUnavailable",
+	"SlowDown",
+	"Throttling",
+	"ThrottlingException",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RetryPolicy {
+	/// Total number of attempts, including the first one
+	pub(crate) max_attempts: u32,
+	pub(crate) initial_backoff: Duration,
+	pub(crate) max_backoff: Duration,
+	/// Waits a random time between half and all of the backoff, so that parts failing
+	/// together are not retried in lockstep
+	pub(crate) jitter: bool,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: DEFAULT_MAX_ATTEMPTS,
+			initial_backoff: DEFAULT_INITIAL_BACKOFF,
+			max_backoff: DEFAULT_MAX_BACKOFF,
+			jitter: true,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Time to wait after the failed attempt number `attempt` (starting at 1). The backoff doubles
+	/// for every attempt up to `max_backoff`. `jitter_fraction` in `0.0..=1.0` selects where in
+	/// the jitter range the delay ends up, and is ignored if jitter is turned off.
+	pub(crate) fn backoff(&self, attempt: u32, jitter_fraction: f64) -> Duration {
+		let exponent = attempt.saturating_sub(1).min(31);
+		let backoff = self
+			.initial_backoff
+			.saturating_mul(1 << exponent)
+			.min(self.max_backoff);
+		if self.jitter {
+			backoff.div_f64(2.0)
+				+ backoff
+					.div_f64(2.0)
+					.mul_f64(jitter_fraction.clamp(0.0, 1.0))
+		} else {
+			backoff
+		}
+	}
+}
+
+static RETRY_POLICY: Lazy<Mutex<RetryPolicy>> = Lazy::new(|| Mutex::new(RetryPolicy::default()));
+
+pub fn set_retry_policy(
+	max_attempts: u32,
+	initial_backoff_ms: u64,
+	max_backoff_ms: u64,
+	jitter: bool,
+) -> Result<(), String> {
+	if !(1..=MAX_ATTEMPTS_LIMIT).contains(&max_attempts) {
+		return Err(format!(
+			"Invalid number of attempts. Must be between 1 and {MAX_ATTEMPTS_LIMIT}."
+		));
+	}
+	let initial_backoff = Duration::from_millis(initial_backoff_ms);
+	let max_backoff = Duration::from_millis(max_backoff_ms);
+	if initial_backoff > max_backoff || max_backoff > MAX_BACKOFF_LIMIT {
+		return Err(format!(
+			"Invalid backoff. The initial backoff must not exceed the maximum backoff, which must be at most {} ms.",
+			MAX_BACKOFF_LIMIT.as_millis()
+		));
+	}
+
+	let mut policy = RETRY_POLICY.lock().map_err(|e| e.to_string())?;
+	*policy = RetryPolicy {
+		max_attempts,
+		initial_backoff,
+		max_backoff,
+		jitter,
+	};
+	Ok(())
+}
+
+pub(crate) fn get_retry_policy() -> RetryPolicy {
+	RETRY_POLICY.lock().map(|p| *p).unwrap_or_default()
+}
+
+/// Failure of a single attempt, telling `with_retry` whether it is worth trying again
+#[derive(Debug)]
+pub(crate) enum AttemptError {
+	Retryable(String),
+	Permanent(UploadError),
+}
+
+impl AttemptError {
+	/// Classifies an error from the S3 client. `context` describes the request that failed.
+	pub(crate) fn from_sdk<E>(context: &str, error: SdkError<E, HttpResponse>) -> Self
+	where
+		E: ProvideErrorMetadata + Debug,
+	{
+		let retryable = is_retryable(&error);
+		let message = format!("{context}: {error:?}");
+		if retryable {
+			AttemptError::Retryable(message)
+		} else {
+			AttemptError::Permanent(UploadError::Failed(message))
+		}
+	}
+}
+
+impl From<UploadError> for AttemptError {
+	fn from(error: UploadError) -> Self {
+		AttemptError::Permanent(error)
+	}
+}
+
+impl From<String> for AttemptError {
+	fn from(message: String) -> Self {
+		AttemptError::Permanent(UploadError::Failed(message))
+	}
+}
+
+/// Timeouts, connection failures, throttling and server errors are retryable. Everything else,
+/// such as access denied or a missing bucket, fails the same way when sent again.
+pub(crate) fn is_retryable<E: ProvideErrorMetadata>(error: &SdkError<E, HttpResponse>) -> bool {
+	match error {
+		SdkError::TimeoutError(_) | SdkError::ResponseError(_) => true,
+		SdkError::DispatchFailure(failure) => !failure.is_user(),
+		SdkError::ServiceError(service_error) => {
+			let status = service_error.raw().status().as_u16();
+			status >= 500
+				|| status == 408
+				|| status == 429
+				|| service_error
+					.err()
+					.code()
+					.is_some_and(|code| RETRYABLE_ERROR_CODES.contains(&code))
+		}
+		_ => false,
+	}
+}
+
+/// Runs `attempt` until it succeeds, fails permanently, or `policy.max_attempts` is used up,
+/// sleeping between attempts. Every retry is recorded as a Sentry breadcrumb. Stops early if the
+/// upload is cancelled while waiting.
+pub(crate) async fn with_retry<T, F, Fut>(
+	policy: &RetryPolicy,
+	description: &str,
+	cancellation: &CancellationFlag,
+	mut attempt: F,
+) -> Result<T, UploadError>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T, AttemptError>>,
+{
+	let max_attempts = policy.max_attempts.max(1);
+	let mut attempt_nr = 1;
+	loop {
+		cancellation.check()?;
+		let message = match attempt().await {
+			Ok(output) => return Ok(output),
+			Err(AttemptError::Permanent(e)) => return Err(e),
+			Err(AttemptError::Retryable(message)) => message,
+		};
+		if attempt_nr >= max_attempts {
+			return Err(UploadError::Failed(format!(
+				"{message} (gave up after {attempt_nr} attempts)"
+			)));
+		}
+
+		let delay = policy.backoff(attempt_nr, fastrand::f64());
+		add_breadcrumb(Breadcrumb {
+			category: Some("s3".into()),
+			message: Some(format!(
+				"Retrying {description} in {} ms (attempt {} of {max_attempts}): {message}",
+				delay.as_millis(),
+				attempt_nr + 1
+			)),
+			level: Level::Warning,
+			..Default::default()
+		});
+		sleep_unless_cancelled(delay, cancellation).await?;
+		attempt_nr += 1;
+	}
+}
+
+async fn sleep_unless_cancelled(
+	delay: Duration,
+	cancellation: &CancellationFlag,
+) -> Result<(), UploadError> {
+	let deadline = Instant::now() + delay;
+	loop {
+		cancellation.check()?;
+		let remaining = deadline.saturating_duration_since(Instant::now());
+		if remaining.is_zero() {
+			return Ok(());
+		}
+		tokio::time::sleep(remaining.min(CANCELLATION_POLL_INTERVAL)).await;
+	}
+}
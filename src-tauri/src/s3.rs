@@ -1,46 +1,235 @@
 #[cfg(not(feature = "debug-mock"))]
 use crate::HashMap;
 #[cfg(not(feature = "debug-mock"))]
-use crate::file_utils::get_file_paths_in_directory;
+use crate::batch_validation::{get_max_batch_upload_size, validate_batch};
+#[cfg(not(feature = "debug-mock"))]
+use crate::checksum::{self, Sha256Digest};
+#[cfg(not(feature = "debug-mock"))]
+use crate::error::UploadError;
+#[cfg(not(feature = "debug-mock"))]
+use crate::file_utils::{get_file_paths_in_directory, get_file_stability, unfinished_files};
+#[cfg(not(feature = "debug-mock"))]
+use crate::key_layout::{KeyLayout, PageKey, get_key_layout, today};
+#[cfg(not(feature = "debug-mock"))]
+use crate::manifest::{ManifestBatch, build_manifest, now_rfc3339, upload_manifest};
 #[cfg(not(feature = "debug-mock"))]
-use crate::get_secret_variables;
 use crate::model::BatchRepresentation;
 #[cfg(not(feature = "debug-mock"))]
-use crate::model::PutObjectRequest;
+use crate::model::{
+	BatchPage, MultipartUploadRequest, PutObjectRequest, UploadJob, UploadPartRequest,
+};
+#[cfg(not(feature = "debug-mock"))]
+use crate::model::{SecretVariables, TransferProgress, UploadSummary, UploadedObject};
+#[cfg(not(feature = "debug-mock"))]
+use crate::orientation_sidecar;
+#[cfg(not(feature = "debug-mock"))]
+use crate::retry::{AttemptError, get_retry_policy, with_retry};
+#[cfg(not(feature = "debug-mock"))]
+use crate::upload_journal::{FileVersion, JournalPart, UploadJournal};
 #[cfg(not(feature = "debug-mock"))]
-use crate::model::{SecretVariables, TransferProgress};
+use crate::upload_progress::UploadProgress;
+#[cfg(not(feature = "debug-mock"))]
+use crate::{get_hostname, get_secret_variables};
 #[cfg(not(feature = "debug-mock"))]
 use aws_sdk_s3::Client;
 #[cfg(not(feature = "debug-mock"))]
+use aws_sdk_s3::config::retry::RetryConfig;
+#[cfg(not(feature = "debug-mock"))]
 use aws_sdk_s3::config::{Credentials, Region};
 #[cfg(not(feature = "debug-mock"))]
-use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+#[cfg(not(feature = "debug-mock"))]
+use aws_sdk_s3::operation::complete_multipart_upload::CompleteMultipartUploadOutput;
+#[cfg(not(feature = "debug-mock"))]
+use aws_sdk_s3::types::{ChecksumAlgorithm, CompletedMultipartUpload, CompletedPart};
+#[cfg(not(feature = "debug-mock"))]
+use bytes::Bytes;
 #[cfg(not(feature = "debug-mock"))]
-use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use once_cell::sync::Lazy;
 #[cfg(not(feature = "debug-mock"))]
 use sentry::{Breadcrumb, Level, add_breadcrumb, capture_message};
 #[cfg(not(feature = "debug-mock"))]
+use std::collections::HashSet;
+#[cfg(not(feature = "debug-mock"))]
+use std::io::SeekFrom;
+#[cfg(not(feature = "debug-mock"))]
 use std::path::Path;
 #[cfg(not(feature = "debug-mock"))]
 use std::path::PathBuf;
 #[cfg(not(feature = "debug-mock"))]
-use tauri::{Emitter, Window};
+use std::sync::Mutex;
+#[cfg(not(feature = "debug-mock"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(feature = "debug-mock"))]
+use std::sync::{Arc, MutexGuard};
+#[cfg(not(feature = "debug-mock"))]
+use std::time::{Duration, SystemTime};
+#[cfg(not(feature = "debug-mock"))]
+use tauri::{AppHandle, Emitter, Manager, Window};
 #[cfg(not(feature = "debug-mock"))]
 use tokio::sync::OnceCell;
 #[cfg(not(feature = "debug-mock"))]
+use tokio::task::JoinSet;
+#[cfg(not(feature = "debug-mock"))]
 use tokio::{
 	fs::File,
-	io::{AsyncReadExt, BufReader},
+	io::{AsyncReadExt, AsyncSeekExt},
 };
 
-const MULTIPART_PART_SIZE: usize = 16 * 1024 * 1024; // 16 MiB (server limit)
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) const MULTIPART_PART_SIZE: usize = 16 * 1024 * 1024; // 16 MiB (server limit)
+#[cfg(not(feature = "debug-mock"))]
+const UPLOAD_JOURNAL_FOLDER_NAME: &str = "upload_journal";
+/// Journals not written for this long belong to uploads that were given up, such as failed
+/// uploads that were registered again under a new object id
+#[cfg(not(feature = "debug-mock"))]
+const UPLOAD_JOURNAL_TTL: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+/// Error code S3 answers with for a multipart upload that was aborted or has expired
+#[cfg(not(feature = "debug-mock"))]
+const NO_SUCH_UPLOAD: &str = "NoSuchUpload";
+#[cfg(not(feature = "debug-mock"))]
+const DEFAULT_CONCURRENT_OBJECTS: usize = 4;
+#[cfg(not(feature = "debug-mock"))]
+const DEFAULT_CONCURRENT_PARTS: usize = 2;
+#[cfg(not(feature = "debug-mock"))]
+const MIN_CONCURRENCY: usize = 1;
+#[cfg(not(feature = "debug-mock"))]
+const MAX_CONCURRENCY: usize = 16;
+
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct UploadConcurrency {
+	pub(crate) max_concurrent_objects: usize,
+	pub(crate) max_concurrent_parts: usize,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+static UPLOAD_CONCURRENCY: Lazy<Mutex<UploadConcurrency>> = Lazy::new(|| {
+	Mutex::new(UploadConcurrency {
+		max_concurrent_objects: DEFAULT_CONCURRENT_OBJECTS,
+		max_concurrent_parts: DEFAULT_CONCURRENT_PARTS,
+	})
+});
+
+#[cfg(not(feature = "debug-mock"))]
+pub fn set_upload_concurrency(
+	max_concurrent_objects: usize,
+	max_concurrent_parts: usize,
+) -> Result<(), String> {
+	let valid_range = MIN_CONCURRENCY..=MAX_CONCURRENCY;
+	if !valid_range.contains(&max_concurrent_objects)
+		|| !valid_range.contains(&max_concurrent_parts)
+	{
+		return Err(format!(
+			"Invalid upload concurrency. Both must be between {} and {}.",
+			MIN_CONCURRENCY, MAX_CONCURRENCY
+		));
+	}
+
+	let mut concurrency = UPLOAD_CONCURRENCY.lock().map_err(|e| e.to_string())?;
+	concurrency.max_concurrent_objects = max_concurrent_objects;
+	concurrency.max_concurrent_parts = max_concurrent_parts;
+	Ok(())
+}
+
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) fn get_upload_concurrency() -> UploadConcurrency {
+	UPLOAD_CONCURRENCY
+		.lock()
+		.map(|c| *c)
+		.unwrap_or(UploadConcurrency {
+			max_concurrent_objects: DEFAULT_CONCURRENT_OBJECTS,
+			max_concurrent_parts: DEFAULT_CONCURRENT_PARTS,
+		})
+}
+
+/// Shared flag telling the tasks of one upload job to stop before starting another part
+#[cfg(not(feature = "debug-mock"))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CancellationFlag(Arc<AtomicBool>);
+
+#[cfg(not(feature = "debug-mock"))]
+impl CancellationFlag {
+	pub(crate) fn cancel(&self) {
+		self.0.store(true, Ordering::SeqCst);
+	}
+
+	pub(crate) fn is_cancelled(&self) -> bool {
+		self.0.load(Ordering::SeqCst)
+	}
+
+	pub(crate) fn check(&self) -> Result<(), UploadError> {
+		if self.is_cancelled() {
+			Err(UploadError::Cancelled)
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// Uploads that are currently running, by the job id the frontend started them with
+#[cfg(not(feature = "debug-mock"))]
+static UPLOAD_JOBS: Lazy<Mutex<HashMap<String, CancellationFlag>>> =
+	Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Registration of a running upload job, removed from `UPLOAD_JOBS` when dropped
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) struct RegisteredUploadJob {
+	job_id: String,
+	pub(crate) cancellation: CancellationFlag,
+}
+
+#[cfg(not(feature = "debug-mock"))]
+impl Drop for RegisteredUploadJob {
+	fn drop(&mut self) {
+		if let Ok(mut jobs) = UPLOAD_JOBS.lock() {
+			jobs.remove(&self.job_id);
+		}
+	}
+}
+
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) fn register_upload_job(job_id: &str) -> Result<RegisteredUploadJob, String> {
+	let mut jobs = UPLOAD_JOBS.lock().map_err(|e| e.to_string())?;
+	if jobs.contains_key(job_id) {
+		return Err(format!("Upload job {job_id} is already running"));
+	}
+	let cancellation = CancellationFlag::default();
+	jobs.insert(job_id.to_string(), cancellation.clone());
+	Ok(RegisteredUploadJob {
+		job_id: job_id.to_string(),
+		cancellation,
+	})
+}
+
+/// Asks the upload job to stop. Parts already being sent are finished, open multipart uploads
+/// are aborted and the job returns `UploadError::Cancelled`. Returns `false` if no job with
+/// that id is running.
+#[cfg(not(feature = "debug-mock"))]
+pub fn cancel_upload(job_id: &str) -> Result<bool, String> {
+	let jobs = UPLOAD_JOBS.lock().map_err(|e| e.to_string())?;
+	let Some(cancellation) = jobs.get(job_id) else {
+		return Ok(false);
+	};
+	cancellation.cancel();
+
+	add_breadcrumb(Breadcrumb {
+		category: Some("s3".into()),
+		message: Some(format!("Cancelling upload job {job_id}")),
+		level: Level::Info,
+		..Default::default()
+	});
+	Ok(true)
+}
 
 #[cfg(not(feature = "debug-mock"))]
 pub(crate) async fn upload_directory(
 	directory_path: &str,
 	object_id: &str,
+	job_id: &str,
 	app_window: Window,
-) -> Result<usize, String> {
+) -> Result<UploadSummary, UploadError> {
+	let job = register_upload_job(job_id)?;
 	let secret_variables = get_secret_variables()
 		.await
 		.map_err(|e| format!("Failed to get secret variables: {e}"))?;
@@ -49,44 +238,91 @@ pub(crate) async fn upload_directory(
 		.await
 		.map_err(|e| format!("Failed to get S3 client: {e}"))?;
 
+	let mut journal = open_journal(&app_window, object_id)?;
+	let upload_date = journal
+		.upload_date(&today())
+		.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+	journal
+		.set_directory(Path::new(directory_path))
+		.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+	let journal = Arc::new(Mutex::new(journal));
+
+	let key_layout = get_key_layout();
 	let file_paths = get_file_paths_in_directory(directory_path)?;
-	for (index, file_path) in file_paths.iter().enumerate() {
-		let meta = tokio::fs::metadata(file_path.clone())
+	let check_paths = file_paths.clone();
+	let unfinished =
+		tokio::task::spawn_blocking(move || unfinished_files(&check_paths, &get_file_stability()))
 			.await
-			.map_err(|e| format!("stat failed for {}: {e}", file_path.display()))?;
-		let file_size = meta.len() as usize;
+			.map_err(|e| format!("Failed to run blocking task: {e}"))?;
+	if let Some((_, reason)) = unfinished.into_iter().next() {
+		return Err(UploadError::Failed(reason));
+	}
+	let mut jobs = Vec::with_capacity(file_paths.len());
+	for (index, file_path) in file_paths.iter().enumerate() {
+		jobs.push(UploadJob {
+			key: key_layout.directory_key(&PageKey {
+				object_id,
+				representation: None,
+				page_nr: index + 1,
+				extension: file_extension(file_path)?,
+				material_type: None,
+				date: &upload_date,
+			})?,
+			version: get_file_version(file_path).await?,
+			path: file_path.clone(),
+			journal: journal.clone(),
+		});
+	}
 
-		let page_nr = index + 1;
-		put_object(PutObjectRequest {
-			client,
-			secret_variables,
-			path: file_path,
-			object_id,
-			page_nr,
-			file_size,
-			representation_type: None,
-		})
-		.await?;
+	let progress = transfer_progress(&app_window, directory_path.to_string(), &jobs);
+	let upload_dates = HashMap::from([(object_id.to_string(), upload_date)]);
+	let summary = upload_jobs(
+		client,
+		secret_variables,
+		jobs,
+		upload_dates,
+		&job.cancellation,
+		progress,
+	)
+	.await?;
 
-		app_window
-			.emit(
-				"transfer_progress",
-				TransferProgress {
-					directory: directory_path.to_string(),
-					page_nr,
-					total_pages: file_paths.len(),
-				},
-			)
-			.map_err(|e| e.to_string())?;
-	}
-	Ok(file_paths.len())
+	remove_journal(&journal)?;
+	Ok(summary)
 }
 
 #[cfg(not(feature = "debug-mock"))]
 pub(crate) async fn upload_batch_to_s3(
 	batch_map: HashMap<String, BatchRepresentation>,
+	material_type: Option<String>,
+	job_id: &str,
 	app_window: Window,
-) -> Result<usize, String> {
+) -> Result<UploadSummary, UploadError> {
+	let job = register_upload_job(job_id)?;
+
+	// Nothing is sent unless every file of every batch can be uploaded
+	let (batch_map, report) = tokio::task::spawn_blocking(move || {
+		let report = validate_batch(
+			&batch_map,
+			get_max_batch_upload_size(),
+			&get_file_stability(),
+		);
+		(batch_map, report)
+	})
+	.await
+	.map_err(|e| format!("Failed to run blocking task: {e}"))?;
+	if !report.valid {
+		add_breadcrumb(Breadcrumb {
+			category: Some("s3".into()),
+			message: Some(format!(
+				"Upload rejected by validation with {} problems",
+				report.problems.len()
+			)),
+			level: Level::Warning,
+			..Default::default()
+		});
+		return Err(UploadError::Invalid(report));
+	}
+
 	let secret_variables = get_secret_variables()
 		.await
 		.map_err(|e| format!("Failed to get secret variables: {e}"))?;
@@ -94,12 +330,6 @@ pub(crate) async fn upload_batch_to_s3(
 		.await
 		.map_err(|e| format!("Failed to get S3 client: {e}"))?;
 
-	let mut uploaded_count = 0;
-	let total_files: usize = batch_map
-		.values()
-		.map(|batch| batch.primary.len() + batch.access.len())
-		.sum();
-
 	add_breadcrumb(Breadcrumb {
 		category: Some("s3".into()),
 		message: Some("Started s3 upload".into()),
@@ -107,206 +337,924 @@ pub(crate) async fn upload_batch_to_s3(
 		..Default::default()
 	});
 
-	let progress_directory_string = if !batch_map.values().next().unwrap().access.is_empty() {
-		&batch_map.values().next().unwrap().access[0]
-	} else {
-		&batch_map.values().next().unwrap().primary[0]
-	};
-
-	let progress_directory = Path::new(progress_directory_string)
-		.parent()
+	let progress_directory = batch_map
+		.values()
+		.find_map(|batch| batch.access.first().or(batch.primary.first()))
+		.and_then(|path| Path::new(path).parent())
 		.map(|p| p.to_string_lossy().to_string())
 		.unwrap_or_default();
 
+	let mut journals = Vec::with_capacity(batch_map.len());
+	let mut batches = Vec::with_capacity(batch_map.len());
+	let mut upload_dates = HashMap::with_capacity(batch_map.len());
+	let mut jobs = Vec::with_capacity(report.file_count);
+	let key_layout = get_key_layout();
 	for (batch_id, batch) in batch_map.iter() {
-		let prefixed_batch_id = format!("tekst_{}", batch_id);
+		let object_id = key_layout.object_id(batch_id, material_type.as_deref())?;
+		let mut journal = open_journal(&app_window, &object_id)?;
+		let upload_date = journal
+			.upload_date(&today())
+			.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+		if let Some(directory) = batch.primary.first().and_then(|p| Path::new(p).parent()) {
+			journal
+				.set_directory(directory)
+				.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+		}
+		let journal = Arc::new(Mutex::new(journal));
+
+		let pages = batch_object_keys(
+			&key_layout,
+			&object_id,
+			batch,
+			material_type.as_deref(),
+			&upload_date,
+		)?;
+		for page in &pages {
+			jobs.push(UploadJob {
+				key: page.key.clone(),
+				version: get_file_version(&page.path).await?,
+				path: page.path.clone(),
+				journal: journal.clone(),
+			});
+		}
+		journals.push(journal);
+		upload_dates.insert(object_id.clone(), upload_date);
+		batches.push((batch_id, object_id, pages));
+	}
 
-		for (files, rep_type) in [(&batch.primary, "primary"), (&batch.access, "access")] {
-			for (file_index, file_path_str) in files.iter().enumerate() {
-				let page_nr = file_index + 1;
-				let file_path = PathBuf::from(file_path_str);
+	let progress = transfer_progress(&app_window, progress_directory, &jobs);
+	let summary = upload_jobs(
+		client,
+		secret_variables,
+		jobs,
+		upload_dates,
+		&job.cancellation,
+		progress,
+	)
+	.await?;
 
-				let meta = tokio::fs::metadata(file_path.clone())
-					.await
-					.map_err(|e| format!("stat failed for {}: {e}", file_path.display()))?;
-				let file_size = meta.len() as usize;
+	// Uploaded last, so a manifest in the bucket means every page it lists is there too
+	let hostname = get_hostname().unwrap_or_else(|name| name.to_string_lossy().to_string());
+	let uploaded_at = now_rfc3339();
+	for (batch_id, object_id, pages) in batches {
+		let manifest = build_manifest(
+			ManifestBatch {
+				object_id: &object_id,
+				batch_id,
+				material_type: material_type.as_deref(),
+				hostname: &hostname,
+				uploaded_at: &uploaded_at,
+			},
+			&pages,
+			&summary.objects,
+		)?;
+		let key = key_layout.manifest_key(
+			&object_id,
+			material_type.as_deref(),
+			&summary.upload_dates[&object_id],
+		)?;
+		upload_manifest(client, secret_variables, &key, &manifest, &job.cancellation).await?;
+	}
 
+	for journal in journals {
+		remove_journal(&journal)?;
+	}
+	capture_message("Finished uploading to S3", Level::Info);
+	Ok(summary)
+}
+
+/// Reports the progress of `jobs` to the window as `transfer_progress` events
+#[cfg(not(feature = "debug-mock"))]
+fn transfer_progress(
+	app_window: &Window,
+	directory: String,
+	jobs: &[UploadJob],
+) -> Arc<UploadProgress> {
+	let window = app_window.clone();
+	let total_bytes = jobs.iter().map(|job| job.version.size).sum();
+	Arc::new(UploadProgress::new(
+		directory,
+		jobs.len(),
+		total_bytes,
+		move |progress: TransferProgress| {
+			let _ = window.emit("transfer_progress", progress);
+		},
+	))
+}
+
+/// Uploads every job exactly once, with up to the configured number of objects in flight.
+/// `progress` is told about every object and part as S3 confirms it.
+#[cfg(not(feature = "debug-mock"))]
+async fn upload_jobs(
+	client: &'static Client,
+	secret_variables: &'static SecretVariables,
+	jobs: Vec<UploadJob>,
+	upload_dates: HashMap<String, String>,
+	cancellation: &CancellationFlag,
+	progress: Arc<UploadProgress>,
+) -> Result<UploadSummary, UploadError> {
+	ensure_unique_keys(&jobs)?;
+
+	let concurrency = get_upload_concurrency();
+	let total_objects = jobs.len();
+	let mut objects = Vec::with_capacity(total_objects);
+	let result = run_bounded(
+		jobs,
+		concurrency.max_concurrent_objects,
+		|job| {
+			let cancellation = cancellation.clone();
+			let progress = progress.clone();
+			async move {
 				put_object(PutObjectRequest {
 					client,
 					secret_variables,
-					path: &file_path,
-					object_id: &prefixed_batch_id,
-					page_nr,
-					file_size,
-					representation_type: Some(rep_type),
+					path: &job.path,
+					key: &job.key,
+					version: job.version,
+					journal: &job.journal,
+					max_concurrent_parts: concurrency.max_concurrent_parts,
+					cancellation: &cancellation,
+					progress: &progress,
 				})
-				.await?;
-				uploaded_count += 1;
-
-				app_window
-					.emit(
-						"transfer_progress",
-						TransferProgress {
-							directory: progress_directory.clone(),
-							page_nr: uploaded_count,
-							total_pages: total_files,
-						},
-					)
-					.map_err(|e| e.to_string())?;
+				.await
 			}
+		},
+		|object| {
+			objects.push(object);
+			progress.page_done();
+			Ok(())
+		},
+	)
+	.await;
+
+	if let Err(UploadError::Cancelled) = result {
+		add_breadcrumb(Breadcrumb {
+			category: Some("s3".into()),
+			message: Some(format!(
+				"Upload cancelled after {} of {} objects",
+				objects.len(),
+				total_objects
+			)),
+			level: Level::Info,
+			..Default::default()
+		});
+	}
+	result?;
+
+	objects.sort_by(|a, b| a.key.cmp(&b.key));
+	Ok(UploadSummary {
+		uploaded_count: objects.len(),
+		objects,
+		upload_dates,
+	})
+}
+
+/// Runs `task` for every job with at most `limit` tasks in flight at the same time.
+/// `on_complete` is called from the calling task in the order the tasks finish. After the
+/// first error no new tasks are started; the ones already running are allowed to finish
+/// (so they can clean up after themselves) before that error is returned.
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn run_bounded<T, R, E, F, Fut>(
+	jobs: Vec<T>,
+	limit: usize,
+	task: F,
+	mut on_complete: impl FnMut(R) -> Result<(), E>,
+) -> Result<(), E>
+where
+	F: Fn(T) -> Fut,
+	Fut: Future<Output = Result<R, E>> + Send + 'static,
+	R: Send + 'static,
+	E: From<String> + Send + 'static,
+{
+	let mut pending = jobs.into_iter();
+	let mut running = JoinSet::new();
+	let mut first_error = None;
+
+	loop {
+		while first_error.is_none()
+			&& running.len() < limit.max(1)
+			&& let Some(job) = pending.next()
+		{
+			running.spawn(task(job));
+		}
+
+		let Some(result) = running.join_next().await else {
+			return first_error.map_or(Ok(()), Err);
+		};
+		let outcome = match result {
+			Ok(Ok(output)) => on_complete(output),
+			Ok(Err(e)) => Err(e),
+			Err(e) => Err(E::from(format!("Upload task failed: {e}"))),
+		};
+		if let Err(e) = outcome
+			&& first_error.is_none()
+		{
+			first_error = Some(e);
 		}
 	}
-	capture_message("Finished uploading to S3", Level::Info);
-	Ok(uploaded_count)
 }
 
 #[cfg(not(feature = "debug-mock"))]
-async fn put_object(req: PutObjectRequest<'_>) -> Result<(), String> {
+fn ensure_unique_keys(jobs: &[UploadJob]) -> Result<(), String> {
+	let mut keys = HashSet::with_capacity(jobs.len());
+	for job in jobs {
+		if !keys.insert(job.key.as_str()) {
+			return Err(format!(
+				"{} would overwrite another page in the same upload: {}",
+				job.path.display(),
+				job.key
+			));
+		}
+	}
+	Ok(())
+}
+
+/// Keys of every page of the batch, paired with the local file uploaded to that key
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) fn batch_object_keys(
+	key_layout: &KeyLayout,
+	object_id: &str,
+	batch: &BatchRepresentation,
+	material_type: Option<&str>,
+	upload_date: &str,
+) -> Result<Vec<BatchPage>, String> {
+	let mut pages = Vec::with_capacity(batch.primary.len() + batch.access.len());
+	for (files, rep_type) in [(&batch.primary, "primary"), (&batch.access, "access")] {
+		for (file_index, file_path_str) in files.iter().enumerate() {
+			let file_path = PathBuf::from(file_path_str);
+			let key = key_layout.batch_key(&PageKey {
+				object_id,
+				representation: Some(rep_type),
+				page_nr: file_index + 1,
+				extension: file_extension(&file_path)?,
+				material_type,
+				date: upload_date,
+			})?;
+			pages.push(BatchPage {
+				key,
+				path: file_path,
+				representation: rep_type,
+				page_nr: file_index + 1,
+			});
+		}
+	}
+	Ok(pages)
+}
+
+#[cfg(not(feature = "debug-mock"))]
+fn file_extension(path: &Path) -> Result<&str, String> {
+	path.extension()
+		.and_then(|ext| ext.to_str())
+		.ok_or_else(|| format!("Missing file extension: {}", path.display()))
+}
+
+#[cfg(not(feature = "debug-mock"))]
+async fn get_file_version(path: &Path) -> Result<FileVersion, String> {
+	let check_path = path.to_path_buf();
+	tokio::task::spawn_blocking(move || FileVersion::of(&check_path))
+		.await
+		.map_err(|e| format!("Failed to run blocking task: {e}"))?
+		.map_err(|e| format!("stat failed for {}: {e}", path.display()))
+}
+
+#[cfg(not(feature = "debug-mock"))]
+async fn put_object(req: PutObjectRequest<'_>) -> Result<UploadedObject, UploadError> {
 	let PutObjectRequest {
 		client,
 		secret_variables,
 		path,
-		object_id,
-		page_nr,
-		file_size,
-		representation_type,
+		key,
+		version,
+		journal,
+		max_concurrent_parts,
+		cancellation,
+		progress,
 	} = req;
-	let extension = path
-		.extension()
-		.and_then(|ext| ext.to_str())
-		.ok_or("Missing file extension")?;
+	let file_size = version.size as usize;
 
-	let key = if let Some(rep_type) = representation_type {
-		format!(
-			"{}/representations/{}/data/{}_{:0>5}.{}",
-			object_id, rep_type, object_id, page_nr, extension
-		)
-	} else {
-		format!("{}/{}_{:0>5}.{}", object_id, object_id, page_nr, extension)
-	};
+	let completed_sha256 = lock_journal(journal)?
+		.completed_sha256(key, version)
+		.map(str::to_string);
+	if let Some(sha256) = completed_sha256 {
+		// Already confirmed by S3 in an earlier attempt
+		progress.add_bytes(path, None, file_size as u64, false);
+		return Ok(UploadedObject {
+			key: key.to_string(),
+			path: path.to_string_lossy().to_string(),
+			size: file_size,
+			sha256,
+		});
+	}
 
+	cancellation.check()?;
 	if file_size <= MULTIPART_PART_SIZE {
 		// Small file, upload in a single PUT request
-		let body = ByteStream::from_path(path)
+		let body = tokio::fs::read(path)
 			.await
 			.map_err(|e| format!("Failed to read file: {e}"))?;
+		let digest = checksum::sha256(&body);
+		let checksum_sha256 = checksum::to_base64(&digest);
+		let body = Bytes::from(body);
+		let metadata = orientation_sidecar::object_metadata(path);
 
-		client
-			.put_object()
-			.bucket(&secret_variables.s3_bucket_name)
-			.key(key)
-			.content_length(file_size as i64)
-			.body(body)
-			.send()
-			.await
-			.inspect_err(|e| eprintln!("Error: {e:?}"))
-			.map_err(|e| format!("Failed to upload file: {e:?}"))?;
+		let (resp, attempt) = with_retry(
+			&get_retry_policy(),
+			&format!("upload of {key}"),
+			cancellation,
+			|| async {
+				let attempt = progress.start_attempt(path, None);
+				client
+					.put_object()
+					.bucket(&secret_variables.s3_bucket_name)
+					.key(key)
+					.content_length(file_size as i64)
+					.checksum_sha256(&checksum_sha256)
+					.set_metadata(metadata.clone())
+					.body(attempt.body(body.clone()))
+					.send()
+					.await
+					.map(|resp| (resp, attempt))
+					.inspect_err(|e| eprintln!("Error: {e:?}"))
+					.map_err(|e| AttemptError::from_sdk("Failed to upload file", e))
+			},
+		)
+		.await?;
+		verify_checksum(key, &checksum_sha256, resp.checksum_sha256())?;
 
-		Ok(())
+		let sha256 = checksum::to_hex(&digest);
+		lock_journal(journal)?
+			.mark_completed(key, version, &sha256)
+			.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+		attempt.confirm();
+
+		Ok(UploadedObject {
+			key: key.to_string(),
+			path: path.to_string_lossy().to_string(),
+			size: file_size,
+			sha256,
+		})
 	} else {
 		// Large file, use multipart upload
-		multipart_upload(client, secret_variables, path, &key).await
+		multipart_upload(MultipartUploadRequest {
+			client,
+			secret_variables,
+			path,
+			key,
+			version,
+			journal,
+			max_concurrent_parts,
+			cancellation,
+			progress,
+		})
+		.await
+	}
+}
+
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn multipart_upload(
+	req: MultipartUploadRequest<'_>,
+) -> Result<UploadedObject, UploadError> {
+	let MultipartUploadRequest {
+		client,
+		secret_variables,
+		path,
+		key,
+		version,
+		journal,
+		cancellation,
+		..
+	} = req;
+
+	// S3 only reports a checksum of the part checksums, so the whole file is hashed separately
+	let sha256 = {
+		let path = path.clone();
+		tokio::task::spawn_blocking(move || checksum::sha256_file(&path))
+			.await
+			.map_err(|e| format!("Failed to run blocking task: {e}"))?
+	}
+	.map_err(|e| format!("Failed to compute checksum of {}: {e}", path.display()))?;
+
+	let resumed = resume_multipart_upload(
+		client,
+		secret_variables,
+		key,
+		version,
+		journal,
+		cancellation,
+	)
+	.await?;
+	let (upload_id, completed) = match resumed {
+		Some(resumed) => resumed,
+		None => {
+			let metadata = orientation_sidecar::object_metadata(path);
+			let init = with_retry(
+				&get_retry_policy(),
+				&format!("start of multipart upload of {key}"),
+				cancellation,
+				|| async {
+					client
+						.create_multipart_upload()
+						.bucket(&secret_variables.s3_bucket_name)
+						.key(key)
+						.checksum_algorithm(ChecksumAlgorithm::Sha256)
+						.set_metadata(metadata.clone())
+						.send()
+						.await
+						.map_err(|e| AttemptError::from_sdk("init multipart failed", e))
+				},
+			)
+			.await?;
+			let upload_id = init.upload_id().ok_or("missing upload_id")?.to_string();
+			lock_journal(journal)?
+				.start_multipart_upload(key, &upload_id, version)
+				.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+			(upload_id, Vec::new())
+		}
+	};
+
+	// Once the upload exists, a cancel at any later point (also while it is being completed)
+	// has to abort it, or S3 keeps its parts around
+	let result = upload_parts_and_complete(&req, &upload_id, completed, &sha256).await;
+	if let Err(UploadError::Cancelled) = result {
+		abort_multipart_upload(client, secret_variables, key, &upload_id, journal).await;
+	}
+	result
+}
+
+/// Uploads the parts of the multipart upload `upload_id` that are not `completed` yet, and asks
+/// S3 to assemble them into the object
+#[cfg(not(feature = "debug-mock"))]
+async fn upload_parts_and_complete(
+	req: &MultipartUploadRequest<'_>,
+	upload_id: &str,
+	mut completed: Vec<CompletedPart>,
+	sha256: &Sha256Digest,
+) -> Result<UploadedObject, UploadError> {
+	let MultipartUploadRequest {
+		client,
+		secret_variables,
+		path,
+		key,
+		version,
+		journal,
+		max_concurrent_parts,
+		cancellation,
+		progress,
+	} = *req;
+	let file_size = version.size as usize;
+
+	// Parts already confirmed by S3 are skipped
+	for part in &completed {
+		if let Some(part_number) = part.part_number() {
+			let size = part_size(file_size, part_number) as u64;
+			progress.add_bytes(path, Some(part_number), size, false);
+		}
 	}
+	let part_count = file_size.div_ceil(MULTIPART_PART_SIZE) as i32;
+	let pending_parts: Vec<i32> = (1..=part_count)
+		.filter(|part_number| {
+			!completed
+				.iter()
+				.any(|part: &CompletedPart| part.part_number() == Some(*part_number))
+		})
+		.collect();
+
+	run_bounded(
+		pending_parts,
+		max_concurrent_parts,
+		|part_number| {
+			upload_part(UploadPartRequest {
+				client: client.clone(),
+				bucket: secret_variables.s3_bucket_name.clone(),
+				key: key.to_string(),
+				upload_id: upload_id.to_string(),
+				path: path.clone(),
+				file_size,
+				part_number,
+				cancellation: cancellation.clone(),
+				progress: progress.clone(),
+			})
+		},
+		|part: JournalPart| {
+			lock_journal(journal)?
+				.record_part(key, part.clone())
+				.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+			completed.push(
+				CompletedPart::builder()
+					.part_number(part.part_number)
+					.e_tag(part.e_tag)
+					.checksum_sha256(part.checksum_sha256)
+					.build(),
+			);
+			Ok(())
+		},
+	)
+	.await?;
+
+	completed.sort_by_key(|part| part.part_number());
+	let part_digests = completed
+		.iter()
+		.map(|part| part.checksum_sha256().and_then(checksum::from_base64))
+		.collect::<Option<Vec<_>>>()
+		.ok_or_else(|| format!("Missing part checksum for {key}"))?;
+	let composite_checksum = checksum::composite_sha256_base64(&part_digests);
+
+	let resp = complete_multipart_upload(
+		client,
+		&secret_variables.s3_bucket_name,
+		key,
+		upload_id,
+		completed,
+		cancellation,
+	)
+	.await?;
+	verify_checksum(key, &composite_checksum, resp.checksum_sha256())?;
+
+	let sha256 = checksum::to_hex(sha256);
+	lock_journal(journal)?
+		.mark_completed(key, version, &sha256)
+		.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+
+	Ok(UploadedObject {
+		key: key.to_string(),
+		path: path.to_string_lossy().to_string(),
+		size: file_size,
+		sha256,
+	})
 }
 
-async fn multipart_upload(
+/// Asks S3 to assemble the uploaded parts into the object, retrying transient failures
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn complete_multipart_upload(
 	client: &Client,
-	secret_variables: &SecretVariables,
-	path: &PathBuf,
+	bucket: &str,
+	key: &str,
+	upload_id: &str,
+	parts: Vec<CompletedPart>,
+	cancellation: &CancellationFlag,
+) -> Result<CompleteMultipartUploadOutput, UploadError> {
+	let multipart_upload = CompletedMultipartUpload::builder()
+		.set_parts(Some(parts))
+		.build();
+	with_retry(
+		&get_retry_policy(),
+		&format!("completion of multipart upload of {key}"),
+		cancellation,
+		|| async {
+			client
+				.complete_multipart_upload()
+				.bucket(bucket)
+				.key(key)
+				.upload_id(upload_id)
+				.multipart_upload(multipart_upload.clone())
+				.send()
+				.await
+				.map_err(|e| AttemptError::from_sdk("complete multipart failed", e))
+		},
+	)
+	.await
+}
+
+/// Fails unless S3 reports the same checksum as the one computed locally
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) fn verify_checksum(
 	key: &str,
+	expected: &str,
+	reported: Option<&str>,
 ) -> Result<(), String> {
-	// Large file, use multipart upload
-	let init = client
-		.create_multipart_upload()
-		.bucket(&secret_variables.s3_bucket_name)
-		.key(key)
-		.send()
-		.await
-		.map_err(|e| format!("init multipart failed: {e:?}"))?;
-	let upload_id = init.upload_id().ok_or("missing upload_id")?.to_string();
-	let file = File::open(path)
+	match reported {
+		Some(reported) if reported == expected => Ok(()),
+		Some(reported) => Err(format!(
+			"Checksum mismatch for {key}: sent {expected}, server reported {reported}"
+		)),
+		None => Err(format!("Server did not report a checksum for {key}")),
+	}
+}
+
+/// Reads one part of the file from disk and uploads it, retrying transient failures. If it still
+/// fails the multipart upload is left open, so that a retry can resume it.
+#[cfg(not(feature = "debug-mock"))]
+async fn upload_part(req: UploadPartRequest) -> Result<JournalPart, UploadError> {
+	let UploadPartRequest {
+		client,
+		bucket,
+		key,
+		upload_id,
+		path,
+		file_size,
+		part_number,
+		cancellation,
+		progress,
+	} = req;
+	cancellation.check()?;
+
+	let offset = (part_number as usize - 1) * MULTIPART_PART_SIZE;
+	let size = part_size(file_size, part_number);
+	let mut buf = vec![0u8; size];
+
+	let mut file = File::open(&path)
 		.await
 		.map_err(|e| format!("open failed for {}: {e}", path.display()))?;
-	let mut reader = BufReader::new(file);
-	let mut buf = vec![0u8; MULTIPART_PART_SIZE];
-	let mut part_number: i32 = 1;
-	let mut completed: Vec<CompletedPart> = Vec::new();
+	file.seek(SeekFrom::Start(offset as u64))
+		.await
+		.map_err(|e| format!("seek failed: {e}"))?;
+	file.read_exact(&mut buf)
+		.await
+		.map_err(|e| format!("read failed: {e}"))?;
 
-	loop {
-		// Fill up to MULTIPART_PART_SIZE
-		let mut filled = 0usize;
-		while filled < MULTIPART_PART_SIZE {
-			let n = reader
-				.read(&mut buf[filled..])
+	let checksum_sha256 = checksum::to_base64(&checksum::sha256(&buf));
+	let body = Bytes::from(buf);
+
+	let (resp, attempt) = with_retry(
+		&get_retry_policy(),
+		&format!("part #{part_number} of {key}"),
+		&cancellation,
+		|| async {
+			let attempt = progress.start_attempt(&path, Some(part_number));
+			client
+				.upload_part()
+				.bucket(&bucket)
+				.key(&key)
+				.upload_id(&upload_id)
+				.part_number(part_number)
+				.content_length(size as i64)
+				.checksum_sha256(&checksum_sha256)
+				.body(attempt.body(body.clone()))
+				.send()
 				.await
-				.map_err(|e| format!("read failed: {e}"))?;
-			if n == 0 {
-				break;
-			}
-			filled += n;
-		}
-		if filled == 0 {
-			break;
-		}
+				.map(|resp| (resp, attempt))
+				.map_err(|e| {
+					AttemptError::from_sdk(&format!("upload_part #{part_number} failed"), e)
+				})
+		},
+	)
+	.await?;
+	verify_checksum(&key, &checksum_sha256, resp.checksum_sha256())?;
 
-		// Build a ByteStream for this part (exactly the bytes we read)
-		let part_stream = ByteStream::from(buf[..filled].to_vec());
-
-		// Upload part
-		let bucket = secret_variables.s3_bucket_name.clone();
-		let resp = client
-			.upload_part()
-			.bucket(&secret_variables.s3_bucket_name)
-			.key(key)
-			.upload_id(&upload_id)
-			.part_number(part_number)
-			.body(part_stream)
-			.send()
-			.await
-			.map_err(|e| {
-				// Try to abort on failure to avoid leaked multiparts
-				let _task = tokio::spawn({
-					let client = client.clone();
-					let bucket = bucket;
-					let key = key.to_owned();
-					let upload_id = upload_id.clone();
-					async move {
-						let _ = client
-							.abort_multipart_upload()
-							.bucket(bucket)
-							.key(key)
-							.upload_id(upload_id)
-							.send()
-							.await;
-					}
-				});
-				format!("upload_part #{part_number} failed: {e:?}")
-			})?;
+	let e_tag = resp
+		.e_tag()
+		.ok_or_else(|| format!("upload_part #{part_number} of {key} returned no ETag"))?;
+	attempt.confirm();
 
-		completed.push(
-			CompletedPart::builder()
-				.part_number(part_number)
-				.e_tag(resp.e_tag().unwrap_or_default())
-				.build(),
-		);
-		part_number += 1;
-	}
+	Ok(JournalPart {
+		part_number,
+		e_tag: e_tag.to_string(),
+		checksum_sha256,
+	})
+}
 
-	client
-		.complete_multipart_upload()
+#[cfg(not(feature = "debug-mock"))]
+fn part_size(file_size: usize, part_number: i32) -> usize {
+	let offset = (part_number as usize - 1) * MULTIPART_PART_SIZE;
+	MULTIPART_PART_SIZE.min(file_size - offset)
+}
+
+/// Aborts a cancelled multipart upload so S3 discards its parts, and forgets it in the journal.
+/// Failing to abort is only recorded, since the upload has been cancelled either way.
+#[cfg(not(feature = "debug-mock"))]
+async fn abort_multipart_upload(
+	client: &Client,
+	secret_variables: &SecretVariables,
+	key: &str,
+	upload_id: &str,
+	journal: &Mutex<UploadJournal>,
+) {
+	if let Err(e) = client
+		.abort_multipart_upload()
 		.bucket(&secret_variables.s3_bucket_name)
 		.key(key)
 		.upload_id(upload_id)
-		.multipart_upload(
-			CompletedMultipartUpload::builder()
-				.set_parts(Some(completed))
-				.build(),
-		)
 		.send()
 		.await
-		.map_err(|e| format!("complete multipart failed: {e:?}"))?;
+	{
+		add_breadcrumb(Breadcrumb {
+			category: Some("s3".into()),
+			message: Some(format!("Failed to abort multipart upload of {key}: {e:?}")),
+			level: Level::Warning,
+			..Default::default()
+		});
+	}
+	if let Ok(mut journal) = lock_journal(journal) {
+		let _ = journal.discard_multipart_upload(key);
+	}
+}
 
-	Ok(())
+/// Looks up an unfinished multipart upload of `key` in the journal and returns its upload id
+/// together with the recorded parts that S3 still has. Returns `None` if there is nothing to
+/// resume, or if the upload no longer exists on the server (expired or aborted). Any other
+/// failure to list the parts is returned, and the upload is kept in the journal for next time.
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn resume_multipart_upload(
+	client: &Client,
+	secret_variables: &SecretVariables,
+	key: &str,
+	version: FileVersion,
+	journal: &Mutex<UploadJournal>,
+	cancellation: &CancellationFlag,
+) -> Result<Option<(String, Vec<CompletedPart>)>, UploadError> {
+	let Some(recorded) = lock_journal(journal)?
+		.multipart_upload(key, version)
+		.cloned()
+	else {
+		return Ok(None);
+	};
+
+	let listed = with_retry(
+		&get_retry_policy(),
+		&format!("listing of uploaded parts of {key}"),
+		cancellation,
+		|| async {
+			let listed = client
+				.list_parts()
+				.bucket(&secret_variables.s3_bucket_name)
+				.key(key)
+				.upload_id(&recorded.upload_id)
+				.into_paginator()
+				.items()
+				.send()
+				.collect::<Result<Vec<_>, _>>()
+				.await;
+			match listed {
+				Ok(parts) => Ok(Some(parts)),
+				Err(e) if e.code() == Some(NO_SUCH_UPLOAD) => Ok(None),
+				Err(e) => Err(AttemptError::from_sdk("list_parts failed", e)),
+			}
+		},
+	)
+	.await?;
+	let Some(parts) = listed else {
+		// Start over with a fresh multipart upload
+		lock_journal(journal)?
+			.discard_multipart_upload(key)
+			.map_err(|e| format!("Failed to write upload journal: {e}"))?;
+		return Ok(None);
+	};
+
+	let mut confirmed = Vec::new();
+	for part in parts {
+		let recorded_part = recorded.parts.iter().find(|recorded_part| {
+			part.part_number() == Some(recorded_part.part_number)
+				&& part.e_tag() == Some(recorded_part.e_tag.as_str())
+				&& part.checksum_sha256() == Some(recorded_part.checksum_sha256.as_str())
+		});
+		if let Some(recorded_part) = recorded_part {
+			confirmed.push(
+				CompletedPart::builder()
+					.part_number(recorded_part.part_number)
+					.e_tag(&recorded_part.e_tag)
+					.checksum_sha256(&recorded_part.checksum_sha256)
+					.build(),
+			);
+		}
+	}
+
+	add_breadcrumb(Breadcrumb {
+		category: Some("s3".into()),
+		message: Some(format!(
+			"Resuming multipart upload with {} confirmed parts",
+			confirmed.len()
+		)),
+		level: Level::Info,
+		..Default::default()
+	});
+
+	Ok(Some((recorded.upload_id, confirmed)))
+}
+
+/// Folder in the app data directory the upload journals are kept in
+#[cfg(not(feature = "debug-mock"))]
+fn journal_directory(app: &AppHandle) -> Result<PathBuf, String> {
+	Ok(app
+		.path()
+		.app_data_dir()
+		.map_err(|e| format!("Failed to find app data directory: {e}"))?
+		.join(UPLOAD_JOURNAL_FOLDER_NAME))
+}
+
+/// Opens the upload journal for `object_id` in the app data directory
+#[cfg(not(feature = "debug-mock"))]
+fn open_journal(app_window: &Window, object_id: &str) -> Result<UploadJournal, String> {
+	UploadJournal::open(&journal_directory(app_window.app_handle())?, object_id)
+		.map_err(|e| format!("Failed to open upload journal: {e}"))
+}
+
+/// Discards the upload journals that have not been written for `UPLOAD_JOURNAL_TTL`. Returns
+/// the number of journals discarded.
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn discard_expired_upload_journals(app: &AppHandle) -> Result<usize, String> {
+	let now = SystemTime::now();
+	discard_upload_journals(app, |journal| {
+		journal
+			.last_written()
+			.ok()
+			.and_then(|written| now.duration_since(written).ok())
+			.is_some_and(|age| age > UPLOAD_JOURNAL_TTL)
+	})
+	.await
+}
+
+/// Discards the upload journals of files in `directory`, used when it has been deleted.
+/// Returns the number of journals discarded.
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn discard_upload_journals_in(
+	app: &AppHandle,
+	directory: &Path,
+) -> Result<usize, String> {
+	discard_upload_journals(app, |journal| {
+		journal
+			.directory()
+			.is_some_and(|journal_directory| journal_directory.starts_with(directory))
+	})
+	.await
+}
+
+#[cfg(not(feature = "debug-mock"))]
+async fn discard_upload_journals(
+	app: &AppHandle,
+	is_abandoned: impl Fn(&UploadJournal) -> bool,
+) -> Result<usize, String> {
+	let (unfinished, finished): (Vec<_>, Vec<_>) =
+		UploadJournal::open_all(&journal_directory(app)?)
+			.map_err(|e| format!("Failed to open upload journals: {e}"))?
+			.into_iter()
+			.filter(|journal| is_abandoned(journal))
+			.partition(|journal| !journal.unfinished_uploads().is_empty());
+
+	for journal in &finished {
+		journal
+			.remove()
+			.map_err(|e| format!("Failed to remove upload journal: {e}"))?;
+	}
+	if unfinished.is_empty() {
+		return Ok(finished.len());
+	}
+
+	let secret_variables = get_secret_variables()
+		.await
+		.map_err(|e| format!("Failed to get secret variables: {e}"))?;
+	let client = get_client(secret_variables)
+		.await
+		.map_err(|e| format!("Failed to get S3 client: {e}"))?;
+	let discarded = discard_journals(client, &secret_variables.s3_bucket_name, unfinished).await?;
+	Ok(finished.len() + discarded)
+}
+
+/// Aborts the unfinished multipart uploads of `journals`, so S3 discards their parts, and
+/// removes the journals. A journal with an upload that could not be aborted is kept, so it is
+/// tried again next time. Returns the number of journals removed.
+#[cfg(not(feature = "debug-mock"))]
+pub(crate) async fn discard_journals(
+	client: &Client,
+	bucket: &str,
+	journals: Vec<UploadJournal>,
+) -> Result<usize, String> {
+	let mut discarded = 0;
+	for journal in journals {
+		let mut aborted = true;
+		for (key, upload_id) in journal.unfinished_uploads() {
+			let result = client
+				.abort_multipart_upload()
+				.bucket(bucket)
+				.key(&key)
+				.upload_id(&upload_id)
+				.send()
+				.await;
+			match result {
+				Ok(_) => {}
+				Err(e) if e.code() == Some(NO_SUCH_UPLOAD) => {}
+				Err(e) => {
+					add_breadcrumb(Breadcrumb {
+						category: Some("s3".into()),
+						message: Some(format!(
+							"Failed to abort abandoned multipart upload of {key}: {e:?}"
+						)),
+						level: Level::Warning,
+						..Default::default()
+					});
+					aborted = false;
+				}
+			}
+		}
+		if aborted {
+			journal
+				.remove()
+				.map_err(|e| format!("Failed to remove upload journal: {e}"))?;
+			discarded += 1;
+		}
+	}
+	Ok(discarded)
+}
+
+#[cfg(not(feature = "debug-mock"))]
+fn lock_journal(journal: &Mutex<UploadJournal>) -> Result<MutexGuard<'_, UploadJournal>, String> {
+	journal.lock().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "debug-mock"))]
+fn remove_journal(journal: &Mutex<UploadJournal>) -> Result<(), String> {
+	lock_journal(journal)?
+		.remove()
+		.map_err(|e| format!("Failed to remove upload journal: {e}"))
 }
 
 // Use Tokio's OnceCell to create the S3 client only once
@@ -314,7 +1262,9 @@ async fn multipart_upload(
 static S3_CLIENT_CELL: OnceCell<Client> = OnceCell::const_new();
 
 #[cfg(not(feature = "debug-mock"))]
-async fn get_client(secret_variables: &SecretVariables) -> Result<&'static Client, String> {
+pub(crate) async fn get_client(
+	secret_variables: &SecretVariables,
+) -> Result<&'static Client, String> {
 	// Create the S3 client only once, the cell functions as a cache
 	S3_CLIENT_CELL
 		.get_or_try_init(|| async { create_client(secret_variables).await })
@@ -338,6 +1288,8 @@ async fn create_client(secret_variables: &SecretVariables) -> Result<Client, Str
 		.disable_s3_express_session_auth(true)
 		.disable_multi_region_access_points(true)
 		.force_path_style(true)
+		// Retries are handled per object and part by `retry::with_retry`
+		.retry_config(RetryConfig::disabled())
 		.build();
 
 	Ok(Client::from_conf(config))
@@ -0,0 +1,302 @@
+use notify_debouncer_full::notify::event::{AccessKind, AccessMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{
+	DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache, new_debouncer,
+};
+use once_cell::sync::Lazy;
+use sentry::{Breadcrumb, Level, add_breadcrumb, capture_message};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::derivative_cache::{self, PREVIEW_FOLDER_NAME, THUMBNAIL_FOLDER_NAME};
+use crate::error::ImageConversionError;
+use crate::file_utils;
+use crate::image_converter;
+use crate::model::ScanFolderChange;
+use crate::orientation_sidecar::ORIENTATION_FOLDER_NAME;
+
+/// How long events are collected before they are handled, so a burst of writes is handled once
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+/// Files still being written after this long are left for the events their next writes cause
+const MAX_FINISHED_WAIT: Duration = Duration::from_secs(60);
+
+/// Folders the app writes to itself, changes in them are never reported
+const SUPPORT_FOLDER_NAMES: &[&str] = &[
+	THUMBNAIL_FOLDER_NAME,
+	PREVIEW_FOLDER_NAME,
+	ORIENTATION_FOLDER_NAME,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanFolderEventKind {
+	FileAdded,
+	/// An image already reported was written again, e.g. rotated, and has a new thumbnail
+	FileChanged,
+	FileRemoved,
+	FolderAdded,
+}
+
+impl ScanFolderEventKind {
+	/// Name of the window event the change is emitted as
+	pub fn event_name(self) -> &'static str {
+		match self {
+			ScanFolderEventKind::FileAdded => "file_added",
+			ScanFolderEventKind::FileChanged => "file_changed",
+			ScanFolderEventKind::FileRemoved => "file_removed",
+			ScanFolderEventKind::FolderAdded => "folder_added",
+		}
+	}
+}
+
+/// Watches a scan folder recursively, makes thumbnails for images once they are completely
+/// written and reports added, changed and removed images and folders. Images are waited for and
+/// converted on the conversion pool, so a file still being written does not hold up the others.
+pub struct ScanWatcher {
+	debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+	worker: JoinHandle<()>,
+}
+
+impl ScanWatcher {
+	pub fn start<F>(root: PathBuf, on_change: F) -> Result<Self, String>
+	where
+		F: Fn(ScanFolderEventKind, ScanFolderChange) + Send + Sync + 'static,
+	{
+		// Listed before watching, so folders and images made from now on are always reported
+		let known_folders = list_folders(&root);
+		let known_images = list_images(&root);
+		let (sender, receiver) = mpsc::channel();
+		let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, None, sender)
+			.map_err(|e| format!("Failed to create watcher: {e}"))?;
+		debouncer
+			.watch(&root, RecursiveMode::Recursive)
+			.map_err(|e| format!("Failed to watch {}: {e}", root.display()))?;
+
+		let worker = thread::Builder::new()
+			.name("scan-watcher".into())
+			.spawn(move || handle_events(root, known_folders, known_images, receiver, on_change))
+			.map_err(|e| format!("Failed to start watcher thread: {e}"))?;
+
+		Ok(Self { debouncer, worker })
+	}
+
+	/// Stops watching, and waits for the changes already seen to be handled
+	pub fn stop(self) {
+		// Stopping the debouncer drops the sender, which ends the worker
+		self.debouncer.stop();
+		let _ = self.worker.join();
+	}
+}
+
+static SCAN_WATCHERS: Lazy<Mutex<HashMap<PathBuf, ScanWatcher>>> =
+	Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Starts watching `root`, replacing the watcher already running for it
+pub fn watch<F>(root: impl Into<PathBuf>, on_change: F) -> Result<(), String>
+where
+	F: Fn(ScanFolderEventKind, ScanFolderChange) + Send + Sync + 'static,
+{
+	let root = root.into();
+	unwatch(&root);
+	let watcher = ScanWatcher::start(root.clone(), on_change)?;
+	SCAN_WATCHERS.lock().unwrap().insert(root, watcher);
+	Ok(())
+}
+
+/// Stops watching `root`, returns whether it was watched
+pub fn unwatch(root: &Path) -> bool {
+	let watcher = SCAN_WATCHERS.lock().unwrap().remove(root);
+	match watcher {
+		Some(watcher) => {
+			watcher.stop();
+			true
+		}
+		None => false,
+	}
+}
+
+/// Whether a change to the path should be ignored, because the app wrote it itself
+pub fn is_ignored(path: &Path) -> bool {
+	file_utils::is_temporary_file(path)
+		|| path.components().any(|c| {
+			SUPPORT_FOLDER_NAMES
+				.iter()
+				.any(|&name| c.as_os_str() == name)
+		})
+}
+
+fn handle_events<F>(
+	root: PathBuf,
+	mut known_folders: HashSet<PathBuf>,
+	mut known_images: HashSet<PathBuf>,
+	receiver: Receiver<DebounceEventResult>,
+	on_change: F,
+) where
+	F: Fn(ScanFolderEventKind, ScanFolderChange) + Send + Sync + 'static,
+{
+	let on_change = Arc::new(on_change);
+	let change = |path: PathBuf, thumbnail: Option<PathBuf>| ScanFolderChange {
+		root: root.clone(),
+		path,
+		thumbnail,
+	};
+	// Images waited for or converted on the pool right now, so each is handled once at a time
+	let in_progress: Arc<Mutex<HashSet<PathBuf>>> = Arc::default();
+	// Every task holds a sender, so the receiver is done once the last task has finished
+	let (task_sender, task_receiver) = mpsc::channel::<()>();
+
+	while let Ok(result) = receiver.recv() {
+		let events = match result {
+			Ok(events) => events,
+			Err(errors) => {
+				for error in errors {
+					add_breadcrumb(Breadcrumb {
+						category: Some("scan_watcher".into()),
+						message: Some(format!("Watcher error: {error}")),
+						level: Level::Warning,
+						..Default::default()
+					});
+				}
+				continue;
+			}
+		};
+
+		let mut written_images = BTreeSet::new();
+		for path in changed_paths(&events) {
+			match fs::metadata(&path) {
+				Err(_) => {
+					let was_folder = known_folders.remove(&path);
+					if was_folder {
+						known_folders.retain(|folder| !folder.starts_with(&path));
+						known_images.retain(|image| !image.starts_with(&path));
+					}
+					known_images.remove(&path);
+					if was_folder || file_utils::has_image_extension(&path) {
+						on_change(ScanFolderEventKind::FileRemoved, change(path, None));
+					}
+				}
+				Ok(metadata) if metadata.is_dir() => {
+					if known_folders.insert(path.clone()) {
+						// Folders moved in whole may not get events for what is already in them
+						known_folders.extend(list_folders(&path));
+						written_images.extend(list_images(&path));
+						on_change(ScanFolderEventKind::FolderAdded, change(path, None));
+					}
+				}
+				Ok(_) => {
+					if file_utils::has_image_extension(&path) {
+						written_images.insert(path);
+					}
+				}
+			}
+		}
+
+		let stability = file_utils::get_file_stability();
+		for image in written_images {
+			if !in_progress.lock().unwrap().insert(image.clone()) {
+				// Waiting for the image also waits for this write
+				continue;
+			}
+			let kind = if known_images.insert(image.clone()) {
+				ScanFolderEventKind::FileAdded
+			} else {
+				ScanFolderEventKind::FileChanged
+			};
+			let pending_change = change(image.clone(), None);
+			let on_change = on_change.clone();
+			let in_progress = in_progress.clone();
+			let task_sender = task_sender.clone();
+			let task = move || {
+				let finished = file_utils::wait_until_finished(
+					vec![image.clone()],
+					&stability,
+					MAX_FINISHED_WAIT,
+				);
+				if !finished.is_empty() {
+					let thumbnail = ensure_thumbnail(&image);
+					on_change(
+						kind,
+						ScanFolderChange {
+							thumbnail,
+							..pending_change
+						},
+					);
+				}
+				in_progress.lock().unwrap().remove(&image);
+				drop(task_sender);
+			};
+			match image_converter::conversion_pool() {
+				Ok(pool) => pool.spawn(task),
+				Err(_) => task(),
+			}
+		}
+	}
+
+	drop(task_sender);
+	while task_receiver.recv().is_ok() {}
+}
+
+/// Paths changed by the events, parents before children, without the app's own files
+fn changed_paths(events: &[DebouncedEvent]) -> BTreeSet<PathBuf> {
+	events
+		.iter()
+		.filter(|event| match event.kind {
+			// Reading a file, like the conversion does, is not a change
+			EventKind::Access(kind) => kind == AccessKind::Close(AccessMode::Write),
+			_ => true,
+		})
+		.flat_map(|event| event.paths.iter())
+		.filter(|path| !is_ignored(path))
+		.cloned()
+		.collect()
+}
+
+fn list_folders(root: &Path) -> HashSet<PathBuf> {
+	let mut folders = HashSet::new();
+	let mut pending = vec![root.to_path_buf()];
+	while let Some(folder) = pending.pop() {
+		let Ok(entries) = fs::read_dir(&folder) else {
+			continue;
+		};
+		for path in entries.flatten().map(|entry| entry.path()) {
+			if path.is_dir() && !is_ignored(&path) {
+				pending.push(path);
+			}
+		}
+		folders.insert(folder);
+	}
+	folders
+}
+
+fn list_images(root: &Path) -> HashSet<PathBuf> {
+	file_utils::list_image_files(root, true)
+		.unwrap_or_default()
+		.into_iter()
+		.filter(|image| !is_ignored(image))
+		.collect()
+}
+
+/// Makes the thumbnail of an image unless it is up to date, and returns where it is
+fn ensure_thumbnail(image: &Path) -> Option<PathBuf> {
+	let thumbnail = match image_converter::check_if_thumbnail_exists(image) {
+		Ok(true) => {
+			derivative_cache::derivative_path(image, false).map_err(ImageConversionError::StrError)
+		}
+		_ => image_converter::convert_to_webp(image, false),
+	};
+	thumbnail
+		.map_err(|e| {
+			add_breadcrumb(Breadcrumb {
+				category: Some("scan_watcher".into()),
+				message: Some(format!("Failed to create thumbnail for {image:?}: {e}")),
+				level: Level::Error,
+				..Default::default()
+			});
+			capture_message("Watcher failed to create thumbnail", Level::Warning);
+		})
+		.ok()
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use ::tempfile::TempDir;
+
+use crate::batch_validation::validate_batch;
+use crate::file_utils::FileStability;
+use crate::model::{BatchProblemKind, BatchRepresentation};
+
+/// The test pages are not real images and are never written to after the test writes them
+const FINISHED: FileStability = FileStability {
+	interval: Duration::ZERO,
+	parse_header: false,
+};
+
+fn setup_scan_dir() -> TempDir {
+	TempDir::with_prefix("trokk-test-batch-").expect("Failed to create temp dir")
+}
+
+fn write_page(dir: &Path, name: &str, size: usize) -> String {
+	let path = dir.join(name);
+	fs::write(&path, vec![0u8; size]).expect("Failed to write test file");
+	path.to_string_lossy().to_string()
+}
+
+fn batch(primary: Vec<String>, access: Vec<String>) -> BatchRepresentation {
+	BatchRepresentation { primary, access }
+}
+
+#[test]
+fn test_validate_batch_accepts_complete_batch_and_sums_sizes() {
+	let scan_dir = setup_scan_dir();
+	let page_1 = write_page(scan_dir.path(), "page_1.tif", 10);
+	let page_2 = write_page(scan_dir.path(), "page_2.tif", 20);
+	let batch_map = HashMap::from([("0a1b2c".to_string(), batch(vec![page_1, page_2], vec![]))]);
+
+	let report = validate_batch(&batch_map, 1024, &FINISHED);
+
+	assert!(report.valid);
+	assert!(report.problems.is_empty());
+	assert_eq!(report.file_count, 2);
+	assert_eq!(report.total_bytes, 30);
+}
+
+#[test]
+fn test_validate_batch_reports_every_problem() {
+	let scan_dir = setup_scan_dir();
+	let page = write_page(scan_dir.path(), "page_1.tif", 10);
+	let notes = write_page(scan_dir.path(), "notes.txt", 10);
+	let missing = scan_dir
+		.path()
+		.join("missing.tif")
+		.to_string_lossy()
+		.to_string();
+	let batch_map = HashMap::from([
+		(
+			"0a1b2c".to_string(),
+			batch(vec![page.clone(), notes, missing], vec![page]),
+		),
+		("3d4e5f".to_string(), batch(vec![], vec![])),
+	]);
+
+	let report = validate_batch(&batch_map, 1024, &FINISHED);
+	let kinds: Vec<BatchProblemKind> = report.problems.iter().map(|p| p.kind).collect();
+
+	assert!(!report.valid);
+	assert_eq!(
+		kinds,
+		vec![
+			BatchProblemKind::RepresentationCountMismatch,
+			BatchProblemKind::UnsupportedExtension,
+			BatchProblemKind::MissingFile,
+			BatchProblemKind::DuplicateFile,
+			BatchProblemKind::EmptyBatch,
+		]
+	);
+}
+
+#[test]
+fn test_validate_batch_rejects_empty_map_and_too_large_uploads() {
+	let report = validate_batch(&HashMap::new(), 1024, &FINISHED);
+	assert_eq!(report.problems[0].kind, BatchProblemKind::EmptyBatchMap);
+
+	let scan_dir = setup_scan_dir();
+	let page = write_page(scan_dir.path(), "page_1.tif", 2048);
+	let batch_map = HashMap::from([("0a1b2c".to_string(), batch(vec![page], vec![]))]);
+
+	let report = validate_batch(&batch_map, 1024, &FINISHED);
+	assert_eq!(report.problems.len(), 1);
+	assert_eq!(report.problems[0].kind, BatchProblemKind::TooLarge);
+}
+
+#[test]
+fn test_validate_batch_reports_images_whose_header_cannot_be_read() {
+	let scan_dir = setup_scan_dir();
+	let page = write_page(scan_dir.path(), "page_1.tif", 10);
+	let batch_map = HashMap::from([("0a1b2c".to_string(), batch(vec![page.clone()], vec![]))]);
+	let stability = FileStability {
+		parse_header: true,
+		..FINISHED
+	};
+
+	let report = validate_batch(&batch_map, 1024, &stability);
+
+	assert!(!report.valid);
+	assert_eq!(report.problems.len(), 1);
+	assert_eq!(report.problems[0].kind, BatchProblemKind::UnfinishedFile);
+	assert_eq!(report.problems[0].batch_id.as_deref(), Some("0a1b2c"));
+	assert_eq!(report.problems[0].path, Some(page));
+}
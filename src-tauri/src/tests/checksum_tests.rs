@@ -0,0 +1,59 @@
+use std::fs;
+
+use ::tempfile::TempDir;
+
+use crate::checksum::*;
+
+// SHA-256 of "abc", from FIPS 180-2
+const ABC_SHA256_HEX: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+#[test]
+fn test_sha256_file_matches_in_memory_digest() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-checksum-").expect("Failed to create temp dir");
+	let file_path = tmp_dir.path().join("page.tif");
+	fs::write(&file_path, b"abc").unwrap();
+
+	let digest = sha256_file(&file_path).unwrap();
+
+	assert_eq!(digest, sha256(b"abc"));
+	assert_eq!(to_hex(&digest), ABC_SHA256_HEX);
+}
+
+#[test]
+fn test_base64_round_trip() {
+	let digest = sha256(b"abc");
+
+	assert_eq!(from_base64(&to_base64(&digest)), Some(digest));
+	assert_eq!(from_base64("bm90LWEtZGlnZXN0"), None);
+}
+
+#[test]
+fn test_composite_sha256_base64_appends_part_count() {
+	let parts = [sha256(b"part 1"), sha256(b"part 2")];
+	let mut concatenated = Vec::new();
+	concatenated.extend_from_slice(&parts[0]);
+	concatenated.extend_from_slice(&parts[1]);
+
+	let composite = composite_sha256_base64(&parts);
+
+	assert_eq!(
+		composite,
+		format!("{}-2", to_base64(&sha256(&concatenated)))
+	);
+}
+
+#[test]
+fn test_s3_sha256_file_uses_composite_checksum_for_multipart_files() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-checksum-").expect("Failed to create temp dir");
+	let file_path = tmp_dir.path().join("page.tif");
+	fs::write(&file_path, b"abcde").unwrap();
+
+	assert_eq!(
+		s3_sha256_file(&file_path, 5).unwrap(),
+		to_base64(&sha256(b"abcde"))
+	);
+	assert_eq!(
+		s3_sha256_file(&file_path, 2).unwrap(),
+		composite_sha256_base64(&[sha256(b"ab"), sha256(b"cd"), sha256(b"e")])
+	);
+}
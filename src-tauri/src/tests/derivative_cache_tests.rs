@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use ::tempfile::TempDir;
+
+use crate::derivative_cache::{
+	DerivativeStamp, central_derivative_path, derivative_path, evict, is_current,
+	remove_derivatives, write_stamp,
+};
+use crate::image_converter::{
+	check_if_thumbnail_exists, convert_to_webp, convert_to_webp_derivatives,
+};
+use crate::tests::test_utils::{TEST_IMAGE_PNG, copy_finished_test_image};
+
+fn copy_test_image(directory: &Path) -> std::path::PathBuf {
+	let image_path = directory.join(TEST_IMAGE_PNG);
+	copy_finished_test_image(&image_path);
+	image_path
+}
+
+#[test]
+fn test_derivative_path_is_webp_in_support_folder() {
+	let image_path = Path::new("/scans/batch/page_1.tif");
+
+	assert_eq!(
+		derivative_path(image_path, false).unwrap(),
+		Path::new("/scans/batch/.thumbnails/page_1.webp")
+	);
+	assert_eq!(
+		derivative_path(image_path, true).unwrap(),
+		Path::new("/scans/batch/.previews/page_1.webp")
+	);
+}
+
+#[test]
+fn test_thumbnail_is_stale_after_source_is_overwritten() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = copy_test_image(tmp_dir.path());
+
+	convert_to_webp(&image_path, false).unwrap();
+	assert!(check_if_thumbnail_exists(&image_path).unwrap());
+
+	let mut contents = fs::read(&image_path).unwrap();
+	contents.extend_from_slice(b"rescanned");
+	fs::write(&image_path, contents).unwrap();
+
+	assert!(!check_if_thumbnail_exists(&image_path).unwrap());
+}
+
+#[test]
+fn test_derivative_is_stale_when_profile_changes() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = copy_test_image(tmp_dir.path());
+	let derivative = derivative_path(&image_path, false).unwrap();
+	fs::create_dir_all(derivative.parent().unwrap()).unwrap();
+	fs::write(&derivative, b"webp").unwrap();
+
+	let source = fs::metadata(&image_path).unwrap();
+	write_stamp(&derivative, &DerivativeStamp::new(&source, "old".into())).unwrap();
+
+	assert!(is_current(&image_path, &derivative, "old").unwrap());
+	assert!(!is_current(&image_path, &derivative, "new").unwrap());
+}
+
+#[test]
+fn test_unstamped_derivative_newer_than_source_is_current() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = copy_test_image(tmp_dir.path());
+	let derivative = derivative_path(&image_path, false).unwrap();
+
+	assert!(!is_current(&image_path, &derivative, "profile").unwrap());
+
+	fs::create_dir_all(derivative.parent().unwrap()).unwrap();
+	fs::write(&derivative, b"webp").unwrap();
+
+	assert!(is_current(&image_path, &derivative, "profile").unwrap());
+}
+
+fn write_cache_entry(directory: &Path, name: &str, bytes: usize, last_used_secs: u64) {
+	let derivative = directory.join("thumbnails").join(format!("{name}.webp"));
+	fs::create_dir_all(derivative.parent().unwrap()).unwrap();
+	fs::write(&derivative, vec![0; bytes]).unwrap();
+	let stamp = derivative.with_extension("webp.json");
+	fs::write(&stamp, b"{}").unwrap();
+	fs::File::options()
+		.append(true)
+		.open(&stamp)
+		.unwrap()
+		.set_modified(UNIX_EPOCH + Duration::from_secs(last_used_secs))
+		.unwrap();
+}
+
+#[test]
+fn test_central_derivative_path_is_hashed_per_source_and_kind() {
+	let cache = Path::new("/cache/derivatives");
+	let thumbnail = central_derivative_path(cache, Path::new("/scans/a/page_1.tif"), false);
+	let preview = central_derivative_path(cache, Path::new("/scans/a/page_1.tif"), true);
+	let other = central_derivative_path(cache, Path::new("/scans/b/page_1.tif"), false);
+
+	assert!(thumbnail.starts_with("/cache/derivatives/thumbnails"));
+	assert!(preview.starts_with("/cache/derivatives/previews"));
+	assert_eq!(thumbnail.file_name(), preview.file_name());
+	assert_ne!(thumbnail.file_name(), other.file_name());
+	assert_eq!(thumbnail.extension().unwrap(), "webp");
+}
+
+#[test]
+fn test_evict_removes_least_recently_used_until_under_limit() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	write_cache_entry(tmp_dir.path(), "oldest", 1000, 100);
+	write_cache_entry(tmp_dir.path(), "newest", 1000, 300);
+	write_cache_entry(tmp_dir.path(), "middle", 1000, 200);
+
+	let evicted = evict(tmp_dir.path(), 2100).unwrap();
+
+	let thumbnails = tmp_dir.path().join("thumbnails");
+	assert_eq!(evicted, 1);
+	assert!(!thumbnails.join("oldest.webp").exists());
+	assert!(!thumbnails.join("oldest.webp.json").exists());
+	assert!(thumbnails.join("middle.webp").exists());
+	assert!(thumbnails.join("newest.webp").exists());
+
+	assert_eq!(evict(tmp_dir.path(), 2100).unwrap(), 0);
+}
+
+#[test]
+fn test_remove_derivatives_removes_thumbnail_preview_and_stamps() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = copy_test_image(tmp_dir.path());
+	let derivatives = convert_to_webp_derivatives(&image_path, true, true).unwrap();
+
+	assert_eq!(remove_derivatives(&image_path).unwrap(), 2);
+	for derivative in derivatives {
+		assert!(!derivative.exists());
+		assert!(!derivative.with_extension("webp.json").exists());
+	}
+	assert!(image_path.exists());
+	assert_eq!(remove_derivatives(&image_path).unwrap(), 0);
+}
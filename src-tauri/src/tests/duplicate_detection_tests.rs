@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ::tempfile::TempDir;
+use image::{DynamicImage, GrayImage, Luma};
+
+use crate::duplicate_detection::{
+	find_duplicate_pages, find_duplicate_pages_in_batches, group_similar, similarity,
+};
+use crate::model::BatchRepresentation;
+use crate::tests::test_utils::mark_finished;
+
+const PAPER: u8 = 235;
+const INK: u8 = 20;
+
+/// A page with lines of text in one column and a picture in the other
+fn page(text_on_left: bool) -> DynamicImage {
+	DynamicImage::ImageLuma8(GrayImage::from_fn(500, 700, |x, y| {
+		let (text, picture) = if text_on_left {
+			(75..240, 280..425)
+		} else {
+			(280..425, 75..240)
+		};
+		let ink = (text.contains(&x) && y % 20 < 3) || (picture.contains(&x) && y < 350);
+		Luma([if ink { INK } else { PAPER }])
+	}))
+}
+
+fn blank_page() -> DynamicImage {
+	DynamicImage::ImageLuma8(GrayImage::from_pixel(500, 700, Luma([PAPER])))
+}
+
+fn save(directory: &Path, name: &str, image: &DynamicImage) -> PathBuf {
+	let path = directory.join(name);
+	image.save(&path).expect("Failed to save test image");
+	mark_finished(&path);
+	path
+}
+
+fn path_string(path: &Path) -> String {
+	path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_similarity_counts_equal_bits() {
+	assert_eq!(similarity(0xFF00, 0xFF00), 1.0);
+	assert_eq!(similarity(0, u64::MAX), 0.0);
+	assert_eq!(similarity(0, 0xFFFF_FFFF), 0.5);
+}
+
+#[test]
+fn test_pages_are_not_grouped_through_a_chain_of_similar_pages() {
+	// A and B differ in 8 bits, B and C in 8 more, so A and C differ in 16 and are not similar
+	let (a, b, c) = (0, 0xFF, 0xFFFF);
+	assert!(similarity(a, b) >= 0.85 && similarity(b, c) >= 0.85);
+	assert!(similarity(a, c) < 0.85);
+
+	assert_eq!(group_similar(&[(0, a), (1, b), (2, c)]), vec![vec![0, 1]]);
+	// A page similar to every page in the group joins it
+	assert_eq!(
+		group_similar(&[(0, a), (1, b), (2, 0x7F)]),
+		vec![vec![0, 1, 2]]
+	);
+}
+
+#[test]
+fn test_rescanned_page_is_grouped_and_blank_pages_are_not() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let first = save(tmp_dir.path(), "page_1.png", &page(true));
+	save(tmp_dir.path(), "page_2.png", &page(false));
+	let rescan = save(
+		tmp_dir.path(),
+		"page_3.png",
+		&page(true).blur(0.5).brighten(-8),
+	);
+	save(tmp_dir.path(), "page_4.png", &blank_page());
+	save(tmp_dir.path(), "page_5.png", &blank_page());
+
+	let groups = find_duplicate_pages(tmp_dir.path()).unwrap();
+
+	assert_eq!(groups.len(), 1);
+	assert_eq!(groups[0].batch_id, None);
+	assert_eq!(
+		groups[0].pages,
+		vec![path_string(&first), path_string(&rescan)]
+	);
+	assert!(groups[0].similarity >= 0.85);
+}
+
+#[test]
+fn test_access_copy_of_the_same_page_is_not_a_duplicate() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let primary = [
+		save(tmp_dir.path(), "primary_1.png", &page(true)),
+		save(tmp_dir.path(), "primary_2.png", &page(false)),
+		save(tmp_dir.path(), "primary_3.png", &page(false)),
+	];
+	let access = [
+		save(tmp_dir.path(), "access_1.png", &page(true).blur(0.5)),
+		save(tmp_dir.path(), "access_2.png", &page(false).blur(0.5)),
+		save(tmp_dir.path(), "access_3.png", &page(false).blur(0.5)),
+	];
+	let batch_map = HashMap::from([(
+		"object".to_string(),
+		BatchRepresentation {
+			primary: primary.iter().map(|p| path_string(p)).collect(),
+			access: access.iter().map(|p| path_string(p)).collect(),
+		},
+	)]);
+
+	let groups = find_duplicate_pages_in_batches(&batch_map).unwrap();
+
+	// Page 2 and 3 are double-fed, in both the primary and the access set
+	assert_eq!(groups.len(), 1);
+	assert_eq!(groups[0].batch_id.as_deref(), Some("object"));
+	assert_eq!(
+		groups[0].pages,
+		vec![
+			path_string(&primary[1]),
+			path_string(&primary[2]),
+			path_string(&access[1]),
+			path_string(&access[2]),
+		]
+	);
+}
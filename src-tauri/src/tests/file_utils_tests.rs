@@ -0,0 +1,91 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use ::tempfile::TempDir;
+
+use crate::file_utils::{FileStability, unfinished_files, wait_until_finished, write_atomically};
+use crate::tests::test_utils::{TEST_IMAGE_PNG, get_test_resource_dir};
+
+const STABILITY: FileStability = FileStability {
+	interval: Duration::from_millis(300),
+	parse_header: true,
+};
+
+#[test]
+fn test_write_atomically_creates_folder_and_leaves_no_temporary_file() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let path = tmp_dir.path().join(".thumbnails").join("page_1.webp");
+
+	write_atomically(&path, b"first").unwrap();
+	write_atomically(&path, b"second").unwrap();
+
+	assert_eq!(fs::read(&path).unwrap(), b"second");
+	let names: Vec<_> = fs::read_dir(path.parent().unwrap())
+		.unwrap()
+		.map(|entry| entry.unwrap().file_name())
+		.collect();
+	assert_eq!(names, vec!["page_1.webp"]);
+}
+
+#[test]
+fn test_unfinished_files_reports_files_still_being_written() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let path = tmp_dir.path().join("page_1.tif");
+	let mut file = File::create(&path).unwrap();
+	file.write_all(b"II*\0").unwrap();
+
+	let writer = thread::spawn(move || {
+		thread::sleep(Duration::from_millis(100));
+		file.write_all(&[0; 1024]).unwrap();
+	});
+	let unfinished = unfinished_files(&[&path], &STABILITY);
+	writer.join().unwrap();
+
+	assert_eq!(unfinished.len(), 1);
+	assert_eq!(unfinished[0].0, path);
+	assert!(unfinished[0].1.contains("still being written"));
+}
+
+#[test]
+fn test_unfinished_files_does_not_wait_for_files_modified_long_ago() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image = tmp_dir.path().join(TEST_IMAGE_PNG);
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), &image).unwrap();
+	let truncated = tmp_dir.path().join("truncated.png");
+	fs::write(&truncated, b"\x89PNG").unwrap();
+	let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+	for path in [&image, &truncated] {
+		File::options()
+			.write(true)
+			.open(path)
+			.unwrap()
+			.set_modified(an_hour_ago)
+			.unwrap();
+	}
+
+	let started = Instant::now();
+	let unfinished = unfinished_files(&[&image, &truncated], &STABILITY);
+
+	assert!(started.elapsed() < STABILITY.interval);
+	assert_eq!(unfinished.len(), 1);
+	assert_eq!(unfinished[0].0, truncated);
+	assert!(unfinished[0].1.contains("image header"));
+}
+
+#[test]
+fn test_wait_until_finished_returns_files_once_they_stop_changing() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image = tmp_dir.path().join(TEST_IMAGE_PNG);
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), &image).unwrap();
+	let removed = tmp_dir.path().join("removed.png");
+
+	let finished = wait_until_finished(
+		vec![image.clone(), removed],
+		&STABILITY,
+		Duration::from_secs(10),
+	);
+
+	assert_eq!(finished, vec![image]);
+}
@@ -37,3 +37,13 @@ fn test_image_conversion_error_str_error_should_display_correct_error_message()
 	let expected_error_message = "Some error";
 	assert_eq!(actual_error_message, expected_error_message);
 }
+
+#[test]
+fn test_image_conversion_error_kind_names_the_variant() {
+	let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
+	assert_eq!(ImageConversionError::IoError(io_error).kind(), "io");
+	assert_eq!(
+		ImageConversionError::StrError("Some error".to_string()).kind(),
+		"other"
+	);
+}
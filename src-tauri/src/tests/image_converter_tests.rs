@@ -1,20 +1,22 @@
 use std::ffi::OsStr;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 use ::tempfile::TempDir;
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, Luma};
 
 use crate::image_converter::*;
+use crate::model::DerivativeReady;
 use crate::tests::test_utils::TEST_IMAGE_PNG;
-use crate::tests::test_utils::get_test_resource_dir;
+use crate::tests::test_utils::copy_finished_test_image;
 
 fn setup_temp_dir<F: FnMut(PathBuf)>(mut handler: F) {
-	let input_image_path = get_test_resource_dir().join(TEST_IMAGE_PNG);
-
 	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
 	let tmp_img_path = tmp_dir.path().join(TEST_IMAGE_PNG);
 
-	fs::copy(&input_image_path, &tmp_img_path).expect("Failed to copy test image to temp dir");
+	copy_finished_test_image(&tmp_img_path);
 
 	handler(tmp_img_path);
 }
@@ -49,3 +51,221 @@ fn test_check_if_webp_exists_returns_true_if_webp_exists() {
 		assert!(webp_exists);
 	});
 }
+
+#[test]
+fn test_convert_to_webp_derivatives_creates_thumbnail_and_preview() {
+	setup_temp_dir(|tmp_img_path| {
+		let paths = convert_to_webp_derivatives(&tmp_img_path, true, true).unwrap();
+
+		assert_eq!(paths.len(), 2);
+		assert!(check_if_thumbnail_exists(&tmp_img_path).unwrap());
+		assert!(check_if_preview_exists(&tmp_img_path).unwrap());
+	});
+}
+
+/// Held by tests that register a derivative listener, so they do not replace each other's
+static DERIVATIVE_LISTENER_TEST: Mutex<()> = Mutex::new(());
+
+/// Registers a listener for the duration of a test, and restores the one before when dropped
+struct ListenerGuard {
+	previous: Option<DerivativeListener>,
+	_serial: MutexGuard<'static, ()>,
+}
+
+impl ListenerGuard {
+	fn register(listener: impl Fn(DerivativeReady) + Send + Sync + 'static) -> Self {
+		let serial = DERIVATIVE_LISTENER_TEST
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner);
+		Self {
+			previous: replace_derivative_listener(Some(Box::new(listener))),
+			_serial: serial,
+		}
+	}
+}
+
+impl Drop for ListenerGuard {
+	fn drop(&mut self) {
+		replace_derivative_listener(self.previous.take());
+	}
+}
+
+#[test]
+fn test_derivative_listener_is_told_about_every_derivative_written() {
+	setup_temp_dir(|tmp_img_path| {
+		let (sender, receiver) = std::sync::mpsc::channel();
+		let sender = Mutex::new(sender);
+		let _listener = ListenerGuard::register(move |ready| {
+			let _ = sender.lock().unwrap().send(ready);
+		});
+
+		let paths = convert_to_webp_derivatives(&tmp_img_path, true, true).unwrap();
+
+		// Other tests convert images at the same time, so only look at this image
+		let ready: Vec<_> = receiver
+			.try_iter()
+			.filter(|ready| ready.source == tmp_img_path)
+			.collect();
+		assert_eq!(ready.len(), 2);
+		assert_eq!(ready[0].path, paths[0]);
+		assert!(!ready[0].high_res);
+		assert_eq!(ready[1].path, paths[1]);
+		assert!(ready[1].high_res);
+	});
+}
+
+#[test]
+fn test_convert_directory_to_webp_counts_converted_and_already_converted() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	for name in ["page_1.png", "page_2.png", "page_3.png"] {
+		copy_finished_test_image(&tmp_dir.path().join(name));
+	}
+	convert_to_webp(tmp_dir.path().join("page_1.png"), false).unwrap();
+
+	let progress = Mutex::new(Vec::new());
+	let count =
+		convert_directory_to_webp(tmp_dir.path(), |p| progress.lock().unwrap().push(p)).unwrap();
+
+	assert_eq!(count.converted, 2);
+	assert_eq!(count.already_converted, 1);
+
+	let progress = progress.into_inner().unwrap();
+	let done: Vec<usize> = progress.iter().map(|p| p.done).collect();
+	assert_eq!(done, vec![1, 2, 3]);
+	assert!(progress.iter().all(|p| p.total == 3 && p.failures == 0));
+}
+
+#[test]
+fn test_convert_directory_to_webp_continues_past_corrupt_images() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	copy_finished_test_image(&tmp_dir.path().join("page_1.png"));
+	fs::write(tmp_dir.path().join("page_2.tif"), b"not a tiff").unwrap();
+	copy_finished_test_image(&tmp_dir.path().join("page_3.png"));
+
+	let count = convert_directory_to_webp(tmp_dir.path(), |_| {}).unwrap();
+
+	assert_eq!(count.converted, 2);
+	assert_eq!(count.failed, 1);
+	let failed: Vec<&FileConversionResult> = count
+		.files
+		.iter()
+		.filter(|f| f.outcome == ConversionOutcome::Failed)
+		.collect();
+	assert_eq!(failed.len(), 1);
+	assert!(failed[0].path.ends_with("page_2.tif"));
+	assert_eq!(failed[0].error_kind.as_deref(), Some("image"));
+	assert!(failed[0].error_message.is_some());
+}
+
+#[test]
+fn test_conversion_profile_accepts_valid_settings() {
+	let profile = ConversionProfile::new(
+		"lanczos3",
+		80.0,
+		false,
+		TargetSize::LongEdge { pixels: 400 },
+	)
+	.unwrap();
+
+	assert_eq!(profile.filter, ResizeFilter::Lanczos3);
+	assert_eq!(profile.quality, 80.0);
+	assert_eq!(profile.size, TargetSize::LongEdge { pixels: 400 });
+}
+
+#[test]
+fn test_conversion_profile_rejects_invalid_settings() {
+	let half = TargetSize::Fraction { fraction: 2 };
+	assert!(ConversionProfile::new("bicubic", 80.0, false, half).is_err());
+	assert!(ConversionProfile::new("nearest", 101.0, false, half).is_err());
+	assert!(
+		ConversionProfile::new("nearest", 80.0, false, TargetSize::Fraction { fraction: 0 })
+			.is_err()
+	);
+	assert!(
+		ConversionProfile::new("nearest", 80.0, false, TargetSize::LongEdge { pixels: 1 }).is_err()
+	);
+	let profile = ConversionProfile::new("nearest", 80.0, false, half).unwrap();
+	assert!(set_conversion_profile("original", profile).is_err());
+}
+
+#[test]
+fn test_target_size_dimensions() {
+	let fraction = TargetSize::Fraction { fraction: 4 };
+	assert_eq!(fraction.dimensions(12000, 8000), (3000, 2000));
+
+	let long_edge = TargetSize::LongEdge { pixels: 600 };
+	assert_eq!(long_edge.dimensions(12000, 8000), (600, 400));
+	assert_eq!(long_edge.dimensions(2000, 3000), (400, 600));
+	// Never scaled up
+	assert_eq!(long_edge.dimensions(300, 200), (300, 200));
+
+	let bounding_box = TargetSize::BoundingBox {
+		width: 800,
+		height: 200,
+	};
+	assert_eq!(bounding_box.dimensions(12000, 8000), (300, 200));
+
+	let size: TargetSize = serde_json::from_str(r#"{"mode":"longEdge","pixels":600}"#).unwrap();
+	assert_eq!(size, long_edge);
+}
+
+#[test]
+fn test_target_size_with_fraction_keeps_pixel_sizes() {
+	assert_eq!(
+		TargetSize::Fraction { fraction: 8 }.with_fraction(2),
+		TargetSize::Fraction { fraction: 2 }
+	);
+	let long_edge = TargetSize::LongEdge { pixels: 600 };
+	assert_eq!(long_edge.with_fraction(2), long_edge);
+	let bounding_box = TargetSize::BoundingBox {
+		width: 800,
+		height: 200,
+	};
+	assert_eq!(bounding_box.with_fraction(2), bounding_box);
+}
+
+/// A 3 x 2 image where every pixel is different, so any transform can be told apart
+fn asymmetric_image() -> DynamicImage {
+	DynamicImage::ImageLuma8(GrayImage::from_fn(3, 2, |x, y| Luma([(y * 3 + x) as u8])))
+}
+
+fn oriented(orientation: Orientation) -> DynamicImage {
+	let mut image = asymmetric_image();
+	image.apply_orientation(orientation);
+	image
+}
+
+type OrientationChange = fn(Orientation) -> Orientation;
+type PixelTransform = fn(&DynamicImage) -> DynamicImage;
+
+#[test]
+fn test_orientation_changes_compose_with_every_exif_orientation() {
+	let changes: [(OrientationChange, PixelTransform); 4] = [
+		(rotate_clockwise, DynamicImage::rotate90),
+		(rotate_counter_clockwise, DynamicImage::rotate270),
+		(flip_horizontal, DynamicImage::fliph),
+		(flip_vertical, DynamicImage::flipv),
+	];
+	for exif in 1..=8 {
+		let orientation = Orientation::from_exif(exif).unwrap();
+		for (change, transform) in changes {
+			assert_eq!(
+				oriented(change(orientation)),
+				transform(&oriented(orientation)),
+				"orientation {exif}"
+			);
+		}
+	}
+}
+
+#[test]
+fn test_rotating_a_mirrored_image_keeps_it_mirrored() {
+	assert_eq!(
+		rotate_clockwise(Orientation::FlipHorizontal),
+		Orientation::Rotate270FlipH
+	);
+	assert_eq!(
+		flip_horizontal(Orientation::FlipHorizontal),
+		Orientation::NoTransforms
+	);
+}
@@ -0,0 +1,110 @@
+use crate::key_layout::{KeyLayout, PageKey};
+
+fn page<'a>(representation: Option<&'a str>, material_type: Option<&'a str>) -> PageKey<'a> {
+	PageKey {
+		object_id: "tekst_0a1b2c",
+		representation,
+		page_nr: 7,
+		extension: "tif",
+		material_type,
+		date: "2026-10-18",
+	}
+}
+
+#[test]
+fn test_default_key_layout_matches_ingest_convention() {
+	let layout = KeyLayout::default();
+
+	assert_eq!(layout.object_id("0a1b2c", None).unwrap(), "tekst_0a1b2c");
+	assert_eq!(
+		layout.batch_key(&page(Some("primary"), None)).unwrap(),
+		"tekst_0a1b2c/representations/primary/data/tekst_0a1b2c_00007.tif"
+	);
+	assert_eq!(
+		layout.directory_key(&page(None, None)).unwrap(),
+		"tekst_0a1b2c/tekst_0a1b2c_00007.tif"
+	);
+	assert_eq!(
+		layout
+			.manifest_key("tekst_0a1b2c", None, "2026-10-18")
+			.unwrap(),
+		"tekst_0a1b2c/manifest.json"
+	);
+}
+
+#[test]
+fn test_custom_key_layout_renders_every_placeholder() {
+	let layout = KeyLayout::new(
+		"{material_type}-{batch_id}",
+		"{date}/{material_type}/{object_id}/{representation}/{page:3}.{extension}",
+		"{object_id}/{page}.{extension}",
+	)
+	.unwrap();
+
+	assert_eq!(
+		layout.object_id("0a1b2c", Some("MAP")).unwrap(),
+		"MAP-0a1b2c"
+	);
+	assert_eq!(
+		layout
+			.batch_key(&page(Some("access"), Some("MAP")))
+			.unwrap(),
+		"2026-10-18/MAP/tekst_0a1b2c/access/007.tif"
+	);
+	assert_eq!(
+		layout.directory_key(&page(None, None)).unwrap(),
+		"tekst_0a1b2c/7.tif"
+	);
+	assert!(layout.object_id("0a1b2c", None).is_err());
+	// Verification lists the folder of the batch, and the manifest is stored there
+	assert_eq!(
+		layout
+			.batch_prefix("tekst_0a1b2c", Some("MAP"), "2026-10-18")
+			.unwrap(),
+		"2026-10-18/MAP/tekst_0a1b2c/"
+	);
+	assert_eq!(
+		layout
+			.manifest_key("tekst_0a1b2c", Some("MAP"), "2026-10-18")
+			.unwrap(),
+		"2026-10-18/MAP/tekst_0a1b2c/manifest.json"
+	);
+}
+
+#[test]
+fn test_key_layout_rejects_invalid_templates() {
+	let batch_key = "{object_id}/{representation}/{page:05}.{extension}";
+	let directory_key = "{object_id}/{page:05}.{extension}";
+
+	assert!(KeyLayout::new("tekst_{batch_id}", batch_key, directory_key).is_ok());
+	// Unknown placeholder
+	assert!(KeyLayout::new("tekst_{batch}", batch_key, directory_key).is_err());
+	// Object ids name the upload journal and cannot depend on the date
+	assert!(KeyLayout::new("{date}_{batch_id}", batch_key, directory_key).is_err());
+	// Pages and representations would overwrite each other
+	assert!(KeyLayout::new("tekst_{batch_id}", "{object_id}/{page}", directory_key).is_err());
+	assert!(KeyLayout::new("tekst_{batch_id}", batch_key, "{object_id}.{extension}").is_err());
+	// Every page of a batch must be in the folder of the object, so it can be listed
+	assert!(
+		KeyLayout::new(
+			"tekst_{batch_id}",
+			"{representation}/{object_id}/{page}.{extension}",
+			directory_key
+		)
+		.is_err()
+	);
+	assert!(
+		KeyLayout::new(
+			"tekst_{batch_id}",
+			"{object_id}_{representation}_{page}.{extension}",
+			directory_key
+		)
+		.is_err()
+	);
+	// Only the page can be padded
+	assert!(KeyLayout::new("tekst_{batch_id:5}", batch_key, directory_key).is_err());
+	assert!(KeyLayout::new("tekst_{batch_id}", batch_key, "{object_id}/{page:x}").is_err());
+	// Unbalanced braces
+	assert!(KeyLayout::new("tekst_{batch_id", batch_key, directory_key).is_err());
+	assert!(KeyLayout::new("tekst_batch_id}", batch_key, directory_key).is_err());
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+
+use crate::manifest::{ManifestBatch, build_manifest, format_rfc3339};
+use crate::model::{BatchPage, UploadedObject};
+
+fn batch<'a>() -> ManifestBatch<'a> {
+	ManifestBatch {
+		object_id: "tekst_0a1b2c",
+		batch_id: "0a1b2c",
+		material_type: Some("NEWSPAPER"),
+		hostname: "scanner-01",
+		uploaded_at: "2024-05-17T08:30:00Z",
+	}
+}
+
+fn page(key: &str, path: &str, representation: &'static str, page_nr: usize) -> BatchPage {
+	BatchPage {
+		key: key.to_string(),
+		path: PathBuf::from(path),
+		representation,
+		page_nr,
+	}
+}
+
+fn uploaded(key: &str, size: usize, sha256: &str) -> UploadedObject {
+	UploadedObject {
+		key: key.to_string(),
+		path: String::new(),
+		size,
+		sha256: sha256.to_string(),
+	}
+}
+
+#[test]
+fn test_build_manifest_lists_every_page_with_its_checksum() {
+	let pages = vec![
+		page(
+			"tekst_0a1b2c/primary_1.tif",
+			"/scans/page_1.tif",
+			"primary",
+			1,
+		),
+		page(
+			"tekst_0a1b2c/access_1.webp",
+			"/scans/.previews/page_1.webp",
+			"access",
+			1,
+		),
+	];
+	let objects = vec![
+		uploaded("tekst_0a1b2c/access_1.webp", 10, "bb"),
+		uploaded("tekst_0a1b2c/primary_1.tif", 100, "aa"),
+	];
+
+	let manifest = build_manifest(batch(), &pages, &objects).unwrap();
+
+	assert_eq!(manifest.object_id, "tekst_0a1b2c");
+	assert_eq!(manifest.hostname, "scanner-01");
+	assert_eq!(manifest.pages.len(), 2);
+	assert_eq!(manifest.pages[0].representation, "primary");
+	assert_eq!(manifest.pages[0].size, 100);
+	assert_eq!(manifest.pages[0].sha256, "aa");
+	assert_eq!(manifest.pages[0].original_filename, "page_1.tif");
+	assert_eq!(manifest.pages[1].representation, "access");
+	assert_eq!(manifest.pages[1].sha256, "bb");
+
+	let json = serde_json::to_value(&manifest).unwrap();
+	assert_eq!(json["materialType"], "NEWSPAPER");
+	assert_eq!(json["uploadedAt"], "2024-05-17T08:30:00Z");
+	assert_eq!(json["pages"][0]["originalFilename"], "page_1.tif");
+	assert_eq!(json["pages"][0]["pageNr"], 1);
+}
+
+#[test]
+fn test_build_manifest_fails_if_a_page_was_not_uploaded() {
+	let pages = vec![page(
+		"tekst_0a1b2c/primary_1.tif",
+		"/scans/page_1.tif",
+		"primary",
+		1,
+	)];
+
+	let result = build_manifest(batch(), &pages, &[]);
+
+	assert!(result.is_err());
+}
+
+#[test]
+fn test_timestamp_format() {
+	assert_eq!(
+		format_rfc3339(
+			PrimitiveDateTime::new(
+				Date::from_calendar_date(2024, Month::May, 17).unwrap(),
+				Time::from_hms(8, 30, 5).unwrap(),
+			)
+			.assume_offset(UtcOffset::from_hms(2, 0, 0).unwrap())
+		),
+		"2024-05-17T06:30:05Z"
+	);
+}
@@ -1,4 +1,29 @@
 mod auth_token_tests;
+mod batch_validation_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod checksum_tests;
+mod derivative_cache_tests;
+mod duplicate_detection_tests;
+mod file_utils_tests;
 mod image_conversion_error_test;
 mod image_converter_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod key_layout_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod manifest_tests;
+mod orientation_sidecar_tests;
+mod page_analysis_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod retry_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod s3_tests;
+mod scan_watcher_tests;
 mod test_utils;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_error_test;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_journal_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_progress_tests;
+#[cfg(not(feature = "debug-mock"))]
+mod upload_verification_tests;
@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::Path;
+
+use ::tempfile::TempDir;
+use image::DynamicImage;
+use image::metadata::Orientation;
+
+use crate::image_converter::{convert_to_webp, read_orientation};
+#[cfg(not(feature = "debug-mock"))]
+use crate::orientation_sidecar::object_metadata;
+use crate::orientation_sidecar::{
+	central_sidecar_path, remove_orientation, sidecar_path, write_orientation,
+};
+use crate::tests::test_utils::mark_finished;
+
+#[test]
+fn test_sidecar_path_is_in_orientation_folder_next_to_image() {
+	let path = sidecar_path(Path::new("/scans/batch/page_1.png")).unwrap();
+
+	assert_eq!(path, Path::new("/scans/batch/.orientation/page_1.png.json"));
+}
+
+#[test]
+fn test_central_sidecar_path_is_hashed_per_source() {
+	let cache = Path::new("/cache/derivatives");
+	let sidecar = central_sidecar_path(cache, Path::new("/scans/a/page_1.png"));
+	let other = central_sidecar_path(cache, Path::new("/scans/b/page_1.png"));
+
+	assert!(sidecar.starts_with("/cache/derivatives/orientation"));
+	assert_ne!(sidecar.file_name(), other.file_name());
+	assert_eq!(sidecar.extension().unwrap(), "json");
+}
+
+#[test]
+fn test_sidecar_orientation_is_used_when_rendering() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = tmp_dir.path().join("page_1.png");
+	fs::write(&image_path, b"").unwrap();
+
+	assert_eq!(read_orientation(&image_path), Orientation::NoTransforms);
+
+	write_orientation(&image_path, Orientation::Rotate90FlipH.to_exif()).unwrap();
+
+	assert_eq!(read_orientation(&image_path), Orientation::Rotate90FlipH);
+}
+
+#[test]
+fn test_invalid_sidecar_is_ignored() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = tmp_dir.path().join("page_1.png");
+	let sidecar = sidecar_path(&image_path).unwrap();
+	fs::create_dir_all(sidecar.parent().unwrap()).unwrap();
+	fs::write(&sidecar, b"{\"orientation\": 42}").unwrap();
+
+	assert_eq!(read_orientation(&image_path), Orientation::NoTransforms);
+}
+
+#[test]
+fn test_convert_to_webp_honours_sidecar_of_png_without_writable_exif() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = tmp_dir.path().join("page_1.png");
+	DynamicImage::new_rgb8(800, 400).save(&image_path).unwrap();
+	mark_finished(&image_path);
+	// What rotate_image records when the EXIF orientation of the PNG cannot be written
+	write_orientation(&image_path, Orientation::Rotate90.to_exif()).unwrap();
+
+	let thumbnail = convert_to_webp(&image_path, false).unwrap();
+
+	let (width, height) = image::image_dimensions(thumbnail).unwrap();
+	assert!(height > width);
+}
+
+#[cfg(not(feature = "debug-mock"))]
+#[test]
+fn test_sidecar_orientation_is_uploaded_as_object_metadata() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = tmp_dir.path().join("page_1.png");
+
+	assert_eq!(object_metadata(&image_path), None);
+
+	write_orientation(&image_path, Orientation::Rotate270.to_exif()).unwrap();
+
+	let metadata = object_metadata(&image_path).unwrap();
+	assert_eq!(metadata["orientation"], "8");
+}
+
+#[test]
+fn test_remove_orientation_deletes_sidecar() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = tmp_dir.path().join("page_1.png");
+	write_orientation(&image_path, Orientation::Rotate90.to_exif()).unwrap();
+
+	assert!(remove_orientation(&image_path).unwrap());
+	assert!(!sidecar_path(&image_path).unwrap().exists());
+	assert!(!remove_orientation(&image_path).unwrap());
+}
@@ -0,0 +1,96 @@
+use image::{DynamicImage, GrayImage, Luma};
+
+use ::tempfile::TempDir;
+
+use crate::derivative_cache::{current_stamp, derivative_path};
+use crate::image_converter::get_conversion_profile;
+use crate::page_analysis::{Blankness, analyze, analyze_page, set_sharpness_threshold};
+use crate::tests::test_utils::{TEST_IMAGE_PNG, copy_finished_test_image};
+
+const PAPER: u8 = 235;
+const INK: u8 = 20;
+
+fn page(draw: impl Fn(u32, u32) -> bool) -> DynamicImage {
+	DynamicImage::ImageLuma8(GrayImage::from_fn(1000, 1400, |x, y| {
+		Luma([if draw(x, y) { INK } else { PAPER }])
+	}))
+}
+
+#[test]
+fn test_empty_page_is_blank() {
+	let analysis = analyze(&page(|_, _| false));
+
+	assert_eq!(analysis.ink_coverage, 0.0);
+	assert_eq!(analysis.blankness(), Blankness::Blank);
+}
+
+#[test]
+fn test_page_with_a_few_marks_is_nearly_blank() {
+	// Two small blots in the middle of the page, like a stamp and a page number
+	let analysis = analyze(&page(|x, y| {
+		(400..440).contains(&x) && ((500..540).contains(&y) || (900..940).contains(&y))
+	}));
+
+	assert_eq!(analysis.blankness(), Blankness::NearlyBlank);
+}
+
+#[test]
+fn test_page_with_text_lines_has_content() {
+	let analysis = analyze(&page(|x, y| (150..850).contains(&x) && y % 40 < 6));
+
+	assert!(analysis.ink_coverage > 0.05);
+	assert_eq!(analysis.blankness(), Blankness::Content);
+}
+
+#[test]
+fn test_dark_scanner_bed_around_empty_page_is_ignored() {
+	let analysis = analyze(&page(|x, y| {
+		!(60..940).contains(&x) || !(80..1320).contains(&y)
+	}));
+
+	assert_eq!(analysis.blankness(), Blankness::Blank);
+}
+
+fn words() -> DynamicImage {
+	page(|x, y| (150..850).contains(&x) && y % 40 < 6 && x % 30 < 20)
+}
+
+#[test]
+fn test_page_in_focus_is_sharper_than_blurred_page() {
+	let sharp = analyze(&words());
+	let blurred = analyze(&words().blur(4.0));
+
+	assert!(sharp.sharpness > blurred.sharpness);
+	assert!(!sharp.is_blurry(100.0));
+	assert!(blurred.is_blurry(100.0));
+}
+
+#[test]
+fn test_blank_page_is_never_blurry() {
+	let analysis = analyze(&page(|_, _| false));
+
+	assert_eq!(analysis.sharpness, 0.0);
+	assert!(!analysis.is_blurry(100.0));
+}
+
+#[test]
+fn test_set_sharpness_threshold_rejects_negative_and_nan() {
+	assert!(set_sharpness_threshold(-1.0).is_err());
+	assert!(set_sharpness_threshold(f32::NAN).is_err());
+}
+
+#[test]
+fn test_analyze_page_stores_analysis_with_thumbnail() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let image_path = tmp_dir.path().join(TEST_IMAGE_PNG);
+	copy_finished_test_image(&image_path);
+
+	let analysis = analyze_page(&image_path).unwrap();
+
+	let thumbnail = derivative_path(&image_path, false).unwrap();
+	let profile = get_conversion_profile(false).fingerprint();
+	let stamp = current_stamp(&image_path, &thumbnail, &profile).unwrap();
+	assert!(thumbnail.exists());
+	assert_eq!(stamp.analysis, Some(analysis));
+	assert_eq!(analyze_page(&image_path).unwrap(), analysis);
+}
@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ErrorMetadata, SdkError};
+use aws_sdk_s3::operation::put_object::PutObjectError;
+use aws_sdk_s3::primitives::SdkBody;
+
+use crate::error::UploadError;
+use crate::retry::{AttemptError, RetryPolicy, is_retryable, with_retry};
+use crate::s3::CancellationFlag;
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+	RetryPolicy {
+		max_attempts,
+		initial_backoff: Duration::from_millis(1),
+		max_backoff: Duration::from_millis(2),
+		jitter: false,
+	}
+}
+
+fn service_error(status: u16, code: &str) -> SdkError<PutObjectError, HttpResponse> {
+	SdkError::service_error(
+		PutObjectError::generic(ErrorMetadata::builder().code(code).build()),
+		HttpResponse::new(status.try_into().unwrap(), SdkBody::empty()),
+	)
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+	tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(future)
+}
+
+#[test]
+fn test_retry_policy_backoff_doubles_up_to_max_and_applies_jitter() {
+	let policy = RetryPolicy {
+		max_attempts: 5,
+		initial_backoff: Duration::from_millis(500),
+		max_backoff: Duration::from_secs(3),
+		jitter: false,
+	};
+	assert_eq!(policy.backoff(1, 0.5), Duration::from_millis(500));
+	assert_eq!(policy.backoff(2, 0.5), Duration::from_millis(1000));
+	assert_eq!(policy.backoff(3, 0.5), Duration::from_millis(2000));
+	assert_eq!(policy.backoff(4, 0.5), Duration::from_secs(3));
+
+	let jittered = RetryPolicy {
+		jitter: true,
+		..policy
+	};
+	assert_eq!(jittered.backoff(2, 0.0), Duration::from_millis(500));
+	assert_eq!(jittered.backoff(2, 1.0), Duration::from_millis(1000));
+}
+
+#[test]
+fn test_is_retryable_distinguishes_transient_and_permanent_errors() {
+	assert!(is_retryable(&service_error(503, "ServiceUnavailable")));
+	assert!(is_retryable(&service_error(500, "InternalError")));
+	assert!(is_retryable(&service_error(400, "RequestTimeout")));
+	assert!(is_retryable(&service_error(429, "TooManyRequests")));
+	assert!(is_retryable(
+		&SdkError::<PutObjectError, HttpResponse>::timeout_error("timed out")
+	));
+
+	assert!(!is_retryable(&service_error(403, "AccessDenied")));
+	assert!(!is_retryable(&service_error(404, "NoSuchBucket")));
+}
+
+#[test]
+fn test_with_retry_retries_transient_failures_until_success() {
+	let attempts = AtomicU32::new(0);
+
+	let result = block_on(with_retry(
+		&fast_policy(3),
+		"test upload",
+		&CancellationFlag::default(),
+		|| async {
+			if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+				Err(AttemptError::Retryable("503".to_string()))
+			} else {
+				Ok("uploaded")
+			}
+		},
+	));
+
+	assert_eq!(result, Ok("uploaded"));
+	assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_with_retry_gives_up_after_max_attempts_and_on_permanent_errors() {
+	let attempts = AtomicU32::new(0);
+	let result: Result<(), UploadError> = block_on(with_retry(
+		&fast_policy(2),
+		"test upload",
+		&CancellationFlag::default(),
+		|| async {
+			attempts.fetch_add(1, Ordering::SeqCst);
+			Err(AttemptError::Retryable("503".to_string()))
+		},
+	));
+	assert_eq!(
+		result,
+		Err(UploadError::Failed(
+			"503 (gave up after 2 attempts)".to_string()
+		))
+	);
+	assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+	let attempts = AtomicU32::new(0);
+	let result: Result<(), UploadError> = block_on(with_retry(
+		&fast_policy(5),
+		"test upload",
+		&CancellationFlag::default(),
+		|| async {
+			attempts.fetch_add(1, Ordering::SeqCst);
+			Err(AttemptError::from("403 AccessDenied".to_string()))
+		},
+	));
+	assert_eq!(
+		result,
+		Err(UploadError::Failed("403 AccessDenied".to_string()))
+	);
+	assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_with_retry_stops_when_cancelled() {
+	let cancellation = CancellationFlag::default();
+	cancellation.cancel();
+
+	let result: Result<(), UploadError> = block_on(with_retry(
+		&fast_policy(3),
+		"test upload",
+		&cancellation,
+		|| async { Ok(()) },
+	));
+
+	assert_eq!(result, Err(UploadError::Cancelled));
+}
@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ::tempfile::TempDir;
+use aws_sdk_s3::Client;
+use aws_sdk_s3::config::retry::RetryConfig;
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::types::CompletedPart;
+use bytes::Bytes;
+
+use crate::checksum;
+use crate::error::UploadError;
+use crate::model::{MultipartUploadRequest, SecretVariables};
+use crate::s3::{
+	CancellationFlag, cancel_upload, complete_multipart_upload, discard_journals, multipart_upload,
+	register_upload_job, resume_multipart_upload,
+};
+use crate::s3::{run_bounded, verify_checksum};
+use crate::upload_journal::{FileVersion, UploadJournal};
+use crate::upload_progress::UploadProgress;
+use crate::upload_verification::list_prefix;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+	tokio::runtime::Builder::new_multi_thread()
+		.worker_threads(4)
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(future)
+}
+
+#[test]
+fn test_run_bounded_runs_every_job_exactly_once_within_limit() {
+	let in_flight = Arc::new(AtomicUsize::new(0));
+	let max_in_flight = Arc::new(AtomicUsize::new(0));
+	let keys: Vec<String> = (1..=40).map(|page| format!("page_{page:05}")).collect();
+	let mut uploaded: HashMap<String, usize> = HashMap::new();
+
+	block_on(run_bounded(
+		keys.clone(),
+		3,
+		|key| {
+			let in_flight = in_flight.clone();
+			let max_in_flight = max_in_flight.clone();
+			async move {
+				let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+				max_in_flight.fetch_max(current, Ordering::SeqCst);
+				for _ in 0..10 {
+					tokio::task::yield_now().await;
+				}
+				in_flight.fetch_sub(1, Ordering::SeqCst);
+				Ok::<_, String>(key)
+			}
+		},
+		|key| {
+			*uploaded.entry(key).or_default() += 1;
+			Ok(())
+		},
+	))
+	.unwrap();
+
+	assert_eq!(uploaded.len(), keys.len());
+	assert!(uploaded.values().all(|count| *count == 1));
+	assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+}
+
+#[test]
+fn test_run_bounded_returns_first_error() {
+	let result = block_on(run_bounded(
+		(1..=10).collect(),
+		2,
+		|page: usize| async move {
+			if page == 4 {
+				Err(format!("page {page} failed"))
+			} else {
+				Ok(page)
+			}
+		},
+		|_| Ok(()),
+	));
+
+	assert_eq!(result.unwrap_err(), "page 4 failed");
+}
+
+#[test]
+fn test_run_bounded_lets_running_tasks_finish_after_an_error() {
+	let started = Arc::new(AtomicUsize::new(0));
+	let finished = Arc::new(AtomicUsize::new(0));
+
+	let result = block_on(run_bounded(
+		(1..=10).collect(),
+		2,
+		|page: usize| {
+			let started = started.clone();
+			let finished = finished.clone();
+			async move {
+				started.fetch_add(1, Ordering::SeqCst);
+				if page == 1 {
+					return Err(format!("page {page} failed"));
+				}
+				for _ in 0..10 {
+					tokio::task::yield_now().await;
+				}
+				finished.fetch_add(1, Ordering::SeqCst);
+				Ok(page)
+			}
+		},
+		|_| Ok(()),
+	));
+
+	assert_eq!(result.unwrap_err(), "page 1 failed");
+	assert!(started.load(Ordering::SeqCst) < 10);
+	assert_eq!(
+		finished.load(Ordering::SeqCst),
+		started.load(Ordering::SeqCst) - 1
+	);
+}
+
+#[test]
+fn test_cancel_upload_cancels_registered_job_only_while_it_runs() {
+	let job_id = "0192d7a4-cancel-test";
+	assert!(!cancel_upload(job_id).unwrap());
+
+	let job = register_upload_job(job_id).unwrap();
+	assert!(register_upload_job(job_id).is_err());
+	assert!(job.cancellation.check().is_ok());
+
+	assert!(cancel_upload(job_id).unwrap());
+	assert!(job.cancellation.is_cancelled());
+	assert_eq!(job.cancellation.check(), Err(UploadError::Cancelled));
+
+	drop(job);
+	assert!(!cancel_upload(job_id).unwrap());
+}
+
+#[test]
+fn test_verify_checksum_fails_on_mismatch_or_missing_checksum() {
+	let key = "tekst_0a1b2c/tekst_0a1b2c_00001.tif";
+
+	assert!(verify_checksum(key, "abc=", Some("abc=")).is_ok());
+	assert!(
+		verify_checksum(key, "abc=", Some("xyz="))
+			.unwrap_err()
+			.contains("Checksum mismatch")
+	);
+	assert!(verify_checksum(key, "abc=", None).is_err());
+}
+
+/// Answers the requests it receives with the given responses in order, one connection each,
+/// and returns its address together with the number of requests answered so far
+fn serve(responses: Vec<(u16, &'static str)>) -> (SocketAddr, Arc<AtomicUsize>) {
+	let answered = Arc::new(AtomicUsize::new(0));
+	let counter = answered.clone();
+	let mut responses = responses.into_iter();
+	let address = serve_with(responses.len(), move |_| {
+		counter.fetch_add(1, Ordering::SeqCst);
+		let (status, body) = responses.next().unwrap();
+		(status, String::new(), body.to_string())
+	});
+	(address, answered)
+}
+
+/// Answers `count` requests, one connection each, with the status, extra header lines and body
+/// `respond` returns for the request
+fn serve_with<F>(count: usize, mut respond: F) -> SocketAddr
+where
+	F: FnMut(&str) -> (u16, String, String) + Send + 'static,
+{
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let address = listener.local_addr().unwrap();
+	std::thread::spawn(move || {
+		for _ in 0..count {
+			let (mut stream, _) = listener.accept().unwrap();
+			let request = read_request(&mut stream);
+			let (status, headers, body) = respond(&request);
+			let response = format!(
+				"HTTP/1.1 {status} Status\r\nContent-Type: application/xml\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+				body.len()
+			);
+			stream.write_all(response.as_bytes()).unwrap();
+		}
+	});
+	address
+}
+
+/// Reads one request, so the client is done sending before the connection is closed
+fn read_request(stream: &mut impl Read) -> String {
+	let mut request = Vec::new();
+	let mut buffer = [0; 4096];
+	let complete = |request: &[u8]| {
+		let text = String::from_utf8_lossy(request).to_lowercase();
+		let Some(end_of_headers) = text.find("\r\n\r\n") else {
+			return false;
+		};
+		let body = &text[end_of_headers + 4..];
+		if text.contains("transfer-encoding: chunked") {
+			// The last chunk is empty, optionally followed by trailers
+			return body.ends_with("\r\n\r\n")
+				&& (body.starts_with("0\r\n") || body.contains("\r\n0\r\n"));
+		}
+		let length = text[..end_of_headers]
+			.lines()
+			.find_map(|line| line.strip_prefix("content-length:"))
+			.map_or(0, |length| length.trim().parse().unwrap());
+		body.len() >= length
+	};
+	while !complete(&request) {
+		let read = stream.read(&mut buffer).unwrap();
+		assert!(
+			read > 0,
+			"Connection closed before the request was complete"
+		);
+		request.extend_from_slice(&buffer[..read]);
+	}
+	String::from_utf8_lossy(&request).into_owned()
+}
+
+fn client_for(address: SocketAddr) -> Client {
+	let config = aws_sdk_s3::Config::builder()
+		.credentials_provider(Credentials::new("key", "secret", None, None, "test"))
+		.region(Region::new("us-east-1"))
+		.endpoint_url(format!("http://{address}"))
+		.force_path_style(true)
+		.retry_config(RetryConfig::disabled())
+		.build();
+	Client::from_conf(config)
+}
+
+/// Secret variables for the bucket `bucket` on the server the test client talks to
+fn secret_variables() -> SecretVariables {
+	SecretVariables {
+		papi_path: String::new(),
+		oidc_base_url: String::new(),
+		oidc_client_id: String::new(),
+		oidc_client_secret: String::new(),
+		oidc_tekst_base_url: String::new(),
+		oidc_tekst_client_id: String::new(),
+		oidc_tekst_client_secret: String::new(),
+		s3_access_key_id: String::new(),
+		s3_secret_access_key: String::new(),
+		s3_url: String::new(),
+		s3_bucket_name: "bucket".to_string(),
+		s3_region: String::new(),
+	}
+}
+
+#[test]
+fn test_complete_multipart_upload_is_retried_after_server_error() {
+	let (address, answered) = serve(vec![
+		(
+			500,
+			"<Error><Code>InternalError</Code><Message>We encountered an internal error.</Message></Error>",
+		),
+		(
+			200,
+			"<CompleteMultipartUploadResult><Bucket>bucket</Bucket><Key>object/page_00001.tif</Key><ChecksumSHA256>checksum-2</ChecksumSHA256></CompleteMultipartUploadResult>",
+		),
+	]);
+	let client = client_for(address);
+	let parts = vec![
+		CompletedPart::builder()
+			.part_number(1)
+			.e_tag("etag")
+			.checksum_sha256("checksum")
+			.build(),
+	];
+
+	let output = block_on(complete_multipart_upload(
+		&client,
+		"bucket",
+		"object/page_00001.tif",
+		"upload-id",
+		parts,
+		&CancellationFlag::default(),
+	))
+	.unwrap();
+
+	assert_eq!(output.checksum_sha256(), Some("checksum-2"));
+	assert_eq!(answered.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_verification_listing_is_retried_after_server_error() {
+	let (address, answered) = serve(vec![
+		(
+			503,
+			"<Error><Code>SlowDown</Code><Message>Please reduce your request rate.</Message></Error>",
+		),
+		(
+			200,
+			"<ListBucketResult><Name>bucket</Name><Prefix>object/</Prefix><KeyCount>1</KeyCount><IsTruncated>false</IsTruncated><Contents><Key>object/page_00001.tif</Key><Size>1000</Size></Contents></ListBucketResult>",
+		),
+	]);
+	let client = client_for(address);
+
+	let listed = block_on(list_prefix(
+		&client,
+		"bucket",
+		"object/",
+		&CancellationFlag::default(),
+	))
+	.unwrap();
+
+	assert_eq!(
+		listed,
+		HashMap::from([("object/page_00001.tif".to_string(), 1000)])
+	);
+	assert_eq!(answered.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_multipart_upload_is_aborted_when_cancelled_before_completion() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let path = tmp_dir.path().join("page_1.tif");
+	let data = vec![7u8; 1000];
+	fs::write(&path, &data).unwrap();
+	let part_checksum = checksum::to_base64(&checksum::sha256(&data));
+	let key = "object/page_00001.tif";
+
+	// The upload is cancelled while its only part is being sent, so the part succeeds and the
+	// cancel is noticed when the upload is about to be completed
+	let cancellation = CancellationFlag::default();
+	let requests = Arc::new(Mutex::new(Vec::new()));
+	let address = serve_with(3, {
+		let cancellation = cancellation.clone();
+		let requests = requests.clone();
+		move |request| {
+			let request_line = request.lines().next().unwrap().to_string();
+			requests.lock().unwrap().push(request_line.clone());
+			if request_line.starts_with("POST") {
+				let body = format!(
+					"<InitiateMultipartUploadResult><Bucket>bucket</Bucket><Key>{key}</Key><UploadId>upload-id</UploadId></InitiateMultipartUploadResult>"
+				);
+				(200, String::new(), body)
+			} else if request_line.starts_with("PUT") {
+				cancellation.cancel();
+				let headers =
+					format!("ETag: \"etag-1\"\r\nx-amz-checksum-sha256: {part_checksum}\r\n");
+				(200, headers, String::new())
+			} else {
+				(204, String::new(), String::new())
+			}
+		}
+	});
+	let client = client_for(address);
+	let secret_variables = secret_variables();
+	let version = FileVersion::of(&path).unwrap();
+	let journal = Mutex::new(UploadJournal::open(tmp_dir.path(), "object").unwrap());
+	let progress = Arc::new(UploadProgress::new(
+		tmp_dir.path().to_string_lossy().to_string(),
+		1,
+		data.len() as u64,
+		|_| {},
+	));
+
+	let result = block_on(multipart_upload(MultipartUploadRequest {
+		client: &client,
+		secret_variables: &secret_variables,
+		path: &path,
+		key,
+		version,
+		journal: &journal,
+		max_concurrent_parts: 1,
+		cancellation: &cancellation,
+		progress: &progress,
+	}));
+
+	assert!(matches!(result, Err(UploadError::Cancelled)));
+	let requests = requests.lock().unwrap();
+	assert_eq!(requests.len(), 3);
+	assert!(requests[2].starts_with("DELETE"));
+	assert!(requests[2].contains("uploadId=upload-id"));
+	assert!(
+		journal
+			.lock()
+			.unwrap()
+			.multipart_upload(key, version)
+			.is_none()
+	);
+}
+
+#[test]
+fn test_resume_starts_over_when_the_multipart_upload_no_longer_exists() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let key = "object/page_00001.tif";
+	let version = FileVersion {
+		size: 1000,
+		modified: 1,
+	};
+	let mut journal = UploadJournal::open(tmp_dir.path(), "object").unwrap();
+	journal
+		.start_multipart_upload(key, "upload-id", version)
+		.unwrap();
+	let journal = Mutex::new(journal);
+	let (address, _) = serve(vec![(
+		404,
+		"<Error><Code>NoSuchUpload</Code><Message>The specified upload does not exist.</Message></Error>",
+	)]);
+
+	let resumed = block_on(resume_multipart_upload(
+		&client_for(address),
+		&secret_variables(),
+		key,
+		version,
+		&journal,
+		&CancellationFlag::default(),
+	))
+	.unwrap();
+
+	assert!(resumed.is_none());
+	assert!(
+		journal
+			.lock()
+			.unwrap()
+			.multipart_upload(key, version)
+			.is_none()
+	);
+}
+
+#[test]
+fn test_resume_keeps_the_multipart_upload_when_listing_parts_fails() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let key = "object/page_00001.tif";
+	let version = FileVersion {
+		size: 1000,
+		modified: 1,
+	};
+	let mut journal = UploadJournal::open(tmp_dir.path(), "object").unwrap();
+	journal
+		.start_multipart_upload(key, "upload-id", version)
+		.unwrap();
+	let journal = Mutex::new(journal);
+	let (address, _) = serve(vec![(
+		403,
+		"<Error><Code>AccessDenied</Code><Message>Access Denied</Message></Error>",
+	)]);
+	let client = client_for(address);
+	let cancelled = CancellationFlag::default();
+	cancelled.cancel();
+
+	let cancel_result = block_on(resume_multipart_upload(
+		&client,
+		&secret_variables(),
+		key,
+		version,
+		&journal,
+		&cancelled,
+	));
+	let denied_result = block_on(resume_multipart_upload(
+		&client,
+		&secret_variables(),
+		key,
+		version,
+		&journal,
+		&CancellationFlag::default(),
+	));
+
+	assert!(matches!(cancel_result, Err(UploadError::Cancelled)));
+	assert!(matches!(denied_result, Err(UploadError::Failed(_))));
+	assert!(
+		journal
+			.lock()
+			.unwrap()
+			.multipart_upload(key, version)
+			.is_some()
+	);
+}
+
+#[test]
+fn test_discard_journals_keeps_journals_whose_upload_could_not_be_aborted() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let version = FileVersion {
+		size: 1000,
+		modified: 1,
+	};
+	for (object_id, upload_id) in [("aborted", "upload-a"), ("failing", "upload-b")] {
+		UploadJournal::open(tmp_dir.path(), object_id)
+			.unwrap()
+			.start_multipart_upload("object/page_00001.tif", upload_id, version)
+			.unwrap();
+	}
+	let address = serve_with(2, |request| {
+		if request
+			.lines()
+			.next()
+			.unwrap()
+			.contains("uploadId=upload-a")
+		{
+			(204, String::new(), String::new())
+		} else {
+			let body = "<Error><Code>AccessDenied</Code><Message>Access Denied</Message></Error>";
+			(403, String::new(), body.to_string())
+		}
+	});
+
+	let journals = UploadJournal::open_all(tmp_dir.path()).unwrap();
+	let discarded = block_on(discard_journals(&client_for(address), "bucket", journals)).unwrap();
+
+	assert_eq!(discarded, 1);
+	assert!(!tmp_dir.path().join("aborted.json").exists());
+	assert!(tmp_dir.path().join("failing.json").exists());
+}
+
+#[test]
+fn test_counted_body_is_uploaded_and_reported_as_sent() {
+	let (address, answered) = serve(vec![(200, "")]);
+	let client = client_for(address);
+	let bytes_sent = Arc::new(AtomicUsize::new(0));
+	let reported = bytes_sent.clone();
+	let progress = Arc::new(UploadProgress::new(
+		"/scans/tekst_0a1b2c".to_string(),
+		1,
+		300_000,
+		move |event| reported.store(event.bytes_sent as usize, Ordering::SeqCst),
+	));
+	let data = Bytes::from(vec![7u8; 300_000]);
+
+	let attempt = progress.start_attempt(Path::new("/scans/tekst_0a1b2c/page_1.tif"), None);
+	block_on(
+		client
+			.put_object()
+			.bucket("bucket")
+			.key("object/page_00001.tif")
+			.content_length(data.len() as i64)
+			.checksum_sha256(checksum::to_base64(&checksum::sha256(&data)))
+			.body(attempt.body(data))
+			.send(),
+	)
+	.unwrap();
+	attempt.confirm();
+	progress.page_done();
+
+	assert_eq!(answered.load(Ordering::SeqCst), 1);
+	assert_eq!(bytes_sent.load(Ordering::SeqCst), 300_000);
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use ::tempfile::TempDir;
+
+use crate::model::ScanFolderChange;
+use crate::scan_watcher::{ScanFolderEventKind, ScanWatcher, is_ignored};
+use crate::tests::test_utils::{TEST_IMAGE_PNG, get_test_resource_dir};
+
+const EVENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn test_is_ignored_skips_the_apps_own_files() {
+	assert!(is_ignored(Path::new("/scans/book/.thumbnails/page_1.webp")));
+	assert!(is_ignored(Path::new("/scans/book/.previews/page_1.webp")));
+	assert!(is_ignored(Path::new(
+		"/scans/book/.orientation/page_1.tif.json"
+	)));
+	assert!(is_ignored(Path::new("/scans/book/.page_1.tif.1-0.tmp")));
+	assert!(!is_ignored(Path::new("/scans/book/page_1.tif")));
+}
+
+#[test]
+fn test_scan_watcher_reports_changes_and_makes_thumbnails() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let root = tmp_dir.path().to_path_buf();
+	let (sender, receiver) = mpsc::channel::<(ScanFolderEventKind, ScanFolderChange)>();
+	let sender = std::sync::Mutex::new(sender);
+	let watcher = ScanWatcher::start(root.clone(), move |kind, change| {
+		let _ = sender.lock().unwrap().send((kind, change));
+	})
+	.unwrap();
+
+	let folder = root.join("book");
+	fs::create_dir(&folder).unwrap();
+	let (kind, change) = receiver.recv_timeout(EVENT_TIMEOUT).unwrap();
+	assert_eq!(kind, ScanFolderEventKind::FolderAdded);
+	assert_eq!(change.path, folder);
+	assert_eq!(change.root, root);
+
+	let image = folder.join("page_1.png");
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), &image).unwrap();
+	let (kind, change) = receiver.recv_timeout(EVENT_TIMEOUT).unwrap();
+	assert_eq!(kind, ScanFolderEventKind::FileAdded);
+	assert_eq!(change.path, image);
+	assert!(change.thumbnail.unwrap().is_file());
+
+	fs::remove_file(&image).unwrap();
+	let (kind, change) = receiver.recv_timeout(EVENT_TIMEOUT).unwrap();
+	assert_eq!(kind, ScanFolderEventKind::FileRemoved);
+	assert_eq!(change.path, image);
+
+	watcher.stop();
+	assert!(receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_scan_watcher_reports_rewritten_image_as_changed() {
+	let tmp_dir = TempDir::with_prefix("trokk-test-tmp-").expect("Failed to create temp dir");
+	let root = tmp_dir.path().to_path_buf();
+	let image = root.join("page_1.png");
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), &image).unwrap();
+	let (sender, receiver) = mpsc::channel::<(ScanFolderEventKind, ScanFolderChange)>();
+	let sender = std::sync::Mutex::new(sender);
+	let watcher = ScanWatcher::start(root.clone(), move |kind, change| {
+		let _ = sender.lock().unwrap().send((kind, change));
+	})
+	.unwrap();
+
+	// Like rotating the image, which writes its EXIF orientation
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), &image).unwrap();
+	let (kind, change) = receiver.recv_timeout(EVENT_TIMEOUT).unwrap();
+	assert_eq!(kind, ScanFolderEventKind::FileChanged);
+	assert_eq!(change.path, image);
+	assert!(change.thumbnail.unwrap().is_file());
+
+	let added = root.join("page_2.png");
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), &added).unwrap();
+	// The rewrite may be reported more than once, if it was seen in more than one burst
+	let (kind, _) = std::iter::from_fn(|| receiver.recv_timeout(EVENT_TIMEOUT).ok())
+		.find(|(_, change)| change.path == added)
+		.unwrap();
+	assert_eq!(kind, ScanFolderEventKind::FileAdded);
+
+	watcher.stop();
+}
@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 #[cfg(test)]
 mod tests {
@@ -39,3 +41,20 @@ pub fn get_test_resource_dir() -> PathBuf {
 	path.push("src/tests/resources");
 	path
 }
+
+/// Copies the test image to `destination`, dated as if the scanner finished writing it an hour
+/// ago, so conversions don't wait for it to be finished
+pub fn copy_finished_test_image(destination: &Path) {
+	fs::copy(get_test_resource_dir().join(TEST_IMAGE_PNG), destination)
+		.expect("Failed to copy test image");
+	mark_finished(destination);
+}
+
+/// Dates the file as if the scanner finished writing it an hour ago
+pub fn mark_finished(path: &Path) {
+	File::options()
+		.write(true)
+		.open(path)
+		.and_then(|file| file.set_modified(SystemTime::now() - Duration::from_secs(3600)))
+		.expect("Failed to date test image");
+}
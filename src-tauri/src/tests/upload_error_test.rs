@@ -0,0 +1,44 @@
+use crate::error::UploadError;
+use crate::model::{BatchProblem, BatchProblemKind, BatchValidationReport};
+
+#[test]
+fn test_upload_error_cancelled_should_serialize_with_cancelled_kind() {
+	let actual = serde_json::to_value(UploadError::Cancelled).unwrap();
+	let expected = serde_json::json!({
+		"kind": "cancelled",
+		"message": "Upload was cancelled"
+	});
+	assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_upload_error_failed_should_serialize_with_failed_kind_and_message() {
+	let actual = serde_json::to_value(UploadError::from("init multipart failed")).unwrap();
+	let expected = serde_json::json!({
+		"kind": "failed",
+		"message": "init multipart failed"
+	});
+	assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_upload_error_invalid_should_serialize_with_report() {
+	let report = BatchValidationReport {
+		valid: false,
+		file_count: 1,
+		total_bytes: 0,
+		problems: vec![BatchProblem {
+			kind: BatchProblemKind::MissingFile,
+			batch_id: Some("0a1b2c".to_string()),
+			path: Some("/scans/page_1.tif".to_string()),
+			message: "/scans/page_1.tif does not exist".to_string(),
+		}],
+	};
+
+	let actual = serde_json::to_value(UploadError::Invalid(report)).unwrap();
+
+	assert_eq!(actual["kind"], "invalid");
+	assert_eq!(actual["message"], "/scans/page_1.tif does not exist");
+	assert_eq!(actual["report"]["problems"][0]["kind"], "missingFile");
+	assert_eq!(actual["report"]["fileCount"], 1);
+}
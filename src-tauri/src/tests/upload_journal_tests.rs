@@ -0,0 +1,146 @@
+use ::tempfile::TempDir;
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::upload_journal::{FileVersion, JournalPart, UploadJournal};
+
+const OBJECT_ID: &str = "tekst_0a1b2c";
+const KEY: &str = "tekst_0a1b2c/representations/primary/data/tekst_0a1b2c_00001.tif";
+const SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+fn setup_journal_dir() -> TempDir {
+	TempDir::with_prefix("trokk-test-journal-").expect("Failed to create temp dir")
+}
+
+fn version(size: u64) -> FileVersion {
+	FileVersion {
+		size,
+		modified: 1_760_000_000_000_000_000,
+	}
+}
+
+#[test]
+fn test_upload_journal_remembers_completed_objects_after_reopening() {
+	let journal_dir = setup_journal_dir();
+
+	let mut journal = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	journal.mark_completed(KEY, version(1024), SHA256).unwrap();
+
+	let reopened = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	assert_eq!(reopened.completed_sha256(KEY, version(1024)), Some(SHA256));
+	assert_eq!(reopened.completed_sha256(KEY, version(2048)), None);
+}
+
+#[test]
+fn test_upload_journal_keeps_multipart_parts_until_object_is_completed() {
+	let journal_dir = setup_journal_dir();
+
+	let mut journal = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	journal
+		.start_multipart_upload(KEY, "upload-1", version(4096))
+		.unwrap();
+	journal
+		.record_part(
+			KEY,
+			JournalPart {
+				part_number: 1,
+				e_tag: "\"etag-1\"".to_string(),
+				checksum_sha256: "cGFydC0x".to_string(),
+			},
+		)
+		.unwrap();
+
+	let mut reopened = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	let upload = reopened.multipart_upload(KEY, version(4096)).unwrap();
+	assert_eq!(upload.upload_id, "upload-1");
+	assert_eq!(upload.parts.len(), 1);
+	assert!(reopened.multipart_upload(KEY, version(1)).is_none());
+
+	reopened.mark_completed(KEY, version(4096), SHA256).unwrap();
+	assert!(reopened.multipart_upload(KEY, version(4096)).is_none());
+}
+
+#[test]
+fn test_upload_journal_remove_deletes_journal_file() {
+	let journal_dir = setup_journal_dir();
+
+	let mut journal = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	journal.mark_completed(KEY, version(1024), SHA256).unwrap();
+	journal.remove().unwrap();
+
+	let reopened = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	assert_eq!(reopened.completed_sha256(KEY, version(1024)), None);
+}
+
+#[test]
+fn test_upload_journal_keeps_the_date_of_the_first_attempt() {
+	let journal_dir = setup_journal_dir();
+
+	let mut journal = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	assert_eq!(journal.upload_date("2026-10-18").unwrap(), "2026-10-18");
+
+	let mut reopened = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	assert_eq!(reopened.upload_date("2026-10-19").unwrap(), "2026-10-18");
+}
+
+#[test]
+fn test_upload_journal_does_not_skip_a_file_rewritten_with_the_same_size() {
+	let journal_dir = setup_journal_dir();
+	let file_path = journal_dir.path().join("page.tif");
+	fs::write(&file_path, [1u8; 1024]).unwrap();
+	let uploaded = FileVersion::of(&file_path).unwrap();
+
+	let mut journal = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	journal.mark_completed(KEY, uploaded, SHA256).unwrap();
+	journal
+		.start_multipart_upload("other-key", "upload-1", uploaded)
+		.unwrap();
+
+	// Rescanned page, same size but different content and a later modification time
+	fs::write(&file_path, [2u8; 1024]).unwrap();
+	File::options()
+		.write(true)
+		.open(&file_path)
+		.unwrap()
+		.set_modified(UNIX_EPOCH + Duration::from_nanos(uploaded.modified) + Duration::from_secs(2))
+		.unwrap();
+	let rewritten = FileVersion::of(&file_path).unwrap();
+	assert_eq!(rewritten.size, uploaded.size);
+
+	let reopened = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	assert_eq!(reopened.completed_sha256(KEY, uploaded), Some(SHA256));
+	assert_eq!(reopened.completed_sha256(KEY, rewritten), None);
+	assert!(reopened.multipart_upload("other-key", rewritten).is_none());
+}
+
+#[test]
+fn test_upload_journal_open_all_lists_unfinished_uploads_and_directories() {
+	let journal_dir = setup_journal_dir();
+
+	let mut journal = UploadJournal::open(journal_dir.path(), OBJECT_ID).unwrap();
+	journal
+		.set_directory(Path::new("/scans/tekst_0a1b2c"))
+		.unwrap();
+	journal
+		.start_multipart_upload(KEY, "upload-1", version(4096))
+		.unwrap();
+	fs::write(journal_dir.path().join("broken.json"), b"{not json").unwrap();
+
+	let mut journals = UploadJournal::open_all(journal_dir.path()).unwrap();
+	journals.sort_by_key(|journal| journal.directory().is_some());
+
+	assert_eq!(journals.len(), 2);
+	// The unreadable journal is returned empty, so it can still be removed
+	assert!(journals[0].unfinished_uploads().is_empty());
+	journals[0].remove().unwrap();
+	assert!(!journal_dir.path().join("broken.json").exists());
+	assert_eq!(
+		journals[1].directory(),
+		Some(Path::new("/scans/tekst_0a1b2c"))
+	);
+	assert_eq!(
+		journals[1].unfinished_uploads(),
+		vec![(KEY.to_string(), "upload-1".to_string())]
+	);
+}
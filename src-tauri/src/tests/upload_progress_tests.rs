@@ -0,0 +1,100 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+
+use crate::model::TransferProgress;
+use crate::upload_progress::UploadProgress;
+
+fn setup_progress(
+	total_pages: usize,
+	total_bytes: u64,
+) -> (UploadProgress, Arc<Mutex<Vec<TransferProgress>>>) {
+	let events = Arc::new(Mutex::new(Vec::new()));
+	let recorded = events.clone();
+	let progress = UploadProgress::new(
+		"/scans/tekst_0a1b2c".to_string(),
+		total_pages,
+		total_bytes,
+		move |event| recorded.lock().unwrap().push(event),
+	);
+	(progress, events)
+}
+
+#[test]
+fn test_upload_progress_throttles_events_but_always_emits_last_page() {
+	let (progress, events) = setup_progress(3, 300);
+
+	for page in 1..=3 {
+		let path = format!("/scans/tekst_0a1b2c/page_{page}.tif");
+		progress.add_bytes(Path::new(&path), None, 100, true);
+		progress.page_done();
+	}
+
+	let events = events.lock().unwrap();
+	assert_eq!(events.len(), 2);
+	let last = events.last().unwrap();
+	assert_eq!(last.page_nr, 3);
+	assert_eq!(last.total_pages, 3);
+	assert_eq!(last.bytes_sent, 300);
+	assert_eq!(last.total_bytes, 300);
+	assert_eq!(last.current_file, "page_3.tif");
+}
+
+#[test]
+fn test_upload_progress_reports_current_part() {
+	let (progress, events) = setup_progress(1, 40);
+
+	progress.add_bytes(Path::new("/scans/map.tif"), Some(2), 16, false);
+
+	let events = events.lock().unwrap();
+	assert_eq!(events.len(), 1);
+	assert_eq!(events[0].current_part, Some(2));
+	assert_eq!(events[0].bytes_sent, 16);
+	assert_eq!(events[0].bytes_per_second, 0);
+}
+
+fn collect(body: aws_sdk_s3::primitives::ByteStream) -> Bytes {
+	tokio::runtime::Builder::new_current_thread()
+		.build()
+		.unwrap()
+		.block_on(body.collect())
+		.unwrap()
+		.into_bytes()
+}
+
+#[test]
+fn test_upload_progress_counts_body_bytes_as_they_are_sent() {
+	let (progress, events) = setup_progress(1, 200_000);
+	let progress = Arc::new(progress);
+	let data = Bytes::from(vec![7u8; 200_000]);
+
+	let attempt = progress.start_attempt(Path::new("/scans/map.tif"), Some(1));
+	assert_eq!(collect(attempt.body(data.clone())), data);
+	attempt.confirm();
+	progress.page_done();
+
+	let events = events.lock().unwrap();
+	// Counted while the body is read, not once the whole part is done
+	assert!(events[0].bytes_sent > 0 && events[0].bytes_sent < 200_000);
+	assert_eq!(events[0].current_part, Some(1));
+	assert_eq!(events.last().unwrap().bytes_sent, 200_000);
+}
+
+#[test]
+fn test_upload_progress_takes_back_bytes_of_a_failed_attempt() {
+	let (progress, events) = setup_progress(1, 200_000);
+	let progress = Arc::new(progress);
+	let data = Bytes::from(vec![7u8; 200_000]);
+
+	let failed = progress.start_attempt(Path::new("/scans/map.tif"), None);
+	collect(failed.body(data.clone()));
+	drop(failed);
+	let retried = progress.start_attempt(Path::new("/scans/map.tif"), None);
+	collect(retried.body(data.clone()));
+	retried.confirm();
+	progress.page_done();
+
+	let events = events.lock().unwrap();
+	assert_eq!(events.last().unwrap().bytes_sent, 200_000);
+}
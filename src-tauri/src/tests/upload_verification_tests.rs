@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::upload_verification::compare_keys;
+
+const PREFIX: &str = "tekst_0a1b2c/representations/primary/data";
+
+fn key(page: usize) -> String {
+	format!("{PREFIX}/tekst_0a1b2c_{page:0>5}.tif")
+}
+
+#[test]
+fn test_compare_keys_reports_missing_extra_and_present_objects() {
+	let expected = vec![
+		(key(1), PathBuf::from("/scans/page_1.tif")),
+		(key(2), PathBuf::from("/scans/page_2.tif")),
+	];
+	let listed = HashMap::from([(key(1), 1024), (key(3), 2048)]);
+
+	let comparison = compare_keys(expected, listed);
+
+	assert_eq!(comparison.present.len(), 1);
+	assert_eq!(comparison.present[0].key, key(1));
+	assert_eq!(comparison.present[0].remote_size, 1024);
+
+	assert_eq!(comparison.missing.len(), 1);
+	assert_eq!(comparison.missing[0].key, key(2));
+	assert_eq!(
+		comparison.missing[0].path.as_deref(),
+		Some("/scans/page_2.tif")
+	);
+
+	assert_eq!(comparison.extra.len(), 1);
+	assert_eq!(comparison.extra[0].key, key(3));
+	assert_eq!(comparison.extra[0].path, None);
+}
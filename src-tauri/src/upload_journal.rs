@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const JOURNAL_EXTENSION: &str = "json";
+
+/// On-disk record of the objects and multipart parts S3 has confirmed for one object id.
+/// Every change is written straight to disk, so a retried upload (also after an app restart)
+/// can skip objects that are already in the bucket and resume unfinished multipart uploads.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UploadJournal {
+	#[serde(skip)]
+	path: PathBuf,
+	/// Date the upload was first started, used for `{date}` in key templates
+	#[serde(default)]
+	upload_date: Option<String>,
+	/// Folder the uploaded files are in, so the journal can be discarded when it is deleted
+	#[serde(default)]
+	directory: Option<PathBuf>,
+	completed_objects: HashMap<String, CompletedObject>,
+	multipart_uploads: HashMap<String, MultipartProgress>,
+}
+
+/// Size and modification time of the local file an object was uploaded from. A file that has
+/// been rewritten since is uploaded again, even if its size is unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileVersion {
+	pub(crate) size: u64,
+	/// Nanoseconds since the Unix epoch. Missing in journals written before it was recorded,
+	/// which never matches a file on disk.
+	#[serde(default)]
+	pub(crate) modified: u64,
+}
+
+impl FileVersion {
+	pub(crate) fn of(path: &Path) -> io::Result<Self> {
+		let metadata = fs::metadata(path)?;
+		let modified = metadata
+			.modified()?
+			.duration_since(UNIX_EPOCH)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		Ok(Self {
+			size: metadata.len(),
+			modified: modified.as_nanos() as u64,
+		})
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletedObject {
+	#[serde(flatten)]
+	version: FileVersion,
+	sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MultipartProgress {
+	pub(crate) upload_id: String,
+	#[serde(flatten)]
+	pub(crate) version: FileVersion,
+	pub(crate) parts: Vec<JournalPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JournalPart {
+	pub(crate) part_number: i32,
+	pub(crate) e_tag: String,
+	/// Base64, as sent to and reported by S3
+	pub(crate) checksum_sha256: String,
+}
+
+impl UploadJournal {
+	/// Opens the journal for `object_id` in `journal_directory`, or starts an empty one if none exists
+	pub(crate) fn open(journal_directory: &Path, object_id: &str) -> io::Result<Self> {
+		let mut path = journal_directory.join(sanitize_file_name(object_id));
+		path.set_extension(JOURNAL_EXTENSION);
+
+		let mut journal = match fs::read(&path) {
+			Ok(bytes) => serde_json::from_slice::<UploadJournal>(&bytes)
+				.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => UploadJournal::default(),
+			Err(e) => return Err(e),
+		};
+		journal.path = path;
+		Ok(journal)
+	}
+
+	/// Opens every journal in `journal_directory`. A journal that cannot be parsed is returned
+	/// empty, so it can still be removed.
+	pub(crate) fn open_all(journal_directory: &Path) -> io::Result<Vec<Self>> {
+		let entries = match fs::read_dir(journal_directory) {
+			Ok(entries) => entries,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(e),
+		};
+
+		let mut journals = Vec::new();
+		for entry in entries {
+			let path = entry?.path();
+			if path.extension().and_then(|e| e.to_str()) != Some(JOURNAL_EXTENSION) {
+				continue;
+			}
+			let parsed = fs::read(&path).and_then(|bytes| {
+				serde_json::from_slice::<UploadJournal>(&bytes)
+					.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+			});
+			let mut journal = match parsed {
+				Ok(journal) => journal,
+				Err(e) if e.kind() == io::ErrorKind::InvalidData => UploadJournal::default(),
+				Err(e) => return Err(e),
+			};
+			journal.path = path;
+			journals.push(journal);
+		}
+		Ok(journals)
+	}
+
+	/// When the journal was last written to disk
+	pub(crate) fn last_written(&self) -> io::Result<SystemTime> {
+		fs::metadata(&self.path)?.modified()
+	}
+
+	pub(crate) fn directory(&self) -> Option<&Path> {
+		self.directory.as_deref()
+	}
+
+	/// Records the folder the uploaded files are in
+	pub(crate) fn set_directory(&mut self, directory: &Path) -> io::Result<()> {
+		if self.directory.as_deref() == Some(directory) {
+			return Ok(());
+		}
+		self.directory = Some(directory.to_path_buf());
+		self.persist()
+	}
+
+	/// Returns the date the upload was first started, recording `today` if this is the first attempt
+	pub(crate) fn upload_date(&mut self, today: &str) -> io::Result<String> {
+		if let Some(upload_date) = &self.upload_date {
+			return Ok(upload_date.clone());
+		}
+		self.upload_date = Some(today.to_string());
+		self.persist()?;
+		Ok(today.to_string())
+	}
+
+	/// Returns the hex SHA-256 of `key` if it has been uploaded from the same version of the file
+	pub(crate) fn completed_sha256(&self, key: &str, version: FileVersion) -> Option<&str> {
+		self.completed_objects
+			.get(key)
+			.filter(|object| object.version == version)
+			.map(|object| object.sha256.as_str())
+	}
+
+	pub(crate) fn mark_completed(
+		&mut self,
+		key: &str,
+		version: FileVersion,
+		sha256: &str,
+	) -> io::Result<()> {
+		self.multipart_uploads.remove(key);
+		self.completed_objects.insert(
+			key.to_string(),
+			CompletedObject {
+				version,
+				sha256: sha256.to_string(),
+			},
+		);
+		self.persist()
+	}
+
+	/// Returns the unfinished multipart upload of `key`, if it was started from the same version
+	/// of the file
+	pub(crate) fn multipart_upload(
+		&self,
+		key: &str,
+		version: FileVersion,
+	) -> Option<&MultipartProgress> {
+		self.multipart_uploads
+			.get(key)
+			.filter(|upload| upload.version == version)
+	}
+
+	/// Key and upload id of every unfinished multipart upload
+	pub(crate) fn unfinished_uploads(&self) -> Vec<(String, String)> {
+		self.multipart_uploads
+			.iter()
+			.map(|(key, upload)| (key.clone(), upload.upload_id.clone()))
+			.collect()
+	}
+
+	pub(crate) fn start_multipart_upload(
+		&mut self,
+		key: &str,
+		upload_id: &str,
+		version: FileVersion,
+	) -> io::Result<()> {
+		self.multipart_uploads.insert(
+			key.to_string(),
+			MultipartProgress {
+				upload_id: upload_id.to_string(),
+				version,
+				parts: Vec::new(),
+			},
+		);
+		self.persist()
+	}
+
+	pub(crate) fn record_part(&mut self, key: &str, part: JournalPart) -> io::Result<()> {
+		if let Some(upload) = self.multipart_uploads.get_mut(key) {
+			upload
+				.parts
+				.retain(|existing| existing.part_number != part.part_number);
+			upload.parts.push(part);
+		}
+		self.persist()
+	}
+
+	pub(crate) fn discard_multipart_upload(&mut self, key: &str) -> io::Result<()> {
+		self.multipart_uploads.remove(key);
+		self.persist()
+	}
+
+	/// Deletes the journal from disk, used once every object of the upload is confirmed
+	pub(crate) fn remove(&self) -> io::Result<()> {
+		match fs::remove_file(&self.path) {
+			Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+			_ => Ok(()),
+		}
+	}
+
+	fn persist(&self) -> io::Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let bytes = serde_json::to_vec_pretty(self)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+		// Write to a temporary file first so a crash never leaves a half-written journal behind
+		let temporary_path = self.path.with_extension("tmp");
+		fs::write(&temporary_path, bytes)?;
+		fs::rename(&temporary_path, &self.path)
+	}
+}
+
+fn sanitize_file_name(object_id: &str) -> String {
+	object_id
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+				c
+			} else {
+				'_'
+			}
+		})
+		.collect()
+}
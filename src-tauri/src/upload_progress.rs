@@ -0,0 +1,209 @@
+use crate::model::TransferProgress;
+use aws_sdk_s3::primitives::ByteStream;
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Minimum time between two `TransferProgress` events, so the frontend is not flooded
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Request bodies are handed to the connection in chunks of this size, and counted per chunk
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Collects progress from the objects and parts of one upload job as they are sent to S3,
+/// and emits it as `TransferProgress` at most every `PROGRESS_INTERVAL`. The event for the last
+/// page is always emitted.
+pub(crate) struct UploadProgress {
+	directory: String,
+	total_pages: usize,
+	total_bytes: u64,
+	started: Instant,
+	emit: Box<dyn Fn(TransferProgress) + Send + Sync>,
+	state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+	pages_done: usize,
+	bytes_sent: u64,
+	/// Bytes sent in this attempt, excluding those confirmed by an earlier attempt
+	bytes_transferred: u64,
+	current_file: String,
+	current_part: Option<i32>,
+	last_emitted: Option<Instant>,
+}
+
+impl UploadProgress {
+	pub(crate) fn new(
+		directory: String,
+		total_pages: usize,
+		total_bytes: u64,
+		emit: impl Fn(TransferProgress) + Send + Sync + 'static,
+	) -> Self {
+		Self {
+			directory,
+			total_pages,
+			total_bytes,
+			started: Instant::now(),
+			emit: Box::new(emit),
+			state: Mutex::new(ProgressState::default()),
+		}
+	}
+
+	/// Records `bytes` of `path` (or of one of its multipart parts) as uploaded.
+	/// `transferred` is false for bytes an earlier attempt already uploaded, so they count
+	/// towards the progress but not towards the transfer rate.
+	pub(crate) fn add_bytes(&self, path: &Path, part: Option<i32>, bytes: u64, transferred: bool) {
+		self.update(|state| {
+			state.bytes_sent += bytes;
+			if transferred {
+				state.bytes_transferred += bytes;
+			}
+			state.current_file = path
+				.file_name()
+				.map(|name| name.to_string_lossy().to_string())
+				.unwrap_or_default();
+			state.current_part = part;
+		});
+	}
+
+	/// Starts counting the bytes of one attempt at uploading `path`, or one of its parts
+	pub(crate) fn start_attempt(
+		self: &Arc<Self>,
+		path: &Path,
+		part: Option<i32>,
+	) -> AttemptProgress {
+		AttemptProgress {
+			progress: self.clone(),
+			path: path.to_path_buf(),
+			part,
+			sent: Arc::default(),
+			confirmed: false,
+		}
+	}
+
+	/// Takes back bytes counted for an attempt that failed
+	fn remove_bytes(&self, bytes: u64) {
+		self.update(|state| {
+			state.bytes_sent = state.bytes_sent.saturating_sub(bytes);
+			state.bytes_transferred = state.bytes_transferred.saturating_sub(bytes);
+		});
+	}
+
+	pub(crate) fn page_done(&self) {
+		self.update(|state| state.pages_done += 1);
+	}
+
+	fn update(&self, change: impl FnOnce(&mut ProgressState)) {
+		let Ok(mut state) = self.state.lock() else {
+			return;
+		};
+		change(&mut state);
+
+		let now = Instant::now();
+		let is_last_page = state.pages_done == self.total_pages;
+		let is_due = state
+			.last_emitted
+			.is_none_or(|last| now.duration_since(last) >= PROGRESS_INTERVAL);
+		if !is_last_page && !is_due {
+			return;
+		}
+		state.last_emitted = Some(now);
+
+		let elapsed = now.duration_since(self.started).as_secs_f64();
+		let bytes_per_second = if elapsed > 0.0 {
+			(state.bytes_transferred as f64 / elapsed) as u64
+		} else {
+			0
+		};
+
+		// Emitted while holding the lock, so events arrive in the order they were recorded
+		(self.emit)(TransferProgress {
+			directory: self.directory.clone(),
+			page_nr: state.pages_done,
+			total_pages: self.total_pages,
+			bytes_sent: state.bytes_sent,
+			total_bytes: self.total_bytes,
+			current_file: state.current_file.clone(),
+			current_part: state.current_part,
+			bytes_per_second,
+		});
+	}
+}
+
+/// Bytes of one attempt at uploading a file or part, counted as they are read from the request
+/// body. Unless the attempt is confirmed, its bytes are taken back when it is dropped, so a
+/// retried file or part is not counted twice.
+pub(crate) struct AttemptProgress {
+	progress: Arc<UploadProgress>,
+	path: PathBuf,
+	part: Option<i32>,
+	sent: Arc<AtomicU64>,
+	confirmed: bool,
+}
+
+impl AttemptProgress {
+	/// Request body with `bytes`, adding them to the progress as they are sent
+	pub(crate) fn body(&self, bytes: Bytes) -> ByteStream {
+		let progress = self.progress.clone();
+		let path = self.path.clone();
+		let part = self.part;
+		let sent = self.sent.clone();
+		ByteStream::from_body_1_x(CountingBody {
+			remaining: bytes,
+			on_sent: Box::new(move |bytes| {
+				sent.fetch_add(bytes, Ordering::SeqCst);
+				progress.add_bytes(&path, part, bytes, true);
+			}),
+		})
+	}
+
+	/// Keeps the bytes sent, once S3 has confirmed the upload
+	pub(crate) fn confirm(mut self) {
+		self.confirmed = true;
+	}
+}
+
+impl Drop for AttemptProgress {
+	fn drop(&mut self) {
+		if !self.confirmed {
+			self.progress.remove_bytes(self.sent.load(Ordering::SeqCst));
+		}
+	}
+}
+
+struct CountingBody {
+	remaining: Bytes,
+	on_sent: Box<dyn Fn(u64) + Send + Sync>,
+}
+
+impl Body for CountingBody {
+	type Data = Bytes;
+	type Error = Infallible;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		_cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+		if self.remaining.is_empty() {
+			return Poll::Ready(None);
+		}
+		let size = self.remaining.len().min(BODY_CHUNK_SIZE);
+		let chunk = self.remaining.split_to(size);
+		(self.on_sent)(size as u64);
+		Poll::Ready(Some(Ok(Frame::data(chunk))))
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.remaining.is_empty()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		SizeHint::with_exact(self.remaining.len() as u64)
+	}
+}
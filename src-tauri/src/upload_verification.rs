@@ -0,0 +1,254 @@
+use crate::checksum;
+use crate::get_secret_variables;
+use crate::key_layout::get_key_layout;
+use crate::model::{BatchRepresentation, ObjectDiscrepancy, UploadVerificationReport};
+use crate::retry::{AttemptError, get_retry_policy, with_retry};
+use crate::s3::{
+	CancellationFlag, MULTIPART_PART_SIZE, batch_object_keys, get_client, get_upload_concurrency,
+	run_bounded,
+};
+use aws_sdk_s3::Client;
+use aws_sdk_s3::types::ChecksumMode;
+use sentry::{Breadcrumb, Level, add_breadcrumb};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A page that is in the bucket, with the size S3 listed for it
+pub(crate) struct ListedPage {
+	pub(crate) key: String,
+	pub(crate) path: PathBuf,
+	pub(crate) remote_size: u64,
+}
+
+/// Lists every batch prefix in the bucket and compares keys, sizes and checksums with the local
+/// files, without changing anything in the bucket. `upload_dates` are the dates of the upload by
+/// object id, as returned in its `UploadSummary`.
+pub(crate) async fn verify_batch_upload(
+	batch_map: HashMap<String, BatchRepresentation>,
+	material_type: Option<String>,
+	upload_dates: HashMap<String, String>,
+) -> Result<UploadVerificationReport, String> {
+	let secret_variables = get_secret_variables()
+		.await
+		.map_err(|e| format!("Failed to get secret variables: {e}"))?;
+	let client = get_client(secret_variables)
+		.await
+		.map_err(|e| format!("Failed to get S3 client: {e}"))?;
+	let bucket = &secret_variables.s3_bucket_name;
+
+	let key_layout = get_key_layout();
+	let material_type = material_type.as_deref();
+	// A verification is never cancelled, but requests are retried like those of an upload
+	let cancellation = CancellationFlag::default();
+
+	let mut missing = Vec::new();
+	let mut extra = Vec::new();
+	let mut present = Vec::new();
+	for (batch_id, batch) in batch_map.iter() {
+		let object_id = key_layout.object_id(batch_id, material_type)?;
+		let upload_date = upload_dates
+			.get(&object_id)
+			.ok_or_else(|| format!("No upload date given for {object_id}"))?;
+		let expected =
+			batch_object_keys(&key_layout, &object_id, batch, material_type, upload_date)?
+				.into_iter()
+				.map(|page| (page.key, page.path))
+				.collect();
+		let prefix = key_layout.batch_prefix(&object_id, material_type, upload_date)?;
+		let mut listed = list_prefix(client, bucket, &prefix, &cancellation).await?;
+		// The manifest is written by the upload, so it is not an extra object
+		listed.remove(&key_layout.manifest_key(&object_id, material_type, upload_date)?);
+
+		let comparison = compare_keys(expected, listed);
+		missing.extend(comparison.missing);
+		extra.extend(comparison.extra);
+		present.extend(comparison.present);
+	}
+
+	let mut mismatched = Vec::new();
+	let mut verified_count = 0;
+	run_bounded(
+		present,
+		get_upload_concurrency().max_concurrent_objects,
+		|page| verify_page(client, bucket.clone(), page, cancellation.clone()),
+		|discrepancy| {
+			match discrepancy {
+				Some(discrepancy) => mismatched.push(discrepancy),
+				None => verified_count += 1,
+			}
+			Ok(())
+		},
+	)
+	.await?;
+
+	for list in [&mut missing, &mut extra, &mut mismatched] {
+		list.sort_by(|a, b| a.key.cmp(&b.key));
+	}
+	let complete = missing.is_empty() && extra.is_empty() && mismatched.is_empty();
+	add_breadcrumb(Breadcrumb {
+		category: Some("s3".into()),
+		message: Some(format!(
+			"Verified upload: {verified_count} ok, {} missing, {} extra, {} mismatched",
+			missing.len(),
+			extra.len(),
+			mismatched.len()
+		)),
+		level: if complete {
+			Level::Info
+		} else {
+			Level::Warning
+		},
+		..Default::default()
+	});
+
+	Ok(UploadVerificationReport {
+		complete,
+		verified_count,
+		missing,
+		extra,
+		mismatched,
+	})
+}
+
+pub(crate) struct KeyComparison {
+	pub(crate) missing: Vec<ObjectDiscrepancy>,
+	pub(crate) extra: Vec<ObjectDiscrepancy>,
+	pub(crate) present: Vec<ListedPage>,
+}
+
+/// Splits the expected pages into those missing from the listing and those present, and
+/// reports listed objects that are not part of the batch
+pub(crate) fn compare_keys(
+	expected: Vec<(String, PathBuf)>,
+	mut listed: HashMap<String, u64>,
+) -> KeyComparison {
+	let mut missing = Vec::new();
+	let mut present = Vec::new();
+	for (key, path) in expected {
+		match listed.remove(&key) {
+			Some(remote_size) => present.push(ListedPage {
+				key,
+				path,
+				remote_size,
+			}),
+			None => missing.push(ObjectDiscrepancy {
+				key,
+				path: Some(path.to_string_lossy().to_string()),
+				reason: "Not found in bucket".to_string(),
+			}),
+		}
+	}
+
+	let extra = listed
+		.into_iter()
+		.map(|(key, size)| ObjectDiscrepancy {
+			key,
+			path: None,
+			reason: format!("Not part of the batch ({size} bytes)"),
+		})
+		.collect();
+
+	KeyComparison {
+		missing,
+		extra,
+		present,
+	}
+}
+
+pub(crate) async fn list_prefix(
+	client: &Client,
+	bucket: &str,
+	prefix: &str,
+	cancellation: &CancellationFlag,
+) -> Result<HashMap<String, u64>, String> {
+	let pages = with_retry(
+		&get_retry_policy(),
+		&format!("listing of {prefix}"),
+		cancellation,
+		|| async {
+			client
+				.list_objects_v2()
+				.bucket(bucket)
+				.prefix(prefix)
+				.into_paginator()
+				.send()
+				.collect::<Result<Vec<_>, _>>()
+				.await
+				.map_err(|e| AttemptError::from_sdk(&format!("Failed to list {prefix}"), e))
+		},
+	)
+	.await
+	.map_err(|e| e.to_string())?;
+
+	Ok(pages
+		.iter()
+		.flat_map(|page| page.contents())
+		.filter_map(|object| {
+			let key = object.key()?;
+			Some((key.to_string(), object.size().unwrap_or_default() as u64))
+		})
+		.collect())
+}
+
+/// Compares size and checksum of one page with its local file. Returns `None` if they match.
+async fn verify_page(
+	client: &Client,
+	bucket: String,
+	page: ListedPage,
+	cancellation: CancellationFlag,
+) -> Result<Option<ObjectDiscrepancy>, String> {
+	let discrepancy = |reason: String| {
+		Some(ObjectDiscrepancy {
+			key: page.key.clone(),
+			path: Some(page.path.to_string_lossy().to_string()),
+			reason,
+		})
+	};
+
+	let local_size = match tokio::fs::metadata(&page.path).await {
+		Ok(metadata) => metadata.len(),
+		Err(e) => return Ok(discrepancy(format!("Failed to read local file: {e}"))),
+	};
+	if local_size != page.remote_size {
+		return Ok(discrepancy(format!(
+			"Size differs: {local_size} bytes locally, {} bytes in bucket",
+			page.remote_size
+		)));
+	}
+
+	let head = with_retry(
+		&get_retry_policy(),
+		&format!("metadata of {}", page.key),
+		&cancellation,
+		|| async {
+			client
+				.head_object()
+				.bucket(&bucket)
+				.key(&page.key)
+				.checksum_mode(ChecksumMode::Enabled)
+				.send()
+				.await
+				.map_err(|e| {
+					AttemptError::from_sdk(&format!("Failed to read metadata of {}", page.key), e)
+				})
+		},
+	)
+	.await
+	.map_err(|e| e.to_string())?;
+	let Some(remote_checksum) = head.checksum_sha256().map(str::to_string) else {
+		return Ok(discrepancy("Bucket has no SHA-256 checksum".to_string()));
+	};
+
+	let path = page.path.clone();
+	let local_checksum =
+		tokio::task::spawn_blocking(move || checksum::s3_sha256_file(&path, MULTIPART_PART_SIZE))
+			.await
+			.map_err(|e| format!("Failed to run blocking task: {e}"))?;
+	match local_checksum {
+		Ok(local_checksum) if local_checksum == remote_checksum => Ok(None),
+		Ok(local_checksum) => Ok(discrepancy(format!(
+			"Checksum differs: {local_checksum} locally, {remote_checksum} in bucket"
+		))),
+		Err(e) => Ok(discrepancy(format!("Failed to read local file: {e}"))),
+	}
+}
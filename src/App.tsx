@@ -62,7 +62,7 @@ interface ContentProps {
 
 const Content: React.FC<ContentProps> = ({openSettings, setOpenSettings}) => {
     const {authResponse, loggedOut, isLoggingIn, isRefreshingToken, fetchSecretsError, login, logout} = useAuth();
-    const {scannerPath} = useSettings();
+    const {scannerPath, derivativeCacheEnabled} = useSettings();
     const {secrets, getSecrets} = useSecrets();
     const {handleFrontendError} = useMessage();
     const {
@@ -373,7 +373,7 @@ const Content: React.FC<ContentProps> = ({openSettings, setOpenSettings}) => {
                 </div>
             </div>
             <div className="flex-1 min-h-0 flex flex-col">
-                <TrokkFilesProvider scannerPath={scannerPath}>
+                <TrokkFilesProvider scannerPath={scannerPath} derivativeCacheEnabled={derivativeCacheEnabled}>
                     <SelectionProvider>
                         <RotationProvider>
                             <UploadProgressProvider>
@@ -1,4 +1,5 @@
 // usePostRegistration.ts
+import {useState} from 'react';
 import {useTrokkFiles} from './trokk-files-context.tsx';
 import {invoke} from '@tauri-apps/api/core';
 import {RegistrationFormProps} from '../features/registration/registration-form-props.tsx';
@@ -6,7 +7,7 @@ import {settings} from '../tauri-store/setting-store.ts';
 import {useMessage} from './message-context.tsx';
 import {useUploadProgress} from './upload-progress-context.tsx';
 import {useAuth} from './auth-context.tsx';
-import {uploadToS3} from '../features/registration/upload-to-s3.tsx';
+import {cancelUpload, uploadToS3} from '../features/registration/upload-to-s3.tsx';
 import {useSecrets} from './secret-context.tsx';
 import {useVersion} from './version-context.tsx';
 import {useSelection} from '../context/selection-context.tsx';
@@ -17,6 +18,7 @@ import {BatchTextInputDto} from '../model/batch-text-input-dto.ts';
 import {TextItemResponse} from '../model/text-input-response.ts';
 import {remove} from '@tauri-apps/plugin-fs';
 import {AllTransferProgress} from '@/model/transfer-progress.ts';
+import {UploadSummary} from '@/model/upload-summary.ts';
 import * as Sentry from '@sentry/react';
 import {getErrorDiagnostics, getErrorMessage, type ErrorDiagnostics} from '@/lib/utils.ts';
 
@@ -81,6 +83,25 @@ export function groupFilesByCheckedItems(
     return batchMap;
 }
 
+export function itemsFromUploadSummary(
+    batchMap: Map<string, {
+        primary: string[],
+        access: string[]
+    }>,
+    uploadSummary: UploadSummary
+): BatchTextInputDto['items'] {
+    return Array.from(batchMap.entries()).map(([itemId, pages]) => {
+        const totalItems = pages.access.length > 0
+            ? pages.access.length
+            : pages.primary.length;
+        const itemPaths = new Set([...pages.primary, ...pages.access]);
+        const checksums = uploadSummary.objects
+            .filter(object => itemPaths.has(object.path))
+            .map(({key, sha256}) => ({key, sha256}));
+        return {itemId, pages: totalItems, checksums};
+    });
+}
+
 const API_ERROR_MESSAGES: Record<number, string> = {
     401: 'Kunne ikke lagre objektet fordi innloggingen ikke lenger er gyldig.',
     403: 'Kunne ikke lagre objektet fordi du ikke har tilgang.',
@@ -186,6 +207,15 @@ export function usePostRegistration() {
     const {handleError, handleBackendError, clearError, displaySuccessMessage} = useMessage();
     const {setAllUploadProgress} = useUploadProgress();
     const {checkedItems} = useSelection();
+    // Id of the upload in progress, so it can be cancelled
+    const [uploadJobId, setUploadJobId] = useState<string | null>(null);
+
+    async function cancelCurrentUpload(): Promise<boolean> {
+        if (!uploadJobId) {
+            return false;
+        }
+        return await cancelUpload(uploadJobId);
+    }
 
     async function postRegistration(
         machineName: string,
@@ -214,16 +244,16 @@ export function usePostRegistration() {
             checkedItems
         );
 
-        await uploadToS3(registration, batchMap);
-        const itemIdToCountOfItems = new Map<string, number>();
-        for (const [itemId, pages] of batchMap.entries()) {
-            const totalItems = pages.access.length > 0
-                ? pages.access.length
-                : pages.primary.length;
-            itemIdToCountOfItems.set(itemId, totalItems);
+        const jobId = uuidv7().toString();
+        setUploadJobId(jobId);
+        let uploadSummary: UploadSummary;
+        try {
+            uploadSummary = await uploadToS3(registration, batchMap, jobId);
+        } finally {
+            setUploadJobId(null);
         }
 
-        const itemsArray = Array.from(itemIdToCountOfItems.entries()).map(([itemId, pages]) => ({ itemId, pages }));
+        const itemsArray = itemsFromUploadSummary(batchMap, uploadSummary);
 
         const body = new BatchTextInputDto(
             checkedItems.length > 1 ? uuidv7().toString() : null, //Adding batchId only for multiple objects
@@ -296,5 +326,5 @@ export function usePostRegistration() {
         }
     }
 
-    return {postRegistration};
+    return {postRegistration, uploadJobId, cancelCurrentUpload};
 }
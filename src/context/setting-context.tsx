@@ -1,5 +1,18 @@
 import React, { createContext, ReactNode, useContext, useEffect, useRef, useState } from 'react';
-import { settings, type Theme } from '../tauri-store/setting-store.ts';
+import {
+    type ConversionProfile,
+    type ConversionProfiles,
+    type ConversionTarget,
+    defaultConversionProfiles,
+    defaultKeyLayout,
+    defaultUploadConcurrency,
+    defaultUploadRetryPolicy,
+    type KeyLayout,
+    settings,
+    type Theme,
+    type UploadConcurrency,
+    type UploadRetryPolicy,
+} from '../tauri-store/setting-store.ts';
 import { getVersion } from '@tauri-apps/api/app';
 import {invoke} from '@tauri-apps/api/core';
 import {
@@ -12,13 +25,22 @@ interface SettingContextType {
     scannerPath: string;
     version: React.MutableRefObject<string>;
     textSize: number;
-    thumbnailSizeFraction: number;
-    previewSizeFraction: number;
+    conversionProfiles: ConversionProfiles;
+    derivativeCacheEnabled: boolean;
+    sharpnessThreshold: number;
+    uploadRetryPolicy: UploadRetryPolicy;
+    uploadConcurrency: UploadConcurrency;
+    keyLayout: KeyLayout;
+    keyLayoutError: string | undefined;
     workspacePaneSizes: WorkspacePaneSizes;
     setScannerPathSetting: (path: string) => void;
     setTextSize: (size: number) => void;
-    setThumbnailSizeFraction: (fraction: number) => void;
-    setPreviewSizeFraction: (fraction: number) => void;
+    setConversionProfile: (target: ConversionTarget, profile: ConversionProfile) => Promise<void>;
+    setDerivativeCacheEnabled: (enabled: boolean) => Promise<void>;
+    setSharpnessThreshold: (threshold: number) => Promise<void>;
+    setUploadRetryPolicy: (policy: UploadRetryPolicy) => Promise<void>;
+    setUploadConcurrency: (concurrency: UploadConcurrency) => Promise<void>;
+    setKeyLayout: (layout: KeyLayout) => Promise<void>;
     setWorkspacePaneSizes: (sizes: WorkspacePaneSizes) => void;
     theme: Theme;
     setTheme: (theme: Theme) => void;
@@ -29,13 +51,17 @@ const SettingContext = createContext<SettingContextType | null>(null);
 export const SettingProvider: React.FC<{ children: ReactNode }> = ({ children }) => {
     const [scannerPath, setScannerPath] = useState<string>('');
     const [textSize, setTextSizeState] = useState<number>(100);
-    const [thumbnailSizeFraction, setThumbnailSizeFractionState] = useState<number>(8);
-    const [previewSizeFraction, setPreviewSizeFractionState] = useState<number>(4);
+    const [conversionProfiles, setConversionProfilesState] = useState<ConversionProfiles>(defaultConversionProfiles);
+    const [derivativeCacheEnabled, setDerivativeCacheEnabledState] = useState<boolean>(false);
+    const [sharpnessThreshold, setSharpnessThresholdState] = useState<number>(100);
+    const [uploadRetryPolicy, setUploadRetryPolicyState] = useState<UploadRetryPolicy>(defaultUploadRetryPolicy);
+    const [uploadConcurrency, setUploadConcurrencyState] = useState<UploadConcurrency>(defaultUploadConcurrency);
+    const [keyLayout, setKeyLayoutState] = useState<KeyLayout>(defaultKeyLayout);
+    const [keyLayoutError, setKeyLayoutError] = useState<string | undefined>(undefined);
     const [workspacePaneSizes, setWorkspacePaneSizesState] = useState<WorkspacePaneSizes>(defaultWorkspacePaneSizes);
     const [theme, setThemeState] = useState<Theme>('dark');
     const systemThemeListenerRef = useRef<(() => void) | null>(null);
     const version = useRef<string>('');
-    const initialized = useRef<boolean>(false);
 
     const applyTheme = (theme: Theme) => {
         // Clean up any existing system theme listener
@@ -67,27 +93,59 @@ export const SettingProvider: React.FC<{ children: ReactNode }> = ({ children })
     useEffect(() => {
         const initialize = async () => {
             await settings.init();
+            // Set before the scanner path, so no thumbnails are made in the wrong place
+            const storedDerivativeCacheEnabled = await settings.getDerivativeCacheEnabled();
+            await invoke('set_derivative_cache', {enabled: storedDerivativeCacheEnabled})
+                .then(() => setDerivativeCacheEnabledState(storedDerivativeCacheEnabled))
+                .catch((error) => {
+                    console.error('Error syncing derivative cache setting during init:', error);
+                });
             setScannerPath(await settings.getScannerPath());
+            const storedSharpnessThreshold = await settings.getSharpnessThreshold();
+            await invoke('set_sharpness_threshold', {threshold: storedSharpnessThreshold})
+                .then(() => setSharpnessThresholdState(storedSharpnessThreshold))
+                .catch((error) => {
+                    console.error('Error syncing sharpness threshold during init:', error);
+                });
+            const storedUploadRetryPolicy = await settings.getUploadRetryPolicy();
+            await invoke('set_upload_retry_policy', {...storedUploadRetryPolicy})
+                .then(() => setUploadRetryPolicyState(storedUploadRetryPolicy))
+                .catch((error) => {
+                    console.error('Error syncing upload retry policy during init:', error);
+                });
+            const storedUploadConcurrency = await settings.getUploadConcurrency();
+            await invoke('set_upload_concurrency', {...storedUploadConcurrency})
+                .then(() => setUploadConcurrencyState(storedUploadConcurrency))
+                .catch((error) => {
+                    console.error('Error syncing upload concurrency during init:', error);
+                });
+            // A stored layout the backend rejects keeps the default layout active until it is corrected
+            const storedKeyLayout = await settings.getKeyLayout();
+            setKeyLayoutState(storedKeyLayout);
+            await invoke('set_key_layout', {...storedKeyLayout})
+                .catch((error) => {
+                    console.error('Error syncing key layout during init:', error);
+                    setKeyLayoutError(String(error));
+                });
             const storedTextSize = await settings.getTextSize();
             setTextSizeState(storedTextSize);
-            const storedThumbnailFraction = await settings.getThumbnailSizeFraction();
-            const storedPreviewFraction = await settings.getPreviewSizeFraction();
+            const storedConversionProfiles: ConversionProfiles = {
+                thumbnail: await settings.getConversionProfile('thumbnail'),
+                preview: await settings.getConversionProfile('preview'),
+            };
+            for (const target of ['thumbnail', 'preview'] as const) {
+                await invoke('set_conversion_profile', {target, ...storedConversionProfiles[target]})
+                    .catch((error) => {
+                        console.error(`Error syncing ${target} conversion profile during init:`, error);
+                    });
+            }
+            setConversionProfilesState(storedConversionProfiles);
             const storedWorkspacePaneSizes = await settings.getWorkspacePaneSizes();
-            setThumbnailSizeFractionState(storedThumbnailFraction);
-            setPreviewSizeFractionState(storedPreviewFraction);
             setWorkspacePaneSizesState(storedWorkspacePaneSizes);
             const storedTheme = await settings.getTheme();
             setThemeState(storedTheme);
             applyTheme(storedTheme);
             version.current = await getVersion();
-            initialized.current = true;
-
-            await invoke('set_image_size_fractions', {
-                thumbnailFraction: storedThumbnailFraction,
-                previewFraction: storedPreviewFraction
-            }).catch((error) => {
-                console.error('Error syncing image size fractions during init:', error);
-            });
         };
         void initialize();
     }, []);
@@ -97,16 +155,6 @@ export const SettingProvider: React.FC<{ children: ReactNode }> = ({ children })
         document.documentElement.style.fontSize = `${textSize}%`;
     }, [textSize]);
 
-    useEffect(() => {
-        if (!initialized.current) return;
-        void invoke('set_image_size_fractions', {
-            thumbnailFraction: thumbnailSizeFraction,
-            previewFraction: previewSizeFraction
-        }).catch((error) => {
-            console.error('Error syncing image size fractions:', error);
-        });
-    }, [thumbnailSizeFraction, previewSizeFraction]);
-
     function setScannerPathSetting(path: string) {
         void settings.setScannerPath(path).then(() => {
             setScannerPath(path);
@@ -121,18 +169,41 @@ export const SettingProvider: React.FC<{ children: ReactNode }> = ({ children })
         });
     }
 
-    function setThumbnailSizeFraction(fraction: number) {
-        const clampedFraction = Math.max(1, Math.min(16, fraction));
-        void settings.setThumbnailSizeFraction(clampedFraction).then(() => {
-            setThumbnailSizeFractionState(clampedFraction);
-        });
+    async function setConversionProfile(target: ConversionTarget, profile: ConversionProfile) {
+        await invoke('set_conversion_profile', {target, ...profile});
+        await settings.setConversionProfile(target, profile);
+        setConversionProfilesState((profiles) => ({...profiles, [target]: profile}));
     }
 
-    function setPreviewSizeFraction(fraction: number) {
-        const clampedFraction = Math.max(1, Math.min(16, fraction));
-        void settings.setPreviewSizeFraction(clampedFraction).then(() => {
-            setPreviewSizeFractionState(clampedFraction);
-        });
+    async function setDerivativeCacheEnabled(enabled: boolean) {
+        await invoke('set_derivative_cache', {enabled});
+        await settings.setDerivativeCacheEnabled(enabled);
+        setDerivativeCacheEnabledState(enabled);
+    }
+
+    async function setSharpnessThreshold(threshold: number) {
+        await invoke('set_sharpness_threshold', {threshold});
+        await settings.setSharpnessThreshold(threshold);
+        setSharpnessThresholdState(threshold);
+    }
+
+    async function setUploadRetryPolicy(policy: UploadRetryPolicy) {
+        await invoke('set_upload_retry_policy', {...policy});
+        await settings.setUploadRetryPolicy(policy);
+        setUploadRetryPolicyState(policy);
+    }
+
+    async function setUploadConcurrency(concurrency: UploadConcurrency) {
+        await invoke('set_upload_concurrency', {...concurrency});
+        await settings.setUploadConcurrency(concurrency);
+        setUploadConcurrencyState(concurrency);
+    }
+
+    async function setKeyLayout(layout: KeyLayout) {
+        await invoke('set_key_layout', {...layout});
+        await settings.setKeyLayout(layout);
+        setKeyLayoutState(layout);
+        setKeyLayoutError(undefined);
     }
 
     function setTheme(theme: Theme) {
@@ -162,13 +233,22 @@ export const SettingProvider: React.FC<{ children: ReactNode }> = ({ children })
             scannerPath,
             version,
             textSize,
-            thumbnailSizeFraction,
-            previewSizeFraction,
+            conversionProfiles,
+            derivativeCacheEnabled,
+            sharpnessThreshold,
+            uploadRetryPolicy,
+            uploadConcurrency,
+            keyLayout,
+            keyLayoutError,
             workspacePaneSizes,
             setScannerPathSetting,
             setTextSize,
-            setThumbnailSizeFraction,
-            setPreviewSizeFraction,
+            setConversionProfile,
+            setDerivativeCacheEnabled,
+            setSharpnessThreshold,
+            setUploadRetryPolicy,
+            setUploadConcurrency,
+            setKeyLayout,
             setWorkspacePaneSizes,
             theme,
             setTheme,
@@ -1,16 +1,10 @@
 import React, {createContext, useContext, useEffect, useReducer, useRef} from 'react';
-import {
-    exists,
-    WatchEvent,
-    WatchEventKind,
-    WatchEventKindCreate,
-    WatchEventKindModify,
-    WatchEventKindRemove,
-    watchImmediate
-} from '@tauri-apps/plugin-fs';
 import {FileTree} from '../model/file-tree';
 import {invoke} from '@tauri-apps/api/core';
-import {ConversionResult} from '../model/thumbnail';
+import {listen} from '@tauri-apps/api/event';
+import {exists} from '@tauri-apps/plugin-fs';
+import {ConversionProgress, ConversionResult, DerivativeReady} from '../model/thumbnail';
+import {ScanFolderChange, ScanFolderEventKind, scanFolderEventKinds} from '../model/scan-folder-change';
 import {documentDir, sep} from '@tauri-apps/api/path';
 import {getFolderImageSummary, isImage} from '../util/file-utils.ts';
 
@@ -18,6 +12,10 @@ export interface TrokkFilesState {
     basePath: string;
     fileTrees: FileTree[];
     treeIndex: Map<string, FileTree>;
+    /** Path of the thumbnail of every image that has one, wherever thumbnails are stored */
+    thumbnails: Map<string, string>;
+    /** Latest progress of a directory whose thumbnails are being made */
+    conversionProgress: ConversionProgress | undefined;
     current: FileTree | undefined;
     preview: FileTree | undefined;
     isEven: boolean;
@@ -47,11 +45,17 @@ type TrokkFilesAction =
     | { type: 'RESET' }
     | { type: 'UPDATE_PREVIEW'; payload: FileTree | undefined }
     | { type: 'SET_IS_SUBMITTING'; payload: boolean}
+    | { type: 'UPDATE_THUMBNAILS'; added: [string, string][]; removed: string[] }
+    | { type: 'CLEAR_THUMBNAILS' }
+    | { type: 'SET_CONVERSION_PROGRESS'; payload: ConversionProgress }
+    | { type: 'CONVERSION_FINISHED'; directory: string }
 
 const initialState: TrokkFilesState = {
     basePath: await documentDir(),
     fileTrees: [],
     treeIndex: new Map<string, FileTree>(),
+    thumbnails: new Map<string, string>(),
+    conversionProgress: undefined,
     current: undefined,
     preview: undefined,
     isEven: true,
@@ -94,17 +98,19 @@ const calculateIsEven = (fileTree: FileTree | undefined): boolean => {
 };
 
 const createThumbnailsFromDirectory = async (directoryPath: string) => {
-    invoke<ConversionResult>('convert_directory_to_webp', {directoryPath: directoryPath})
+    await invoke<ConversionResult>('convert_directory_to_webp', {directoryPath: directoryPath})
         .catch((err) => {
             console.error(err);
         });
 };
 
-const createThumbnail = async (filePath: string) => {
-    invoke('create_thumbnail_webp', {filePath: filePath})
-        .catch((err) => {
-            console.error(err);
-        });
+// Thumbnails may be stored beside the scans or in the app's cache, so the backend resolves where
+const findExistingThumbnails = async (imagePaths: string[]): Promise<[string, string][]> => {
+    const thumbnails = await Promise.all(imagePaths.map(async (imagePath) => {
+        const thumbnailPath = await invoke<string>('get_derivative_path', {filePath: imagePath, highRes: false});
+        return await exists(thumbnailPath) ? [imagePath, thumbnailPath] as [string, string] : undefined;
+    }));
+    return thumbnails.filter((thumbnail): thumbnail is [string, string] => thumbnail !== undefined);
 };
 
 const createPreview = async (filePath: string) => {
@@ -114,63 +120,6 @@ const createPreview = async (filePath: string) => {
         });
 }
 
-function updateRename(
-    state: TrokkFilesState,
-    oldPath: string,
-    newPath: string,
-    kind: 'file' | 'folder'
-): TrokkFilesState {
-    const updatedTreeIndex = new Map<string, FileTree>();
-    const pathSeparator = sep();
-
-    const updateFileTreeRecursive = (fileTree: FileTree): FileTree => {
-        const shouldUpdate =
-            kind === 'folder'
-                ? fileTree.path === oldPath || fileTree.path.startsWith(oldPath + pathSeparator)
-                : fileTree.path === oldPath;
-
-        let updatedPath = fileTree.path;
-        let updatedName = fileTree.name;
-
-        if (shouldUpdate) {
-            updatedPath = fileTree.path.replace(oldPath, newPath);
-            updatedName = updatedPath.split(pathSeparator).pop() ?? fileTree.name;
-        }
-
-        const updatedChildren = fileTree.children?.map(updateFileTreeRecursive) ?? [];
-
-        return new FileTree(
-            updatedName,
-            fileTree.isDirectory,
-            fileTree.isFile,
-            fileTree.isSymlink,
-            updatedPath,
-            fileTree.opened,
-            updatedChildren
-        );
-    };
-
-    const updatedFileTrees = state.fileTrees.map(tree => {
-        const updatedTree = updateFileTreeRecursive(tree);
-        populateIndexFromTree(updatedTree, updatedTreeIndex);
-        return updatedTree;
-    });
-
-    return {
-        ...state,
-        fileTrees: updatedFileTrees,
-        treeIndex: updatedTreeIndex,
-    };
-}
-
-// Helper to recursively populate the index
-function populateIndexFromTree(fileTree: FileTree, index: Map<string, FileTree>) {
-    index.set(fileTree.path, fileTree);
-    if (fileTree.children) {
-        fileTree.children.forEach(child => populateIndexFromTree(child, index));
-    }
-}
-
 // Populate the map with existing tree nodes
 const populateIndex = (fileTrees: FileTree[]): Map<string, FileTree> => {
     const treeIndex = new Map<string, FileTree>();
@@ -186,152 +135,11 @@ const populateIndex = (fileTrees: FileTree[]): Map<string, FileTree> => {
     return treeIndex;
 };
 
-function isCreate(event: WatchEventKind): event is { create: WatchEventKindCreate } {
-    try {
-        const createEvent = (event as { create: WatchEventKindCreate }).create;
-        return createEvent.kind == 'any' || createEvent.kind == 'folder' || createEvent.kind == 'file';
-    } catch {
-        return false;
-    }
-}
-
-function isDelete(event: WatchEventKind): event is { remove: WatchEventKindRemove } {
-    try {
-        // Check if event is an object and has remove property
-        if (typeof event !== 'object' || event === null) {
-            return false;
-        }
-        const eventObj = event as { remove?: WatchEventKindRemove };
-        if (!eventObj.remove || !eventObj.remove.kind) {
-            return false;
-        }
-        const removeEvent = eventObj.remove;
-        return removeEvent.kind == 'folder' || removeEvent.kind == 'file' || removeEvent.kind == 'any';
-    } catch {
-        return false;
-    }
-}
-
-function isModifyRenameFrom(event: WatchEventKind): event is { modify: WatchEventKindModify } {
-    try {
-        const modifyEvent = (event as { modify: WatchEventKindModify }).modify;
-        return modifyEvent.kind == 'rename' && modifyEvent.mode == 'from';
-    } catch {
-        return false;
-    }
-}
-
-function isModifyRenameTo(event: WatchEventKind): event is { modify: WatchEventKindModify } {
-    try {
-        const modifyEvent = (event as { modify: WatchEventKindModify }).modify;
-        return modifyEvent.kind == 'rename' && modifyEvent.mode == 'to';
-    } catch {
-        return false;
-    }
-}
-
-
-interface PathsSorted {
-    create: EventPathAndKind[];
-    remove: EventPathAndKind[];
-    renameFrom: EventPathAndKind[];
-    renameTo: EventPathAndKind[];
-}
-
 interface EventPathAndKind {
     path: string;
     kind: 'folder' | 'file';
 }
 
-function isFile(path: string): boolean {
-    return isImage(path);
-}
-
-function splitWatchEvents(events: WatchEvent[], processedEvents: Set<string>): PathsSorted {
-    const deduplicatedEvents = events.filter((event, index, self) => {
-        if (event.paths.some((path) => path.endsWith('TeraCopyTestFile-1234567890'))) {
-            return false; // Ignore TeraCopy test files
-        }
-
-        const eventTypeStr = JSON.stringify(event.type);
-        const eventKey = `${event.paths.join('|')}::${eventTypeStr}`;
-        // NOTE: Since eventsKey is based on path and type, if the user performs multiple identical operations
-        // valid events may be ignored. Implement more sophisticated logic if needed.
-        if (processedEvents.has(eventKey)) {
-            return false;
-        }
-
-        // Check for duplicates in current batch
-        const isDuplicate = self.findIndex((t) => {
-            const tEventTypeStr = JSON.stringify(t.type);
-            return t.paths[0] === event.paths[0] && tEventTypeStr === eventTypeStr;
-        }) !== index;
-
-        if (!isDuplicate) {
-            processedEvents.add(eventKey);
-        }
-
-        return !isDuplicate;
-    });
-
-    const paths = deduplicatedEvents.reduce(
-        (acc, event) => {
-            if (isCreate(event.type)) {
-                event.paths.forEach((path) => {
-                    acc.create.push({path: path, kind: isFile(path) ? 'file' : 'folder'});
-                });
-            } else if (isModifyRenameFrom(event.type)) {
-                event.paths.forEach((path) => {
-                    acc.renameFrom.push({path: path, kind: isFile(path) ? 'file' : 'folder'});
-
-                });
-            } else if (isModifyRenameTo(event.type)) {
-                event.paths.forEach((path) => {
-                    acc.renameTo.push({path: path, kind: isFile(path) ? 'file' : 'folder'});
-                });
-            } else if (isDelete(event.type)) {
-                event.paths.forEach((path) => {
-                    acc.remove.push({path, kind: isFile(path) ? 'file' : 'folder'});
-                });
-            } else {
-                console.debug('❓ Unknown event type:', JSON.stringify(event.type), 'for paths:', event.paths);
-            }
-            return acc;
-        },
-        {
-            create: [] as EventPathAndKind[],
-            remove: [] as EventPathAndKind[],
-            renameFrom: [] as EventPathAndKind[],
-            renameTo: [] as EventPathAndKind[],
-        } as PathsSorted
-    );
-
-    const sortByFolderFirst = (a: EventPathAndKind, b: EventPathAndKind) => {
-        if (a.kind === 'folder' && b.kind === 'file') return -1;
-        if (a.kind === 'file' && b.kind === 'folder') return 1;
-        return 0;
-    };
-
-    paths.create.sort(sortByFolderFirst);
-    paths.remove.sort(sortByFolderFirst);
-    paths.renameFrom.sort(sortByFolderFirst);
-    paths.renameTo.sort(sortByFolderFirst);
-    return paths;
-}
-
-function createNewThumbnailFromEvents(currentPath: string, events: EventPathAndKind[]): void {
-    events.forEach((event) => {
-        if (
-            event.path.includes(currentPath) &&
-            event.kind === 'file' &&
-            isImage(event.path) &&
-            !event.path.includes('.thumbnails/')
-        ) {
-            void createThumbnail(event.path);
-        }
-    });
-}
-
 async function updateFileTreesWithNewObject(state: TrokkFilesState, eventPathsSorted: EventPathAndKind[]): Promise<TrokkFilesState> {
     const insertFileTree = (fileTrees: FileTree[], newFileTree: FileTree): FileTree[] => {
         const parentPath = newFileTree.path.split(sep()).slice(0, -1).join(sep());
@@ -478,14 +286,10 @@ const trokkFilesReducer = (state: TrokkFilesState, action: TrokkFilesAction): Tr
             };
         }
         case 'SET_CURRENT': {
-            if (action.payload) {
-                void createThumbnailsFromDirectory(action.payload.path);
-            }
             const isEven = calculateIsEven(action.payload);
             return {...state, current: action.payload, preview: undefined, isEven};
         }
         case 'SET_CURRENT_AND_EXPAND_PARENTS': {
-            void createThumbnailsFromDirectory(action.payload.path);
             return setCurrentAndExpandParents(state, action.payload);
         }
         case 'REMOVE_FOLDER_PATH': {
@@ -524,19 +328,33 @@ const trokkFilesReducer = (state: TrokkFilesState, action: TrokkFilesAction): Tr
             return {...state, preview: action.payload};
         case 'SET_IS_SUBMITTING':
             return {...state, isSubmitting: action.payload};
+        case 'UPDATE_THUMBNAILS': {
+            const thumbnails = new Map(state.thumbnails);
+            action.removed.forEach((path) => thumbnails.delete(path));
+            action.added.forEach(([path, thumbnail]) => thumbnails.set(path, thumbnail));
+            return {...state, thumbnails};
+        }
+        case 'CLEAR_THUMBNAILS':
+            return {...state, thumbnails: new Map()};
+        case 'SET_CONVERSION_PROGRESS':
+            return {...state, conversionProgress: action.payload};
+        case 'CONVERSION_FINISHED':
+            if (state.conversionProgress?.directory !== action.directory) return state;
+            return {...state, conversionProgress: undefined};
         default:
             return state;
     }
 };
 
-export const TrokkFilesProvider: React.FC<{ children: React.ReactNode; scannerPath: string }> = ({
-                                                                                                     children,
-                                                                                                     scannerPath
-                                                                                                 }) => {
+export const TrokkFilesProvider: React.FC<{
+    children: React.ReactNode;
+    scannerPath: string;
+    derivativeCacheEnabled: boolean;
+}> = ({children, scannerPath, derivativeCacheEnabled}) => {
     const [state, dispatch] = useReducer(trokkFilesReducer, initialState);
     const stateRef = useRef(state);
-    const eventQueue = useRef<WatchEvent[]>([]);
-    const processedEvents = useRef<Set<string>>(new Set());
+    const changeQueue = useRef<{ kind: ScanFolderEventKind; change: ScanFolderChange }[]>([]);
+    const readyThumbnails = useRef<[string, string][]>([]);
 
     useEffect(() => {
         stateRef.current = state;
@@ -549,6 +367,33 @@ export const TrokkFilesProvider: React.FC<{ children: React.ReactNode; scannerPa
         };
     }, [scannerPath]);
 
+    // Thumbnails move when the derivative cache is turned on or off
+    useEffect(() => {
+        dispatch({type: 'CLEAR_THUMBNAILS'});
+    }, [derivativeCacheEnabled]);
+
+    useEffect(() => {
+        const directoryPath = state.current?.path;
+        if (!directoryPath) return;
+        let cancelled = false;
+
+        const createAndFindThumbnails = async () => {
+            await createThumbnailsFromDirectory(directoryPath);
+            dispatch({type: 'CONVERSION_FINISHED', directory: directoryPath});
+            const imagePaths = stateRef.current.current?.children
+                ?.filter((child) => !child.isDirectory && isImage(child.path))
+                .map((child) => child.path) ?? [];
+            const thumbnails = await findExistingThumbnails(imagePaths);
+            if (!cancelled) {
+                dispatch({type: 'UPDATE_THUMBNAILS', added: thumbnails, removed: []});
+            }
+        };
+        createAndFindThumbnails().catch((error) => console.error('Failed to find thumbnails:', error));
+        return () => {
+            cancelled = true;
+        };
+    }, [state.current?.path, derivativeCacheEnabled]);
+
 
     const initialize = async () => {
         console.debug('Initializing TrokkFilesProvider', scannerPath);
@@ -568,51 +413,37 @@ export const TrokkFilesProvider: React.FC<{ children: React.ReactNode; scannerPa
         dispatch({type: 'INIT_STATE', payload: {fileTrees: fileTrees ?? [], scannerPath: scannerPath}});
 
         const processQueue = async () => {
-            if (eventQueue.current.length === 0) return;
-
-            const events = eventQueue.current;
-            eventQueue.current = [];
-
-            // Check if any paths that received events still exist
-            // If they don't exist, they were deleted (even if we didn't get a remove event)
-            const pathsToCheck = new Set<string>();
-            events.forEach(event => {
-                event.paths.forEach(path => {
-                    pathsToCheck.add(path);
-                });
-            });
-
-            const deletedPaths: string[] = [];
-            for (const path of pathsToCheck) {
-                try {
-                    const stillExists = await exists(path);
-                    if (!stillExists) deletedPaths.push(path);
-                } catch {
-                    // Path doesn't exist
-                    deletedPaths.push(path);
+            if (changeQueue.current.length === 0 && readyThumbnails.current.length === 0) return;
+
+            const changes = changeQueue.current;
+            changeQueue.current = [];
+            const thumbnails = readyThumbnails.current;
+            readyThumbnails.current = [];
+
+            // Only the latest change to a path matters, e.g. a page removed and scanned again
+            const latestChanges = new Map<string, ScanFolderEventKind>();
+            changes.forEach(({kind, change}) => {
+                if (change.thumbnail) {
+                    thumbnails.push([change.path, change.thumbnail]);
                 }
-            }
-
-            const {create, remove, renameFrom, renameTo} = splitWatchEvents(events, processedEvents.current);
+                // A changed file is already in the tree, only its thumbnail is new
+                if (kind === 'file_changed') return;
+                latestChanges.delete(change.path);
+                latestChanges.set(change.path, kind);
+            });
 
-            // Add detected deletions to the remove list
-            deletedPaths.forEach(path => {
-                const treeNode = stateRef.current.treeIndex.get(path);
-                const kind = treeNode?.isDirectory ? 'folder' : 'file';
-                if (!remove.some(r => r.path === path)) {
-                    remove.push({ path, kind });
+            const create: EventPathAndKind[] = [];
+            const remove: EventPathAndKind[] = [];
+            latestChanges.forEach((kind, path) => {
+                if (kind === 'file_removed') {
+                    const treeNode = stateRef.current.treeIndex.get(path);
+                    remove.push({path, kind: treeNode?.isDirectory ? 'folder' : 'file'});
+                } else {
+                    create.push({path, kind: kind === 'folder_added' ? 'folder' : 'file'});
                 }
             });
-
-            // Clean up old processed events (keep only last 100 to prevent memory leak)
-            if (processedEvents.current.size > 100) {
-                const entries = Array.from(processedEvents.current);
-                processedEvents.current = new Set(entries.slice(-100));
-            }
-
-            if (stateRef.current?.current) {
-                createNewThumbnailFromEvents(stateRef.current.current.path, create);
-            }
+            // Folders first, so the files in them have somewhere to go
+            create.sort((a, b) => (a.kind === b.kind ? 0 : a.kind === 'folder' ? -1 : 1));
 
             let newState: TrokkFilesState | null = stateRef.current;
 
@@ -620,34 +451,6 @@ export const TrokkFilesProvider: React.FC<{ children: React.ReactNode; scannerPa
 
             newState = removeFileTree(newState, remove);
 
-            // Handles moves being treated as a rename-events from the OS
-            if (renameFrom.length > 0 || renameTo.length > 0) {
-                const usedTo = new Set<number>();
-
-                const baseName = (p: string) => p.split(sep()).pop() ?? p;
-
-                for (const from of renameFrom) {
-                    let toIndex = renameTo.findIndex((t, idx) => !usedTo.has(idx) && t.kind === from.kind && baseName(t.path) === baseName(from.path));
-
-                    if (toIndex === -1) {
-                        toIndex = renameTo.findIndex((t, idx) => !usedTo.has(idx) && t.kind === from.kind);
-                    }
-
-                    usedTo.add(toIndex);
-                    const newPath = renameTo[toIndex].path;
-                    newState = updateRename(newState, from.path, newPath, from.kind);
-
-                    if (newPath.toLowerCase().endsWith('.tif') && stateRef.current?.current?.path) {
-                        createNewThumbnailFromEvents(stateRef.current.current.path, [{ path: newPath, kind: from.kind }]);
-                    }
-                }
-
-                const unpairedTo = renameTo.filter((_, idx) => !usedTo.has(idx));
-                if (unpairedTo.length > 0) {
-                    newState = await updateFileTreesWithNewObject(newState, unpairedTo);
-                }
-            }
-
             // Rebuild index based on updated fileTrees (optional fallback)
             const newTreeIndex = populateIndex(newState.fileTrees);
 
@@ -662,27 +465,45 @@ export const TrokkFilesProvider: React.FC<{ children: React.ReactNode; scannerPa
                 payloadIndex: newTreeIndex,
                 payloadCurrent: current
             });
+            // Thumbnails are complete on disk once announced, wherever they are stored
+            dispatch({
+                type: 'UPDATE_THUMBNAILS',
+                added: thumbnails,
+                removed: remove.map(({path}) => path),
+            });
         };
 
-        const unwatch = await watchImmediate(scannerPath, async (event: WatchEvent) => {
-                // Log all events to understand what's happening
-                const eventTypeStr = JSON.stringify(event.type);
-                console.debug('📁 Event received:', {
-                    paths: event.paths,
-                    type: eventTypeStr,
-                    isDelete: isDelete(event.type),
-                    isCreate: isCreate(event.type)
-                });
-                eventQueue.current.push(event);
-            },
-            {
-                recursive: true
-            }
+        // The backend watches the scan folder, makes thumbnails and reports what changed
+        const unlistenChanges = await Promise.all(
+            scanFolderEventKinds.map((kind) =>
+                listen<ScanFolderChange>(kind, (event) => {
+                    if (event.payload.root === scannerPath) {
+                        changeQueue.current.push({kind, change: event.payload});
+                    }
+                })
+            )
         );
+        await invoke('watch_scan_folder', {directoryPath: scannerPath}).catch((error) => {
+            console.error('Failed to watch scan folder:', error);
+        });
+        const unlistenDerivativeReady = await listen<DerivativeReady>('derivative_ready', (event) => {
+            const {source, path, highRes} = event.payload;
+            if (!highRes && source.startsWith(scannerPath)) {
+                readyThumbnails.current.push([source, path]);
+            }
+        });
+        const unlistenConversionProgress = await listen<ConversionProgress>('conversion_progress', (event) => {
+            if (event.payload.directory === stateRef.current.current?.path) {
+                dispatch({type: 'SET_CONVERSION_PROGRESS', payload: event.payload});
+            }
+        });
         const intervalId = setInterval(processQueue, 1000);
 
         return () => {
-            unwatch();
+            void invoke('unwatch_scan_folder', {directoryPath: scannerPath});
+            unlistenChanges.forEach((unlisten) => unlisten());
+            unlistenDerivativeReady();
+            unlistenConversionProgress();
             clearInterval(intervalId);
         };
     }
@@ -9,13 +9,14 @@ import {
 } from '@/components/ui/dialog.tsx';
 import {useSelection} from '@/context/selection-context.tsx';
 import {remove} from '@tauri-apps/plugin-fs';
+import {invoke} from '@tauri-apps/api/core';
 import {FileTree} from '@/model/file-tree.ts';
 import {useTrokkFiles} from '@/context/trokk-files-context.tsx';
 import {useMessage} from '@/context/message-context.tsx';
 import {Trash} from 'lucide-react';
 import {basename, dirname, join} from '@tauri-apps/api/path';
 import * as Sentry from '@sentry/react';
-import {getErrorDiagnostics, getErrorMessage} from '@/lib/utils.ts';
+import {getErrorDiagnostics} from '@/lib/utils.ts';
 import {Button} from '@/components/ui/button.tsx';
 
 
@@ -32,11 +33,6 @@ const DeleteFile: React.FC<DeleteFile> = ({childPath, setDelFilePath, delFilePat
     const {checkedItems, handleCheck} = useSelection();
     const {handleBackendError} = useMessage();
 
-    const isMissingPathError = (error: unknown): boolean => {
-        const message = getErrorMessage(error).toLowerCase();
-        return message.includes('not found') || message.includes('no such file') || message.includes('finnes ikke');
-    };
-
     const updateFileTrees = (path: string) => {
         const updatedTree = removeFileFromTree(state.fileTrees, path);
         dispatch({type: 'SET_FILE_TREES', payload: updatedTree});
@@ -72,15 +68,6 @@ const DeleteFile: React.FC<DeleteFile> = ({childPath, setDelFilePath, delFilePat
         const path = filePath ?? delFilePath;
         if (!path) return;
 
-        const buildPath = async (targetPath: string, subdir: string, extension = 'webp') => {
-            const dir = await dirname(targetPath);
-            const base = await basename(targetPath);
-            const nameWithoutExt = base.replace(/\.\w+$/, '');
-            const fileName = `${nameWithoutExt}.${extension}`;
-            const targetDir = await join(dir, subdir);
-            return await join(targetDir, fileName);
-        };
-
         // Check if file is in a merge folder (i.e., path ends with /merge/<filename>)
         let parentPath: string | null = null;
         const parentDir = await dirname(path);
@@ -91,20 +78,6 @@ const DeleteFile: React.FC<DeleteFile> = ({childPath, setDelFilePath, delFilePat
             parentPath = await join(grandParentDir, fileBaseName);
         }
 
-            const previewPath = await buildPath(path, '.previews');
-            const thumbPath = await buildPath(path, '.thumbnails');
-
-            const parentPreviewPath = parentPath
-                         ? parentPath
-                             .replace(/([^/]+)$/, '.previews/$1')
-                             .replace(/\.\w+$/, '.webp')
-                         : null;
-             const parentThumbPath = parentPath
-                 ? parentPath
-                     .replace(/([^/]+)$/, '.thumbnails/$1')
-                     .replace(/\.\w+$/, '.webp')
-                 : null;
-
         try {
             Sentry.addBreadcrumb({
                 category: 'delete-file',
@@ -112,30 +85,18 @@ const DeleteFile: React.FC<DeleteFile> = ({childPath, setDelFilePath, delFilePat
                 level: 'info',
             });
 
-            // Delete main file + thumbnail (always required)
-            await Promise.all([
-                remove(path),
-                remove(thumbPath),
-            ]);
-
-            // Best-effort deletion of optional artifacts (ignore if they don't exist)
-            const optionalPaths = [
-                previewPath,
-                parentPreviewPath,
-                parentThumbPath,
-            ].filter((p): p is string => Boolean(p));
-
-            await Promise.all(
-                optionalPaths.map(async (optionalPath) => {
-                    try {
-                        await remove(optionalPath);
-                    } catch (error) {
-                        if (!isMissingPathError(error)) {
-                            throw error;
-                        }
-                    }
-                }),
-            );
+            await remove(path);
+
+            // The backend knows where the thumbnails and previews are kept, and skips missing ones
+            await invoke('delete_derivatives', {filePath: path});
+            await invoke('delete_orientation_sidecar', {filePath: path});
+            // Aborts unfinished uploads of a deleted folder, so S3 does not keep their parts
+            await invoke('discard_upload_journals', {directoryPath: path}).catch((error) => {
+                console.error('Failed to discard upload journals:', error);
+            });
+            if (parentPath) {
+                await invoke('delete_derivatives', {filePath: parentPath});
+            }
 
             updateFileTrees(path);
             if (parentPath) updateFileTrees(parentPath);
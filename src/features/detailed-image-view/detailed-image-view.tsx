@@ -6,7 +6,6 @@ import {useSelection} from '@/context/selection-context.tsx';
 import Checkbox from '@/components/ui/checkbox.tsx';
 import {useRotation} from '@/context/rotation-context.tsx';
 import StatusOverlay from '@/components/ui/rotation-status-overlay.tsx';
-import {sep} from '@tauri-apps/api/path';
 import {convertFileSrc, invoke} from '@tauri-apps/api/core';
 import {Button} from '@/components/ui/button.tsx';
 
@@ -20,6 +19,7 @@ export default function DetailedImageView({ image, totalImagesInFolder}: Detaile
     const [isLoading, setIsLoading] = useState<boolean>(false);
     const [hasError, setHasError] = useState<boolean>(false);
     const [retryBuster, setRetryBuster] = useState<number>(0);
+    const [previewWebpPath, setPreviewWebpPath] = useState<string | undefined>(undefined);
 
     const {currentIndex, handleNext, handlePrevious, handleClose, handleCheck, checkedItems} = useSelection();
     const {rotateImage, getImageStatus, getFileCacheBuster} = useRotation();
@@ -29,13 +29,8 @@ export default function DetailedImageView({ image, totalImagesInFolder}: Detaile
 
     const rotationCacheBuster = getFileCacheBuster(image.path);
 
-    const previewWebpPath = useMemo(() => {
-        const baseNoExt = image.path.replace(/\.[^/.]+$/, '');
-        const dir = image.path.substring(0, image.path.length - image.name.length);
-        return `${dir}.previews${sep()}${baseNoExt.split(sep()).pop()}.webp`;
-    }, [image.path, image.name]);
-
     const imageUrl = useMemo(() => {
+        if (!previewWebpPath) return undefined;
         return `${convertFileSrc(previewWebpPath)}?v=${rotationCacheBuster}.${retryBuster}`;
     }, [previewWebpPath, rotationCacheBuster, retryBuster]);
 
@@ -57,8 +52,20 @@ export default function DetailedImageView({ image, totalImagesInFolder}: Detaile
         setHasError(false);
         setRetryBuster(0);
         setIsLoading(true);
+        setPreviewWebpPath(undefined);
+
+        // Previews are kept next to the scan or in the app's cache, the backend knows which
+        let cancelled = false;
+        invoke<string>('get_derivative_path', {filePath: image.path, highRes: true})
+            .then((path) => {
+                if (!cancelled) setPreviewWebpPath(path);
+            })
+            .catch((e) => console.error('Failed to resolve preview path:', e));
         void invoke('create_preview_webp', { filePath: image.path })
             .catch((e) => console.error('Failed to create preview:', e));
+        return () => {
+            cancelled = true;
+        };
     }, [image.path]);
 
     useEffect(() => {
@@ -107,29 +114,31 @@ export default function DetailedImageView({ image, totalImagesInFolder}: Detaile
                             overflow: 'hidden'
                         }}
                     >
-                        <img
-                            key={`${previewWebpPath}-${rotationCacheBuster}-${retryBuster}`}
-                            src={imageUrl}
-                            alt="Forhåndsvisning av bilde"
-                            onLoad={() => {
-                                setIsLoading(false);
-                                setHasError(false);
-                            }}
-                            onError={() => {
-                                setIsLoading(false);
-                                setHasError(true);
-
-                                void invoke('create_preview_webp', { filePath: image.path })
-                                    .catch((e) => console.error('Failed to regenerate preview:', e))
-                                    .finally(() => setRetryBuster(Date.now()));
-                            }}
-                            style={{
-                                maxWidth: 'calc(100vw - 400px)',
-                                maxHeight: 'calc(100vh - 250px)',
-                                objectFit: 'contain',
-                                display: 'block'
-                            }}
-                        />
+                        {imageUrl && (
+                            <img
+                                key={`${previewWebpPath}-${rotationCacheBuster}-${retryBuster}`}
+                                src={imageUrl}
+                                alt="Forhåndsvisning av bilde"
+                                onLoad={() => {
+                                    setIsLoading(false);
+                                    setHasError(false);
+                                }}
+                                onError={() => {
+                                    setIsLoading(false);
+                                    setHasError(true);
+
+                                    void invoke('create_preview_webp', { filePath: image.path })
+                                        .catch((e) => console.error('Failed to regenerate preview:', e))
+                                        .finally(() => setRetryBuster(Date.now()));
+                                }}
+                                style={{
+                                    maxWidth: 'calc(100vw - 400px)',
+                                    maxHeight: 'calc(100vh - 250px)',
+                                    objectFit: 'contain',
+                                    display: 'block'
+                                }}
+                            />
+                        )}
 
                         {/* Reload spinner overlay */}
                         {isLoading && (
@@ -13,8 +13,10 @@ import {
     DialogDescription
 } from '@/components/ui/dialog.tsx';
 import {useKeyboardNavigation} from '@/hooks/use-keyboard-navigation.tsx';
+import {usePageQuality} from '@/hooks/use-page-quality.tsx';
 import {VisuallyHidden} from '@radix-ui/react-visually-hidden';
 import {cn} from '@/lib/utils.ts';
+import {Progress} from '@/components/ui/progress.tsx';
 import {getBreadcrumbSegments, getWorkingImageChildren, isImage} from '@/util/file-utils.ts';
 
 const FilesContainer: React.FC = () => {
@@ -35,9 +37,14 @@ const FilesContainer: React.FC = () => {
     const visibleChildren = state.current?.children?.filter(child =>
         !child.name.startsWith('.thumbnails') &&
         !child.name.startsWith('.previews') &&
+        !child.name.startsWith('.orientation') &&
         (child.isDirectory || isImage(child.name))
     ) || [];
+    const {blankPages, sharpness, duplicates} = usePageQuality(state.current?.path, files.length);
     const breadcrumbSegments = getBreadcrumbSegments(state.basePath, state.current?.path);
+    const conversionProgress = state.conversionProgress?.directory === state.current?.path
+        ? state.conversionProgress
+        : undefined;
 
     const containerRef = useRef<HTMLDivElement>(null);
     const fileRefs = useRef<(HTMLDivElement | null)[]>([]);
@@ -102,6 +109,19 @@ const FilesContainer: React.FC = () => {
                                 ))}
                             </nav>
                         </div>
+                        {conversionProgress && conversionProgress.done < conversionProgress.total && (
+                            <div className="flex items-center gap-4 text-sm text-muted-foreground">
+                                <p className="whitespace-nowrap">
+                                    Lager miniatyrbilder: {conversionProgress.done} av {conversionProgress.total}
+                                    {conversionProgress.failures > 0 && ` (${conversionProgress.failures} feilet)`}
+                                </p>
+                                <Progress
+                                    value={conversionProgress.done / conversionProgress.total * 100}
+                                    className="max-w-[300px]"
+                                    aria-label="Fremdrift for miniatyrbilder"
+                                />
+                            </div>
+                        )}
                         {visibleChildren.length !== 0 && (
                             <div className="flex items-center gap-4">
                                 <p className="font-semibold">
@@ -169,6 +189,9 @@ const FilesContainer: React.FC = () => {
                                                         setDelFilePath={setDelFilePath}
                                                         delFilePath={delFilePath}
                                                         isDisabled={state.isSubmitting}
+                                                        blankness={blankPages.get(child.path)?.blankness}
+                                                        isBlurry={sharpness.get(child.path)?.blurry}
+                                                        isDuplicate={duplicates.has(child.path)}
                                                     />
                                                     <Checkbox
                                                         aria-label={'Velg forside'}
@@ -178,6 +201,9 @@ const FilesContainer: React.FC = () => {
                                                         onChange={() => handleCheck()}
                                                         isFocused={!previewDialogOpen && currentIndex === index}
                                                         isDisabled={state.isSubmitting}
+                                                        blankness={blankPages.get(child.path)?.blankness}
+                                                        isBlurry={sharpness.get(child.path)?.blurry}
+                                                        isDuplicate={duplicates.has(child.path)}
                                                     />
                                                 </div>
                                             )
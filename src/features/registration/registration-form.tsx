@@ -1,7 +1,7 @@
 import React, {useEffect, useState} from 'react';
 import {MaterialType} from '@/model/registration-enums';
 import {invoke} from '@tauri-apps/api/core';
-import {AllTransferProgress, TransferProgress} from '@/model/transfer-progress';
+import {AllTransferProgress, progressFraction, TransferProgress} from '@/model/transfer-progress';
 import {useTrokkFiles} from '@/context/trokk-files-context.tsx';
 import {SubmitHandler, useForm} from 'react-hook-form';
 import {RegistrationFormProps} from './registration-form-props.tsx';
@@ -21,6 +21,7 @@ import {Progress} from '@/components/ui/progress.tsx';
 import {CircleAlertIcon, InfoIcon, LoaderCircle} from 'lucide-react';
 import {Field, FieldLabel} from '@/components/ui/field.tsx';
 import {Alert, AlertDescription, AlertTitle} from '@/components/ui/alert';
+import {isUploadCancelled} from '@/model/upload-summary.ts';
 
 const RegistrationForm: React.FC = () => {
     const {state, dispatch} = useTrokkFiles();
@@ -28,7 +29,7 @@ const RegistrationForm: React.FC = () => {
     const {secrets} = useSecrets();
     const {uploadVersionBlocking, uploadVersionMessage, checkUploadVersionGate} = useVersion();
     const {checkedItems} = useSelection();
-    const {postRegistration} = usePostRegistration();
+    const {postRegistration, uploadJobId, cancelCurrentUpload} = usePostRegistration();
     const {errorMessage, handleError, successMessage, removeMessages} = useMessage();
     const {hasAnyRotating} = useRotation();
     const {authResponse, loggedOut, login, isLoggingIn} = useAuth();
@@ -36,6 +37,7 @@ const RegistrationForm: React.FC = () => {
     const appWindow = getCurrentWebviewWindow();
     const isAnyImageRotating = hasAnyRotating();
     const [disabled, setDisabled] = useState(false);
+    const [uploadCancelled, setUploadCancelled] = useState(false);
     const {
         register,
         handleSubmit,
@@ -77,6 +79,7 @@ const RegistrationForm: React.FC = () => {
 
     const onSubmit: SubmitHandler<RegistrationFormProps> = async (registration: RegistrationFormProps) => {
         removeMessages();
+        setUploadCancelled(false);
         if (checkedItems.length > 0) {
             const isVersionBlocking = await checkUploadVersionGate();
             if (isVersionBlocking) {
@@ -101,7 +104,9 @@ const RegistrationForm: React.FC = () => {
             } catch (error) {
                 console.error(error);
                 const errorMessage = getErrorMessage(error);
-                if (errorMessage === 'Not logged in') {
+                if (isUploadCancelled(error)) {
+                    setUploadCancelled(true);
+                } else if (errorMessage === 'Not logged in') {
                     handleError('Du må logge inn før du kan TRØKKE. Starter innlogging...');
                     if (!isLoggingIn) {
                         await login().catch((loginError) => {
@@ -155,6 +160,15 @@ const RegistrationForm: React.FC = () => {
         setBarWidthFromProgress(allUploadProgress);
     }, [allUploadProgress]);
 
+    const onCancelUpload = async () => {
+        try {
+            await cancelCurrentUpload();
+        } catch (error) {
+            console.error(error);
+            handleError('Kunne ikke avbryte opplasting.', undefined, getErrorMessage(error));
+        }
+    };
+
     const setBarWidthFromProgress = (progress: AllTransferProgress) => {
         const currentPath = state.current?.path;
         if (!currentPath) {
@@ -171,7 +185,7 @@ const RegistrationForm: React.FC = () => {
             setBarWidth(0);
             return;
         }
-        const width = (currentProgress.pageNr / currentProgress.totalPages) * 100;
+        const width = progressFraction(currentProgress) * 100;
         setBarWidth(width);
     };
 
@@ -258,6 +272,16 @@ const RegistrationForm: React.FC = () => {
                     )}
                 </Button>
             </div>
+            {uploadJobId && (
+                <Button
+                    type='button'
+                    variant='outline'
+                    onClick={onCancelUpload}
+                    className="w-full mt-2"
+                >
+                    Avbryt opplasting
+                </Button>
+            )}
             {isAnyImageRotating && (
                 <p className="text-primary text-sm mt-2">Venter på at bilderotasjon fullføres...</p>
             )}
@@ -283,6 +307,7 @@ const RegistrationForm: React.FC = () => {
                 </div>
             </div>
 
+            {uploadCancelled && <p className="text-muted-foreground mt-4">Opplastingen ble avbrutt.</p>}
             {successMessage && <p className="text-success mt-4">{successMessage}</p>}
         </form>
     );
@@ -1,18 +1,57 @@
 import {RegistrationFormProps} from './registration-form-props.tsx';
 import {invoke} from '@tauri-apps/api/core';
 import {getMaterialTypeAsKeyString} from '@/model/registration-enums.ts';
+import {BatchValidationReport, UploadSummary, UploadVerificationReport} from '@/model/upload-summary.ts';
+import {DuplicateGroup} from '@/model/page-analysis.ts';
 
 export async function uploadToS3(
     registration: RegistrationFormProps,
     batchMap: Map<string, {
         primary: string[],
         access: string[]
-    }>
-): Promise<number> {
+    }>,
+    jobId: string
+): Promise<UploadSummary> {
     const materialType = getMaterialTypeAsKeyString(registration.materialType);
 
     return await invoke('upload_batch_to_s3', {
         batchMap,
         materialType,
+        jobId,
     });
 }
+
+export async function cancelUpload(jobId: string): Promise<boolean> {
+    return await invoke('cancel_upload', {jobId});
+}
+
+export async function validateBatchUpload(
+    batchMap: Map<string, {
+        primary: string[],
+        access: string[]
+    }>
+): Promise<BatchValidationReport> {
+    return await invoke('validate_batch_upload', {batchMap});
+}
+
+export async function findDuplicatePagesInBatches(
+    batchMap: Map<string, {
+        primary: string[],
+        access: string[]
+    }>
+): Promise<DuplicateGroup[]> {
+    return await invoke('find_duplicate_pages_in_batches', {batchMap});
+}
+
+export async function verifyBatchUpload(
+    batchMap: Map<string, {
+        primary: string[],
+        access: string[]
+    }>,
+    registration: RegistrationFormProps,
+    uploadDates: UploadSummary['uploadDates']
+): Promise<UploadVerificationReport> {
+    const materialType = getMaterialTypeAsKeyString(registration.materialType);
+
+    return await invoke('verify_batch_upload', {batchMap, materialType, uploadDates});
+}
@@ -10,6 +10,49 @@ import {Input} from '@/components/ui/input.tsx';
 import {Slider} from '@/components/ui/slider.tsx';
 import {useMessage} from '@/context/message-context.tsx';
 import ErrorLogModal from '@/features/error-log/error-log-modal.tsx';
+import type {
+    ConversionProfile,
+    ConversionProfiles,
+    ConversionTarget,
+    KeyLayout,
+    ResizeFilter,
+    TargetSize,
+    UploadConcurrency,
+    UploadRetryPolicy,
+} from '@/tauri-store/setting-store.ts';
+
+const conversionTargets: [ConversionTarget, string][] = [
+    ['thumbnail', 'Miniatyrbilder'],
+    ['preview', 'Forhåndsvisninger'],
+];
+
+const resizeFilters: [ResizeFilter, string][] = [
+    ['nearest', 'Raskest'],
+    ['triangle', 'Lineær'],
+    ['catmullRom', 'Catmull-Rom'],
+    ['gaussian', 'Gauss'],
+    ['lanczos3', 'Skarpest'],
+];
+
+const sizeModes: [TargetSize['mode'], string][] = [
+    ['fraction', 'Andel av originalen'],
+    ['longEdge', 'Lengste kant'],
+    ['boundingBox', 'Innenfor ramme'],
+];
+
+// Starting point when switching to a size mode, in pixels that suit each derivative
+const initialTargetSizes: Record<ConversionTarget, Record<TargetSize['mode'], TargetSize>> = {
+    thumbnail: {
+        fraction: {mode: 'fraction', fraction: 8},
+        longEdge: {mode: 'longEdge', pixels: 400},
+        boundingBox: {mode: 'boundingBox', width: 400, height: 400},
+    },
+    preview: {
+        fraction: {mode: 'fraction', fraction: 4},
+        longEdge: {mode: 'longEdge', pixels: 1600},
+        boundingBox: {mode: 'boundingBox', width: 1600, height: 1600},
+    },
+};
 
 interface SettingsFormProps {
     setOpen: (open: boolean) => void;
@@ -24,32 +67,67 @@ const SettingsForm: React.FC<SettingsFormProps> = ({setOpen}) => {
         setTextSize,
         theme,
         setTheme,
-        thumbnailSizeFraction,
-        previewSizeFraction,
-        setThumbnailSizeFraction,
-        setPreviewSizeFraction
+        conversionProfiles,
+        setConversionProfile,
+        derivativeCacheEnabled,
+        setDerivativeCacheEnabled,
+        sharpnessThreshold,
+        setSharpnessThreshold,
+        uploadRetryPolicy,
+        setUploadRetryPolicy,
+        uploadConcurrency,
+        setUploadConcurrency,
+        keyLayout,
+        keyLayoutError,
+        setKeyLayout
     } = useSettings();
     const [scanPathError, setScanPathError] = useState<string | undefined>(undefined);
     const [scanPathSuccess, setScanPathSuccess] = useState<string | undefined>(undefined);
     const [deletePreviewsStatus, setDeletePreviewsStatus] = useState<string | undefined>(undefined);
     const [isDeleting, setIsDeleting] = useState<boolean>(false);
-    const [isSavingSizeFractions, setIsSavingSizeFractions] = useState<boolean>(false);
-    const [sizeFractionsStatus, setSizeFractionsStatus] = useState<string | undefined>(undefined);
+    const [isSavingConversionProfiles, setIsSavingConversionProfiles] = useState<boolean>(false);
+    const [conversionProfilesStatus, setConversionProfilesStatus] = useState<string | undefined>(undefined);
+    const [derivativeCacheStatus, setDerivativeCacheStatus] = useState<string | undefined>(undefined);
+    const [sharpnessThresholdStatus, setSharpnessThresholdStatus] = useState<string | undefined>(undefined);
+    const [uploadSettingsStatus, setUploadSettingsStatus] = useState<string | undefined>(undefined);
+    const [keyLayoutStatus, setKeyLayoutStatus] = useState<string | undefined>(undefined);
     const [isErrorLogOpen, setIsErrorLogOpen] = useState(false);
     const {errorLogEntries} = useMessage();
 
     const [scannerPathEdit, setScannerPathEdit] = useState<string>(scannerPath);
-    const [thumbnailSizeEdit, setThumbnailSizeEdit] = useState<number>(thumbnailSizeFraction);
-    const [previewSizeFractionEdit, setPreviewSizeEdit] = useState<number>(previewSizeFraction);
+    const [conversionProfilesEdit, setConversionProfilesEdit] = useState<ConversionProfiles>(conversionProfiles);
+    const [sharpnessThresholdEdit, setSharpnessThresholdEdit] = useState<number>(sharpnessThreshold);
+    const [uploadRetryPolicyEdit, setUploadRetryPolicyEdit] = useState<UploadRetryPolicy>(uploadRetryPolicy);
+    const [uploadConcurrencyEdit, setUploadConcurrencyEdit] = useState<UploadConcurrency>(uploadConcurrency);
+    const [keyLayoutEdit, setKeyLayoutEdit] = useState<KeyLayout>(keyLayout);
 
     useEffect(() => {
         setScannerPathEdit(scannerPath);
     }, [scannerPath]);
 
     useEffect(() => {
-        setThumbnailSizeEdit(thumbnailSizeFraction);
-        setPreviewSizeEdit(previewSizeFraction);
-    }, [thumbnailSizeFraction, previewSizeFraction]);
+        setConversionProfilesEdit(conversionProfiles);
+    }, [conversionProfiles]);
+
+    useEffect(() => {
+        setSharpnessThresholdEdit(sharpnessThreshold);
+    }, [sharpnessThreshold]);
+
+    useEffect(() => {
+        setUploadRetryPolicyEdit(uploadRetryPolicy);
+    }, [uploadRetryPolicy]);
+
+    useEffect(() => {
+        setUploadConcurrencyEdit(uploadConcurrency);
+    }, [uploadConcurrency]);
+
+    useEffect(() => {
+        setKeyLayoutEdit(keyLayout);
+    }, [keyLayout]);
+
+    useEffect(() => {
+        if (keyLayoutError) setKeyLayoutStatus(`Feil i lagret nøkkeloppsett: ${keyLayoutError}`);
+    }, [keyLayoutError]);
 
     const pickScannerPath = async () => {
         try {
@@ -104,45 +182,99 @@ const SettingsForm: React.FC<SettingsFormProps> = ({setOpen}) => {
         }
     };
 
-    const handleSaveImageSizeFractions = async () => {
-        const hasChanged = thumbnailSizeEdit !== thumbnailSizeFraction
-            || previewSizeFractionEdit !== previewSizeFraction;
+    const updateConversionProfileEdit = (target: ConversionTarget, changes: Partial<ConversionProfile>) => {
+        setConversionProfilesEdit((profiles) => ({...profiles, [target]: {...profiles[target], ...changes}}));
+    };
 
-        if (!hasChanged) {
-            setSizeFractionsStatus('Ingen endringer å lagre.');
-            setTimeout(() => setSizeFractionsStatus(undefined), 5000);
+    const handleSaveConversionProfiles = async () => {
+        const changedTargets = conversionTargets
+            .map(([target]) => target)
+            .filter((target) => JSON.stringify(conversionProfilesEdit[target]) !== JSON.stringify(conversionProfiles[target]));
+
+        if (changedTargets.length === 0) {
+            setConversionProfilesStatus('Ingen endringer å lagre.');
+            setTimeout(() => setConversionProfilesStatus(undefined), 5000);
             return;
         }
 
-        setIsSavingSizeFractions(true);
-        setSizeFractionsStatus(undefined);
+        setIsSavingConversionProfiles(true);
+        setConversionProfilesStatus(undefined);
 
         try {
-            setThumbnailSizeFraction(thumbnailSizeEdit);
-            setPreviewSizeFraction(previewSizeFractionEdit);
-            await invoke('set_image_size_fractions', {
-                thumbnailFraction: thumbnailSizeEdit,
-                previewFraction: previewSizeFractionEdit
-            });
+            for (const target of changedTargets) {
+                await setConversionProfile(target, conversionProfilesEdit[target]);
+            }
 
             if (!scannerPath) {
-                setSizeFractionsStatus('Lagret, men ingen skanner mappe valgt for sletting.');
+                setConversionProfilesStatus('Lagret, men ingen skanner mappe valgt for sletting.');
                 return;
             }
 
             const deletedCount = await invoke<number>('delete_all_previews_and_thumbnails', {
                 directoryPath: scannerPath
             });
-            setSizeFractionsStatus(
+            setConversionProfilesStatus(
                 `Lagret. Slettet ${deletedCount} forhåndsvisninger og miniatyrbilder.`
             );
             setDeletePreviewsStatus(undefined);
         } catch (error) {
-            console.error('Failed to save size fractions:', error);
-            setSizeFractionsStatus(`Feil ved lagring: ${error}`);
+            console.error('Failed to save conversion profiles:', error);
+            setConversionProfilesStatus(`Feil ved lagring: ${error}`);
         } finally {
-            setIsSavingSizeFractions(false);
-            setTimeout(() => setSizeFractionsStatus(undefined), 5000);
+            setIsSavingConversionProfiles(false);
+            setTimeout(() => setConversionProfilesStatus(undefined), 5000);
+        }
+    };
+
+    const handleDerivativeCacheChange = async (enabled: boolean) => {
+        if (enabled === derivativeCacheEnabled) return;
+        setDerivativeCacheStatus(undefined);
+        try {
+            await setDerivativeCacheEnabled(enabled);
+        } catch (error) {
+            console.error('Failed to change derivative cache:', error);
+            setDerivativeCacheStatus(`Feil: ${error}`);
+            setTimeout(() => setDerivativeCacheStatus(undefined), 5000);
+        }
+    };
+
+    const handleSaveSharpnessThreshold = async () => {
+        setSharpnessThresholdStatus(undefined);
+        try {
+            await setSharpnessThreshold(sharpnessThresholdEdit);
+            setSharpnessThresholdStatus('Lagret!');
+        } catch (error) {
+            console.error('Failed to save sharpness threshold:', error);
+            setSharpnessThresholdStatus(`Feil: ${error}`);
+        } finally {
+            setTimeout(() => setSharpnessThresholdStatus(undefined), 5000);
+        }
+    };
+
+    const handleSaveUploadSettings = async () => {
+        setUploadSettingsStatus(undefined);
+        try {
+            await setUploadRetryPolicy(uploadRetryPolicyEdit);
+            await setUploadConcurrency(uploadConcurrencyEdit);
+            setUploadSettingsStatus('Lagret!');
+        } catch (error) {
+            console.error('Failed to save upload settings:', error);
+            setUploadSettingsStatus(`Feil: ${error}`);
+        } finally {
+            setTimeout(() => setUploadSettingsStatus(undefined), 5000);
+        }
+    };
+
+    const handleSaveKeyLayout = async () => {
+        setKeyLayoutStatus(undefined);
+        try {
+            await setKeyLayout(keyLayoutEdit);
+            setKeyLayoutStatus('Lagret!');
+            setTimeout(() => setKeyLayoutStatus(undefined), 5000);
+        } catch (error) {
+            // Parse errors stay visible until the templates are corrected
+            console.error('Failed to save key layout:', error);
+            setKeyLayoutStatus(`Feil: ${error}`);
         }
     };
 
@@ -230,56 +362,361 @@ const SettingsForm: React.FC<SettingsFormProps> = ({setOpen}) => {
                 {scanPathSuccess && <p className="text-success ml-2">{scanPathSuccess}</p>}
             </div>
 
-            <label htmlFor="thumbnailSizeFraction" className="w-32">Bildekvalitet</label>
+            <label className="w-32">Bildekvalitet</label>
             <hr className='mb-2'/>
-            <div className="flex mb-2 items-center">
-                <label htmlFor="thumbnailSizeFraction" className="w-40">Miniatyrbilder</label>
-                <div className='flex flex-row gap-2 w-full px-6'>
-                    <span className='text-muted-foreground'>Minst</span>
-                    <Slider
-                        onValueChange={(value) => setThumbnailSizeEdit(Number(value))}
-                        min={1}
-                        max={16}
-                        step={1}
-                    />
-                    <span className='text-muted-foreground'>Størst</span>
+            {conversionTargets.map(([target, label]) => {
+                const profile = conversionProfilesEdit[target];
+                const size = profile.size;
+                return (
+                    <div key={target} className="mb-4">
+                        <div className="flex mb-2 items-center">
+                            <label className="w-40">{label}</label>
+                            <div className="ml-2 flex items-center gap-2">
+                                {sizeModes.map(([mode, modeLabel]) => (
+                                    <Button
+                                        key={mode}
+                                        type="button"
+                                        variant={size.mode === mode ? 'default' : 'outline'}
+                                        onClick={() => {
+                                            if (size.mode === mode) return;
+                                            updateConversionProfileEdit(target, {size: initialTargetSizes[target][mode]});
+                                        }}
+                                    >
+                                        {modeLabel}
+                                    </Button>
+                                ))}
+                            </div>
+                        </div>
+                        <div className="flex mb-2 items-center">
+                            <label htmlFor={`${target}Size`} className="w-40">Størrelse</label>
+                            {size.mode === 'fraction' && (
+                                <div className='flex flex-row gap-2 w-full px-6'>
+                                    <span className='text-muted-foreground'>Minst</span>
+                                    <Slider
+                                        id={`${target}Size`}
+                                        value={[size.fraction]}
+                                        onValueChange={([fraction]) => updateConversionProfileEdit(target, {size: {mode: 'fraction', fraction}})}
+                                        min={1}
+                                        max={16}
+                                        step={1}
+                                        inverted
+                                    />
+                                    <span className='text-muted-foreground'>Størst</span>
+                                </div>
+                            )}
+                            {size.mode === 'longEdge' && (
+                                <>
+                                    <Input
+                                        type="number"
+                                        id={`${target}Size`}
+                                        min={16}
+                                        max={16384}
+                                        value={size.pixels}
+                                        onChange={(e) => updateConversionProfileEdit(target, {size: {mode: 'longEdge', pixels: Number(e.target.value)}})}
+                                        className="ml-2 w-32"
+                                    />
+                                    <span className="ml-2 text-muted-foreground">px</span>
+                                </>
+                            )}
+                            {size.mode === 'boundingBox' && (
+                                <>
+                                    <Input
+                                        type="number"
+                                        id={`${target}Size`}
+                                        min={16}
+                                        max={16384}
+                                        value={size.width}
+                                        onChange={(e) => updateConversionProfileEdit(target, {size: {...size, width: Number(e.target.value)}})}
+                                        className="ml-2 w-32"
+                                    />
+                                    <span className="ml-2 text-muted-foreground">x</span>
+                                    <Input
+                                        type="number"
+                                        min={16}
+                                        max={16384}
+                                        value={size.height}
+                                        onChange={(e) => updateConversionProfileEdit(target, {size: {...size, height: Number(e.target.value)}})}
+                                        className="ml-2 w-32"
+                                    />
+                                    <span className="ml-2 text-muted-foreground">px</span>
+                                </>
+                            )}
+                        </div>
+                        <div className="flex mb-2 items-center">
+                            <label className="w-40">Skalering</label>
+                            <div className="ml-2 flex items-center gap-2">
+                                {resizeFilters.map(([filter, filterLabel]) => (
+                                    <Button
+                                        key={filter}
+                                        type="button"
+                                        variant={profile.filter === filter ? 'default' : 'outline'}
+                                        onClick={() => updateConversionProfileEdit(target, {filter})}
+                                    >
+                                        {filterLabel}
+                                    </Button>
+                                ))}
+                            </div>
+                        </div>
+                        <div className="flex mb-2 items-center">
+                            <label htmlFor={`${target}Quality`} className="w-40">Kvalitet</label>
+                            <Input
+                                type="number"
+                                id={`${target}Quality`}
+                                min={0}
+                                max={100}
+                                value={profile.quality}
+                                disabled={profile.lossless}
+                                onChange={(e) => updateConversionProfileEdit(target, {quality: Number(e.target.value)})}
+                                className="ml-2 w-32"
+                            />
+                            <div className="ml-2 flex items-center gap-2">
+                                <Button
+                                    type="button"
+                                    variant={profile.lossless ? 'outline' : 'default'}
+                                    onClick={() => updateConversionProfileEdit(target, {lossless: false})}
+                                >
+                                    Komprimert
+                                </Button>
+                                <Button
+                                    type="button"
+                                    variant={profile.lossless ? 'default' : 'outline'}
+                                    onClick={() => updateConversionProfileEdit(target, {lossless: true})}
+                                >
+                                    Tapsfri
+                                </Button>
+                            </div>
+                        </div>
+                    </div>
+                );
+            })}
+
+            <div className="flex mb-2 ml-40">
+                <Button
+                    type="button"
+                    variant="secondary"
+                    onClick={handleSaveConversionProfiles}
+                    disabled={isSavingConversionProfiles}
+                    className="w-40"
+                >
+                    {isSavingConversionProfiles ? 'Lagrer...' : 'Lagre bildekvalitet'}
+                </Button>
+
+                {conversionProfilesStatus && (
+                    <p className={`ml-2 ${conversionProfilesStatus.startsWith('Feil') ? 'text-destructive' : 'text-success'}`}>
+                        {conversionProfilesStatus}
+                    </p>
+                )}
+            </div>
+
+            <span className="text-xs ml-40 text-muted-foreground">
+                    Ved endring av bildekvaliteten slettes alle eksisterende forhåndsvisninger og miniatyrbilder automatisk.
+                    Med en størrelse i piksler blir bildene like store uansett skanner.
+            </span>
+
+            <div className="flex mt-7 items-center">
+                <label className="w-40">Lagre miniatyrbilder</label>
+                <div className="ml-2 flex items-center gap-2">
+                    <Button
+                        type="button"
+                        variant={derivativeCacheEnabled ? 'outline' : 'default'}
+                        onClick={() => handleDerivativeCacheChange(false)}
+                    >
+                        I skannermappen
+                    </Button>
+                    <Button
+                        type="button"
+                        variant={derivativeCacheEnabled ? 'default' : 'outline'}
+                        onClick={() => handleDerivativeCacheChange(true)}
+                    >
+                        I app-mappen
+                    </Button>
                 </div>
+                {derivativeCacheStatus && <p className="text-destructive ml-2">{derivativeCacheStatus}</p>}
             </div>
+            <span className="text-xs ml-40 mt-2 text-muted-foreground">
+                I app-mappen holdes skannermappen fri for .thumbnails og .previews, og de eldste slettes når mappen blir full.
+            </span>
 
+            <label className="w-32 mt-7">Sidekontroll</label>
+            <hr className='mb-2'/>
             <div className="flex mb-2 items-center">
-                <label htmlFor="previewSizeFraction" className="w-40">Forhåndsvisninger</label>
-                <div className='flex flex-row gap-2 w-full px-6'>
-                    <span className='text-muted-foreground'>Minst</span>
-                    <Slider
-                        onValueChange={(value) => setThumbnailSizeEdit(Number(value))}
-                        min={1}
-                        max={16}
-                        step={1}
-                    />
-                    <span className='text-muted-foreground'>Størst</span>
-                </div>
+                <label htmlFor="sharpnessThreshold" className="w-40">Grense for uskarphet</label>
+                <Input
+                    type="number"
+                    id="sharpnessThreshold"
+                    min={0}
+                    step={10}
+                    value={sharpnessThresholdEdit}
+                    onChange={(e) => setSharpnessThresholdEdit(Number(e.target.value))}
+                    className="ml-2 w-32"
+                />
+                <Button
+                    type="button"
+                    variant="secondary"
+                    onClick={handleSaveSharpnessThreshold}
+                    className="ml-2"
+                >
+                    Lagre
+                </Button>
+                {sharpnessThresholdStatus && (
+                    <p className={`ml-2 ${sharpnessThresholdStatus.startsWith('Feil') ? 'text-destructive' : 'text-success'}`}>
+                        {sharpnessThresholdStatus}
+                    </p>
+                )}
             </div>
+            <span className="text-xs ml-40 text-muted-foreground">
+                Sider med innhold og skarphet under grensen merkes som uskarpe. Gjelder fra neste gang en mappe åpnes.
+            </span>
 
+            <label className="w-32 mt-7">Opplasting</label>
+            <hr className='mb-2'/>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="uploadConcurrentObjects" className="w-40">Filer samtidig</label>
+                <Input
+                    type="number"
+                    id="uploadConcurrentObjects"
+                    min={1}
+                    max={16}
+                    value={uploadConcurrencyEdit.maxConcurrentObjects}
+                    onChange={(e) => setUploadConcurrencyEdit({...uploadConcurrencyEdit, maxConcurrentObjects: Number(e.target.value)})}
+                    className="ml-2 w-32"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="uploadConcurrentParts" className="w-40">Deler samtidig per fil</label>
+                <Input
+                    type="number"
+                    id="uploadConcurrentParts"
+                    min={1}
+                    max={16}
+                    value={uploadConcurrencyEdit.maxConcurrentParts}
+                    onChange={(e) => setUploadConcurrencyEdit({...uploadConcurrencyEdit, maxConcurrentParts: Number(e.target.value)})}
+                    className="ml-2 w-32"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="uploadMaxAttempts" className="w-40">Antall forsøk</label>
+                <Input
+                    type="number"
+                    id="uploadMaxAttempts"
+                    min={1}
+                    max={10}
+                    value={uploadRetryPolicyEdit.maxAttempts}
+                    onChange={(e) => setUploadRetryPolicyEdit({...uploadRetryPolicyEdit, maxAttempts: Number(e.target.value)})}
+                    className="ml-2 w-32"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="uploadInitialBackoff" className="w-40">Første ventetid (ms)</label>
+                <Input
+                    type="number"
+                    id="uploadInitialBackoff"
+                    min={0}
+                    value={uploadRetryPolicyEdit.initialBackoffMs}
+                    onChange={(e) => setUploadRetryPolicyEdit({...uploadRetryPolicyEdit, initialBackoffMs: Number(e.target.value)})}
+                    className="ml-2 w-32"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="uploadMaxBackoff" className="w-40">Lengste ventetid (ms)</label>
+                <Input
+                    type="number"
+                    id="uploadMaxBackoff"
+                    min={0}
+                    max={300000}
+                    value={uploadRetryPolicyEdit.maxBackoffMs}
+                    onChange={(e) => setUploadRetryPolicyEdit({...uploadRetryPolicyEdit, maxBackoffMs: Number(e.target.value)})}
+                    className="ml-2 w-32"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label className="w-40">Spre ventetiden</label>
+                <div className="ml-2 flex items-center gap-2">
+                    <Button
+                        type="button"
+                        variant={uploadRetryPolicyEdit.jitter ? 'default' : 'outline'}
+                        onClick={() => setUploadRetryPolicyEdit({...uploadRetryPolicyEdit, jitter: true})}
+                    >
+                        På
+                    </Button>
+                    <Button
+                        type="button"
+                        variant={uploadRetryPolicyEdit.jitter ? 'outline' : 'default'}
+                        onClick={() => setUploadRetryPolicyEdit({...uploadRetryPolicyEdit, jitter: false})}
+                    >
+                        Av
+                    </Button>
+                </div>
+            </div>
             <div className="flex mb-2 ml-40">
                 <Button
                     type="button"
                     variant="secondary"
-                    onClick={handleSaveImageSizeFractions}
-                    disabled={isSavingSizeFractions}
+                    onClick={handleSaveUploadSettings}
                     className="w-40"
                 >
-                    {isSavingSizeFractions ? 'Lagrer...' : 'Lagre bildekvalitet'}
+                    Lagre opplasting
                 </Button>
-
-                {sizeFractionsStatus && (
-                    <p className={`ml-2 ${sizeFractionsStatus.startsWith('Feil') ? 'text-destructive' : 'text-success'}`}>
-                        {sizeFractionsStatus}
+                {uploadSettingsStatus && (
+                    <p className={`ml-2 ${uploadSettingsStatus.startsWith('Feil') ? 'text-destructive' : 'text-success'}`}>
+                        {uploadSettingsStatus}
                     </p>
                 )}
             </div>
+            <span className="text-xs ml-40 text-muted-foreground">
+                Flere filer og deler samtidig gir raskere opplasting på god linje, men bruker mer minne.
+                Filer og deler som feiler under opplasting prøves på nytt med økende ventetid mellom forsøkene.
+            </span>
 
+            <label className="w-32 mt-7">Nøkler i lagringen</label>
+            <hr className='mb-2'/>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="objectIdTemplate" className="w-40">Objekt-ID</label>
+                <Input
+                    type="text"
+                    id="objectIdTemplate"
+                    value={keyLayoutEdit.objectIdTemplate}
+                    onChange={(e) => setKeyLayoutEdit({...keyLayoutEdit, objectIdTemplate: e.target.value})}
+                    className="ml-2 w-[36rem]"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="batchKeyTemplate" className="w-40">Sider i batch</label>
+                <Input
+                    type="text"
+                    id="batchKeyTemplate"
+                    value={keyLayoutEdit.batchKeyTemplate}
+                    onChange={(e) => setKeyLayoutEdit({...keyLayoutEdit, batchKeyTemplate: e.target.value})}
+                    className="ml-2 w-[36rem]"
+                />
+            </div>
+            <div className="flex mb-2 items-center">
+                <label htmlFor="directoryKeyTemplate" className="w-40">Sider i mappe</label>
+                <Input
+                    type="text"
+                    id="directoryKeyTemplate"
+                    value={keyLayoutEdit.directoryKeyTemplate}
+                    onChange={(e) => setKeyLayoutEdit({...keyLayoutEdit, directoryKeyTemplate: e.target.value})}
+                    className="ml-2 w-[36rem]"
+                />
+            </div>
+            <div className="flex mb-2 ml-40">
+                <Button
+                    type="button"
+                    variant="secondary"
+                    onClick={handleSaveKeyLayout}
+                    className="w-40"
+                >
+                    Lagre nøkler
+                </Button>
+                {keyLayoutStatus && (
+                    <p className={`ml-2 ${keyLayoutStatus.startsWith('Feil') ? 'text-destructive' : 'text-success'}`}>
+                        {keyLayoutStatus}
+                    </p>
+                )}
+            </div>
             <span className="text-xs ml-40 text-muted-foreground">
-                    Ved endring av disse størrelsene slettes alle eksisterende forhåndsvisninger og miniatyrbilder automatisk.
+                Tilgjengelige felt: {'{batch_id}'}, {'{object_id}'}, {'{representation}'}, {'{page:05}'}, {'{extension}'}, {'{material_type}'} og {'{date}'}.
             </span>
 
             <div className="flex mb-2 mt-10 items-center gap-2">
@@ -1,7 +1,7 @@
 import {
     formatFileNames,
     getFileExtension,
-    getThumbnailFromTree,
+    getThumbnailPathFromTree,
     getThumbnailURIFromTree,
     supportedFileTypes
 } from '@/util/file-utils.ts';
@@ -14,6 +14,7 @@ import StatusOverlay from '@/components/ui/rotation-status-overlay.tsx';
 import React, {forwardRef} from 'react';
 import DeleteFile from '@/features/delete-file/delete-file.tsx';
 import {Button} from '@/components/ui/button.tsx';
+import {Blankness} from '@/model/page-analysis.ts';
 
 export interface ThumbnailProps {
     fileTree: FileTree;
@@ -23,10 +24,18 @@ export interface ThumbnailProps {
     isDisabled: boolean;
     setDelFilePath: (path: string | null) => void;
     delFilePath: string | null;
+    blankness?: Blankness;
+    isBlurry?: boolean;
+    isDuplicate?: boolean;
 }
 
+const blanknessLabels: Partial<Record<Blankness, string>> = {
+    blank: 'Tom side',
+    nearlyBlank: 'Nesten tom',
+};
+
 const Thumbnail = forwardRef<HTMLDivElement, ThumbnailProps>(
-    ({ fileTree, onDoubleClick, isChecked, isFocused, isDisabled, setDelFilePath, delFilePath }, ref) => {
+    ({ fileTree, onDoubleClick, isChecked, isFocused, isDisabled, setDelFilePath, delFilePath, blankness, isBlurry, isDuplicate }, ref) => {
         const {state} = useTrokkFiles();
         const {rotateImage, getImageStatus, getFileCacheBuster} = useRotation();
 
@@ -34,8 +43,7 @@ const Thumbnail = forwardRef<HTMLDivElement, ThumbnailProps>(
         const imageIsRotating = imageStatus === 'rotating';
 
         // Get thumbnail file for cache busting
-        const thumbnailFile = getThumbnailFromTree(fileTree, state);
-        const thumbnailPath = thumbnailFile?.path || fileTree.path;
+        const thumbnailPath = getThumbnailPathFromTree(fileTree, state) || fileTree.path;
         const thumbnailCacheBuster = getFileCacheBuster(thumbnailPath);
 
         const truncateMiddle = (str: string, frontLen: number, backLen: number) => {
@@ -58,6 +66,9 @@ const Thumbnail = forwardRef<HTMLDivElement, ThumbnailProps>(
         const thumbnailUrl = getThumbnailURIFromTree(fileTree, state);
         const hasWebpThumbnail = !!thumbnailUrl;
         const isHiddenDir = fileTree.name === '.thumbnails' || fileTree.name === '.previews';
+        const qualityLabel = (blankness ? blanknessLabels[blankness] : undefined)
+            ?? (isDuplicate ? 'Mulig duplikat' : undefined)
+            ?? (isBlurry ? 'Uskarp' : undefined);
         const rotateBtnClass = `${imageIsRotating ? 'opacity-50 cursor-not-allowed' : ''}`
 
         if (isHiddenDir) return null;
@@ -132,6 +143,11 @@ const Thumbnail = forwardRef<HTMLDivElement, ThumbnailProps>(
                 <div className="relative w-full">
                     {content}
                     <StatusOverlay status={imageStatus} size="small" />
+                    {qualityLabel && (
+                        <span className="absolute top-3 left-3 z-20 rounded-md bg-card/90 px-2 py-0.5 text-xs font-semibold text-muted-foreground">
+                            {qualityLabel}
+                        </span>
+                    )}
                     {((isSupported || hasWebpThumbnail) && !isDisabled) && (
                         <div
                             onClick={(e) => e.stopPropagation()}
@@ -0,0 +1,46 @@
+import {useEffect, useState} from 'react';
+import {invoke} from '@tauri-apps/api/core';
+import {DuplicateGroup, PageBlankness, PageSharpness} from '@/model/page-analysis.ts';
+
+export interface PageQuality {
+    blankPages: Map<string, PageBlankness>;
+    sharpness: Map<string, PageSharpness>;
+    duplicates: Map<string, DuplicateGroup>;
+}
+
+const emptyQuality = (): PageQuality => ({blankPages: new Map(), sharpness: new Map(), duplicates: new Map()});
+
+/**
+ * Analyses the images in the directory for blank, nearly blank, blurry and duplicate pages, again
+ * whenever the number of images changes. Returns the results per image path.
+ */
+export function usePageQuality(directoryPath: string | undefined, imageCount: number) {
+    const [quality, setQuality] = useState<PageQuality>(emptyQuality);
+
+    useEffect(() => {
+        if (!directoryPath || imageCount === 0) {
+            setQuality(emptyQuality());
+            return;
+        }
+        let cancelled = false;
+        Promise.all([
+            invoke<PageBlankness[]>('analyze_blank_pages', {directoryPath}),
+            invoke<PageSharpness[]>('analyze_sharpness', {directoryPath}),
+            invoke<DuplicateGroup[]>('find_duplicate_pages', {directoryPath}),
+        ])
+            .then(([blankPages, sharpness, duplicates]) => {
+                if (cancelled) return;
+                setQuality({
+                    blankPages: new Map(blankPages.map((page) => [page.path, page] as const)),
+                    sharpness: new Map(sharpness.map((page) => [page.path, page] as const)),
+                    duplicates: new Map(duplicates.flatMap((group) => group.pages.map((page) => [page, group] as const))),
+                });
+            })
+            .catch((error) => console.error('Failed to analyse page quality', error));
+        return () => {
+            cancelled = true;
+        };
+    }, [directoryPath, imageCount]);
+
+    return quality;
+}
@@ -1,9 +1,16 @@
 import {getMaterialTypeAsKeyString, MaterialType, PublicationType} from './registration-enums.ts';
 import {getVersion} from '@tauri-apps/api/app';
 
+interface PageChecksumDto {
+    key: string;
+    sha256: string;
+}
+
 interface ItemPagesDto {
     itemId: string;
     pages: number;
+    /** SHA-256 of every file uploaded for the item, as verified by S3 */
+    checksums: PageChecksumDto[];
 }
 
 export class BatchTextInputDto {
@@ -0,0 +1,20 @@
+export type Blankness = 'blank' | 'nearlyBlank' | 'content';
+
+export interface PageBlankness {
+    path: string;
+    inkCoverage: number;
+    luminanceStdDev: number;
+    blankness: Blankness;
+}
+
+export interface PageSharpness {
+    path: string;
+    sharpness: number;
+    blurry: boolean;
+}
+
+export interface DuplicateGroup {
+    batchId: string | null;
+    pages: string[];
+    similarity: number;
+}
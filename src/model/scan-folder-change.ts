@@ -0,0 +1,9 @@
+export const scanFolderEventKinds = ['file_added', 'file_changed', 'file_removed', 'folder_added'] as const;
+
+export type ScanFolderEventKind = typeof scanFolderEventKinds[number];
+
+export interface ScanFolderChange {
+    root: string;
+    path: string;
+    thumbnail: string | null;
+}
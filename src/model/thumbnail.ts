@@ -1,4 +1,34 @@
+export type ConversionOutcome = 'converted' | 'alreadyConverted' | 'failed';
+
+export interface FileConversionResult {
+    path: string;
+    outcome: ConversionOutcome;
+    errorKind: 'image' | 'io' | 'webpEncoding' | 'unfinishedFile' | 'other' | null;
+    errorMessage: string | null;
+}
+
 export interface ConversionResult {
     converted: number;
     alreadyConverted: number;
-}
\ No newline at end of file
+    failed: number;
+    files: FileConversionResult[];
+}
+
+export interface ConversionProgress {
+    directory: string;
+    currentFile: string;
+    done: number;
+    total: number;
+    failures: number;
+}
+
+export interface DerivativeReady {
+    source: string;
+    path: string;
+    highRes: boolean;
+}
+
+export type TargetSize =
+    | { mode: 'fraction'; fraction: number }
+    | { mode: 'longEdge'; pixels: number }
+    | { mode: 'boundingBox'; width: number; height: number };
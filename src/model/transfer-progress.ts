@@ -6,8 +6,20 @@ export interface TransferProgress {
     directory: string;
     pageNr: number;
     totalPages: number;
+    bytesSent: number;
+    totalBytes: number;
+    currentFile: string;
+    currentPart: number | null;
+    bytesPerSecond: number;
 }
 
+export const progressFraction = (progress: TransferProgress): number => {
+    if (progress.totalBytes > 0) {
+        return progress.bytesSent / progress.totalBytes;
+    }
+    return progress.pageNr / progress.totalPages;
+};
+
 export const calculateProgress = (progress: TransferProgress): string => {
-    return ((progress.pageNr / progress.totalPages) * 100).toFixed(0) + '%';
+    return (progressFraction(progress) * 100).toFixed(0) + '%';
 };
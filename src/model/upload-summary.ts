@@ -0,0 +1,63 @@
+export interface UploadedObject {
+    key: string;
+    path: string;
+    size: number;
+    sha256: string;
+}
+
+export interface UploadSummary {
+    uploadedCount: number;
+    objects: UploadedObject[];
+    /** Date (YYYY-MM-DD) each object was first uploaded, by object id. Needed to verify the upload. */
+    uploadDates: Record<string, string>;
+}
+
+export type BatchProblemKind =
+    | 'emptyBatchMap'
+    | 'emptyBatch'
+    | 'missingFile'
+    | 'notAFile'
+    | 'unreadable'
+    | 'unsupportedExtension'
+    | 'duplicateFile'
+    | 'representationCountMismatch'
+    | 'unfinishedFile'
+    | 'tooLarge';
+
+export interface BatchProblem {
+    kind: BatchProblemKind;
+    batchId: string | null;
+    path: string | null;
+    message: string;
+}
+
+export interface BatchValidationReport {
+    valid: boolean;
+    fileCount: number;
+    totalBytes: number;
+    problems: BatchProblem[];
+}
+
+export interface UploadError {
+    kind: 'cancelled' | 'failed' | 'invalid';
+    message: string;
+    report?: BatchValidationReport;
+}
+
+export function isUploadCancelled(error: unknown): boolean {
+    return typeof error === 'object' && error !== null && (error as UploadError).kind === 'cancelled';
+}
+
+export interface ObjectDiscrepancy {
+    key: string;
+    path: string | null;
+    reason: string;
+}
+
+export interface UploadVerificationReport {
+    complete: boolean;
+    verifiedCount: number;
+    missing: ObjectDiscrepancy[];
+    extra: ObjectDiscrepancy[];
+    mismatched: ObjectDiscrepancy[];
+}
@@ -11,6 +11,68 @@ import {
 
 export type Theme = 'dark' | 'light' | 'system';
 
+export interface UploadRetryPolicy {
+    maxAttempts: number;
+    initialBackoffMs: number;
+    maxBackoffMs: number;
+    jitter: boolean;
+}
+
+export const defaultUploadRetryPolicy: UploadRetryPolicy = {
+    maxAttempts: 5,
+    initialBackoffMs: 500,
+    maxBackoffMs: 30000,
+    jitter: true,
+};
+
+export interface UploadConcurrency {
+    maxConcurrentObjects: number;
+    maxConcurrentParts: number;
+}
+
+export const defaultUploadConcurrency: UploadConcurrency = {
+    maxConcurrentObjects: 4,
+    maxConcurrentParts: 2,
+};
+
+export interface KeyLayout {
+    objectIdTemplate: string;
+    batchKeyTemplate: string;
+    directoryKeyTemplate: string;
+}
+
+export const defaultKeyLayout: KeyLayout = {
+    objectIdTemplate: 'tekst_{batch_id}',
+    batchKeyTemplate: '{object_id}/representations/{representation}/data/{object_id}_{page:05}.{extension}',
+    directoryKeyTemplate: '{object_id}/{object_id}_{page:05}.{extension}',
+};
+
+export type ResizeFilter = 'nearest' | 'triangle' | 'catmullRom' | 'gaussian' | 'lanczos3';
+
+export type TargetSize =
+    | {mode: 'fraction'; fraction: number}
+    | {mode: 'longEdge'; pixels: number}
+    | {mode: 'boundingBox'; width: number; height: number};
+
+export type ConversionTarget = 'thumbnail' | 'preview';
+
+export interface ConversionProfile {
+    filter: ResizeFilter;
+    quality: number;
+    lossless: boolean;
+    size: TargetSize;
+}
+
+export type ConversionProfiles = Record<ConversionTarget, ConversionProfile>;
+
+export const defaultConversionProfiles: ConversionProfiles = {
+    thumbnail: {filter: 'nearest', quality: 25, lossless: false, size: {mode: 'fraction', fraction: 8}},
+    preview: {filter: 'catmullRom', quality: 75, lossless: false, size: {mode: 'fraction', fraction: 4}},
+};
+
+// Mirrors the backend, where printed text in focus is usually well above it
+const defaultSharpnessThreshold = 100;
+
 const defaultScannerPath = await documentDir() + sep() + 'trokk' + sep() + 'files';
 const defaultThumbnailSizeFraction = 8;
 const defaultPreviewSizeFraction = 4;
@@ -222,6 +284,151 @@ class SettingStore {
         }
     }
 
+    async getDerivativeCacheEnabled(): Promise<boolean> {
+        await this.ensureStore();
+        const enabled = await this.store!.get<boolean>('derivativeCacheEnabled')
+            .catch(error => {
+                console.error('Error getting derivative cache setting:', error);
+                return false;
+            });
+        return enabled ?? false;
+    }
+
+    async setDerivativeCacheEnabled(enabled: boolean): Promise<void> {
+        await this.ensureStore();
+        try {
+            await this.store!.set('derivativeCacheEnabled', enabled).then(async () => {
+                await this.store!.save();
+            }).catch(error => {
+                console.error('Error setting derivative cache setting:', error);
+            });
+        } catch (error) {
+            console.error('Error setting derivative cache setting:', error);
+        }
+    }
+
+    async getConversionProfile(target: ConversionTarget): Promise<ConversionProfile> {
+        await this.ensureStore();
+        const profile = await this.store!.get<ConversionProfile>(`${target}ConversionProfile`)
+            .catch(error => {
+                console.error(`Error getting ${target} conversion profile:`, error);
+                return undefined;
+            });
+        if (profile == undefined) {
+            // Carry over the size fraction stored before profiles were
+            const fraction = target === 'thumbnail'
+                ? await this.getThumbnailSizeFraction()
+                : await this.getPreviewSizeFraction();
+            return {...defaultConversionProfiles[target], size: {mode: 'fraction', fraction}};
+        }
+        return {...defaultConversionProfiles[target], ...profile};
+    }
+
+    async setConversionProfile(target: ConversionTarget, profile: ConversionProfile): Promise<void> {
+        await this.ensureStore();
+        try {
+            await this.store!.set(`${target}ConversionProfile`, profile).then(async () => {
+                await this.store!.save();
+            }).catch(error => {
+                console.error(`Error setting ${target} conversion profile:`, error);
+            });
+        } catch (error) {
+            console.error(`Error setting ${target} conversion profile:`, error);
+        }
+    }
+
+    async getUploadRetryPolicy(): Promise<UploadRetryPolicy> {
+        await this.ensureStore();
+        const policy = await this.store!.get<UploadRetryPolicy>('uploadRetryPolicy')
+            .catch(error => {
+                console.error('Error getting upload retry policy:', error);
+                return defaultUploadRetryPolicy;
+            });
+        return {...defaultUploadRetryPolicy, ...policy};
+    }
+
+    async setUploadRetryPolicy(policy: UploadRetryPolicy): Promise<void> {
+        await this.ensureStore();
+        try {
+            await this.store!.set('uploadRetryPolicy', policy).then(async () => {
+                await this.store!.save();
+            }).catch(error => {
+                console.error('Error setting upload retry policy:', error);
+            });
+        } catch (error) {
+            console.error('Error setting upload retry policy:', error);
+        }
+    }
+
+    async getSharpnessThreshold(): Promise<number> {
+        await this.ensureStore();
+        const threshold = await this.store!.get<number>('sharpnessThreshold')
+            .catch(error => {
+                console.error('Error getting sharpness threshold:', error);
+                return defaultSharpnessThreshold;
+            });
+        return threshold ?? defaultSharpnessThreshold;
+    }
+
+    async setSharpnessThreshold(threshold: number): Promise<void> {
+        await this.ensureStore();
+        try {
+            await this.store!.set('sharpnessThreshold', threshold).then(async () => {
+                await this.store!.save();
+            }).catch(error => {
+                console.error('Error setting sharpness threshold:', error);
+            });
+        } catch (error) {
+            console.error('Error setting sharpness threshold:', error);
+        }
+    }
+
+    async getUploadConcurrency(): Promise<UploadConcurrency> {
+        await this.ensureStore();
+        const concurrency = await this.store!.get<UploadConcurrency>('uploadConcurrency')
+            .catch(error => {
+                console.error('Error getting upload concurrency:', error);
+                return defaultUploadConcurrency;
+            });
+        return {...defaultUploadConcurrency, ...concurrency};
+    }
+
+    async setUploadConcurrency(concurrency: UploadConcurrency): Promise<void> {
+        await this.ensureStore();
+        try {
+            await this.store!.set('uploadConcurrency', concurrency).then(async () => {
+                await this.store!.save();
+            }).catch(error => {
+                console.error('Error setting upload concurrency:', error);
+            });
+        } catch (error) {
+            console.error('Error setting upload concurrency:', error);
+        }
+    }
+
+    async getKeyLayout(): Promise<KeyLayout> {
+        await this.ensureStore();
+        const layout = await this.store!.get<KeyLayout>('keyLayout')
+            .catch(error => {
+                console.error('Error getting key layout:', error);
+                return defaultKeyLayout;
+            });
+        return {...defaultKeyLayout, ...layout};
+    }
+
+    async setKeyLayout(layout: KeyLayout): Promise<void> {
+        await this.ensureStore();
+        try {
+            await this.store!.set('keyLayout', layout).then(async () => {
+                await this.store!.save();
+            }).catch(error => {
+                console.error('Error setting key layout:', error);
+            });
+        } catch (error) {
+            console.error('Error setting key layout:', error);
+        }
+    }
+
     async getWorkspacePaneSizes(): Promise<WorkspacePaneSizes> {
         await this.ensureStore();
         const sizes = await this.store!.get<number[]>('workspacePaneSizes')
@@ -13,7 +13,7 @@ export const formatFileNames = (fileName?: string): string => {
     return fileName
 }
 
-const HIDDEN_SUPPORT_FOLDERS = ['.thumbnails', '.previews'];
+const HIDDEN_SUPPORT_FOLDERS = ['.thumbnails', '.previews', '.orientation'];
 
 const isHiddenSupportEntry = (fileTree: FileTree): boolean =>
     HIDDEN_SUPPORT_FOLDERS.some((prefix) => fileTree.name.startsWith(prefix));
@@ -81,43 +81,21 @@ export const getBreadcrumbSegments = (basePath: string, currentPath?: string): s
     return path.split(sep()).filter(Boolean);
 };
 
-/*  Thumbnail directories are generated where '.tif' files are located.
- *  Used to find corresponding '.webp' thumbnail for a tif, for example:
- *
- *  folder
- *  ├── image_1.tif
- *  ├── image_2.tif
- *  ├── image_3.tif
- *  └── .thumbnails
- *      ├── image_1.webp
- *      ├── image_2.webp
- *      └── image_3.webp
+/*  Thumbnails are written next to the scans in '.thumbnails', or in the app's cache directory
+ *  when the derivative cache is enabled. The backend resolves where each one is, and the
+ *  resolved paths are kept in 'state.thumbnails' by the image path they belong to.
  */
-export function getThumbnailFromTree(tree: FileTree, state: TrokkFilesState): FileTree | undefined {
-    const thumbnailPath = tree.path.substring(
-        0,
-        tree.path.length - tree.name.length
-    ) + '.thumbnails' + sep() + tree.name.split('.')[0] + '.webp';
-    return state.treeIndex.get(thumbnailPath);
-}
-
+export const getThumbnailPathFromTree = (tree: FileTree, state: TrokkFilesState): string | undefined =>
+    state.thumbnails.get(tree.path);
 
 export const getThumbnailExtensionFromTree = (tree: FileTree, state: TrokkFilesState) => {
-    const foundThumbnail = getThumbnailFromTree(tree, state);
-    return foundThumbnail ? getFileExtension(foundThumbnail.name) : undefined;
+    const thumbnailPath = getThumbnailPathFromTree(tree, state);
+    return thumbnailPath ? getFileExtension(thumbnailPath) : undefined;
 };
 
 export const getThumbnailURIFromTree = (tree: FileTree, state: TrokkFilesState) => {
-    const foundThumbnail = getThumbnailFromTree(tree, state);
-    return foundThumbnail ? convertFileSrc(foundThumbnail.path) : undefined;
-};
-
-export const getPreviewFromTree = (tree: FileTree, state: TrokkFilesState): FileTree | undefined => {
-    const previewPath = tree.path.substring(
-        0,
-        tree.path.length - tree.name.length
-    ) + '.previews' + sep() + tree.name.split('.')[0] + '.webp';
-    return state.treeIndex.get(previewPath);
+    const thumbnailPath = getThumbnailPathFromTree(tree, state);
+    return thumbnailPath ? convertFileSrc(thumbnailPath) : undefined;
 };
 
 export const isImage = (path: string): boolean => {
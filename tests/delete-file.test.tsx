@@ -7,10 +7,12 @@ import {TransferLogProvider} from '../src/context/transfer-log-context';
 import ErrorModal from '../src/features/error-log/error-modal';
 import type {StoredError} from '../src/model/error-log-entry';
 import {remove} from '@tauri-apps/plugin-fs';
+import {invoke} from '@tauri-apps/api/core';
 
 const testFileName = '/some/parent/merge/file1.tif';
 
 const mockRemove = vi.mocked(remove);
+const mockInvoke = vi.mocked(invoke);
 const mockGetErrorLogEntries = vi.fn<() => Promise<StoredError[]>>();
 const mockSetErrorLogEntries = vi.fn<(entries: StoredError[]) => Promise<void>>();
 const mockCaptureException = vi.fn();
@@ -21,6 +23,10 @@ vi.mock('@tauri-apps/plugin-fs', () => ({
     remove: vi.fn().mockResolvedValue(undefined),
 }));
 
+vi.mock('@tauri-apps/api/core', () => ({
+    invoke: vi.fn().mockResolvedValue(0),
+}));
+
 vi.mock('../src/context/selection-context.tsx', () => ({
     useSelection: () => ({
         columns: 3,
@@ -128,6 +134,7 @@ describe('DeleteFile', () => {
         mockGetErrorLogEntries.mockResolvedValue([]);
         mockSetErrorLogEntries.mockResolvedValue();
         mockRemove.mockResolvedValue(undefined);
+        mockInvoke.mockResolvedValue(0);
     });
 
     it('renders dialog trigger', () => {
@@ -177,8 +184,10 @@ describe('DeleteFile', () => {
 
         await waitFor(() => {
             expect(mockRemove).toHaveBeenCalledWith(testFileName);
-            expect(mockRemove).toHaveBeenCalledWith('/some/parent/merge/.thumbnails/file1.webp');
-            expect(mockRemove).toHaveBeenCalledWith('/some/parent/merge/.previews/file1.webp');
+            expect(mockInvoke).toHaveBeenCalledWith('delete_derivatives', {filePath: testFileName});
+            expect(mockInvoke).toHaveBeenCalledWith('delete_orientation_sidecar', {filePath: testFileName});
+            expect(mockInvoke).toHaveBeenCalledWith('discard_upload_journals', {directoryPath: testFileName});
+            expect(mockInvoke).toHaveBeenCalledWith('delete_derivatives', {filePath: '/some/parent/file1.tif'});
         });
     });
 
@@ -240,11 +249,8 @@ describe('DeleteFile', () => {
         expect(screen.getByTestId('error-log-count').textContent).toBe('1');
     });
 
-    it('treats non-missing optional cleanup failures as delete failures', async () => {
-        mockRemove
-            .mockResolvedValueOnce(undefined)
-            .mockResolvedValueOnce(undefined)
-            .mockRejectedValueOnce(new Error('Kunne ikke slette forhåndsvisning'));
+    it('treats thumbnail and preview cleanup failures as delete failures', async () => {
+        mockInvoke.mockRejectedValueOnce(new Error('Kunne ikke slette forhåndsvisning'));
 
         render(<TestWrapper />);
 
@@ -16,16 +16,10 @@ vi.mock('@tauri-apps/api/core', () => ({
     invoke: vi.fn().mockResolvedValue(undefined),
 }));
 
-vi.mock('../src/util/file-utils.ts', async () => {
-    const actual = await vi.importActual<typeof import('../src/util/file-utils.ts')>(
-        '../src/util/file-utils.ts'
-    );
-    return {
-        ...actual,
-        getPreviewFromTree: vi.fn(() => ({path: '/preview/path.webp'})),
-        getPreviewURIFromTree: vi.fn(() => 'mock-preview-uri'),
-    };
-});
+const mockPreviewPath = '/path/.previews/test.webp';
+
+const resolvePreviewPath = (command: string) =>
+    Promise.resolve(command === 'get_derivative_path' ? mockPreviewPath : undefined);
 
 // Make spinner overlay deterministic in tests
 vi.mock('../src/components/ui/loading-spinner.tsx', () => ({
@@ -53,6 +47,7 @@ const mockTrokkFilesState = {
     basePath: '',
     fileTrees: [],
     treeIndex: new Map(),
+    thumbnails: new Map(),
     current: undefined,
     preview: createMockFileTree('preview.webp', '/preview/path.webp'),
     isEven: true,
@@ -162,10 +157,6 @@ describe('DetailedImageView Rotation Feature', () => {
 
     it('shows status overlay during rotation', async () => {
         const {invoke} = await import('@tauri-apps/api/core');
-        const fileUtils = await import('../src/util/file-utils.ts');
-
-        // Ensure preview exists
-        vi.mocked(fileUtils.getPreviewFromTree).mockReturnValue(createMockFileTree('preview.webp', '/preview/path.webp'));
 
         vi.mocked(invoke).mockImplementation(() =>
             new Promise(resolve => setTimeout(resolve, 500))
@@ -192,9 +183,6 @@ describe('DetailedImageView Rotation Feature', () => {
 
     it('invokes backend rotation when button is clicked', async () => {
         const {invoke} = await import('@tauri-apps/api/core');
-        const fileUtils = await import('../src/util/file-utils.ts');
-
-        vi.mocked(fileUtils.getPreviewFromTree).mockReturnValue(createMockFileTree('preview.webp', '/preview/path.webp'));
 
         vi.mocked(invoke).mockResolvedValue(undefined);
 
@@ -219,17 +207,33 @@ describe('DetailedImageView Rotation Feature', () => {
         }, { timeout: 500 });
     });
 
+    it('shows the preview at the path resolved by the backend', async () => {
+        const {invoke} = await import('@tauri-apps/api/core');
+        vi.mocked(invoke).mockImplementation(resolvePreviewPath);
+
+        const fileTree = createMockFileTree('test.jpg', '/path/test.jpg');
+        const {container} = render(componentWithContext(fileTree));
+
+        await waitFor(() => {
+            expect(invoke).toHaveBeenCalledWith('get_derivative_path', {filePath: '/path/test.jpg', highRes: true});
+            expect(container.querySelector('img')?.getAttribute('src')).toContain(`mock-src/${mockPreviewPath}`);
+        });
+    });
+
     it('updates image src (cache buster) after rotation so it reloads', async () => {
         const {invoke} = await import('@tauri-apps/api/core');
-        vi.mocked(invoke).mockResolvedValue(undefined);
+        vi.mocked(invoke).mockImplementation(resolvePreviewPath);
 
         const fileTree = createMockFileTree('test.jpg', '/path/test.jpg');
         const {container} = render(componentWithContext(fileTree));
 
-        const img = container.querySelector('img') as HTMLImageElement | null;
-        expect(img).toBeTruthy();
+        const img = await waitFor(() => {
+            const found = container.querySelector('img') as HTMLImageElement | null;
+            if (!found) throw new Error('Preview not shown');
+            return found;
+        });
 
-        const beforeSrc = img!.getAttribute('src');
+        const beforeSrc = img.getAttribute('src');
 
         const clockwiseBtn = container.querySelector('[aria-label="Roter med klokken"]') as HTMLButtonElement | null;
         expect(clockwiseBtn).toBeTruthy();
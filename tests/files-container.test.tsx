@@ -90,13 +90,9 @@ describe('FilesContainer', () => {
         };
         const thumbnailPath = '/mock/path/.thumbnails/example.webp';
 
-        // Add the thumbnail FileTree to treeIndex map
+        // The thumbnail path resolved by the backend, by the image it belongs to
         const treeIndex = new Map<string, {name: string; path: string; isDirectory: boolean}>();
-        treeIndex.set(thumbnailPath, {
-            name: 'example.webp',
-            path: thumbnailPath,
-            isDirectory: false,
-        });
+        const thumbnails = new Map<string, string>([[originalFile.path, thumbnailPath]]);
 
         (useTrokkFiles as Mock).mockReturnValue({
             state: {
@@ -105,6 +101,7 @@ describe('FilesContainer', () => {
                 },
                 preview: false,
                 treeIndex,
+                thumbnails,
             },
             dispatch: vi.fn(),
         });
@@ -149,11 +146,9 @@ describe('FilesContainer', () => {
         // Simulate state where DetailedImageView set state.preview but it wasn't cleared
         // (the primary close path via DialogContent.onClick doesn't call handleClose)
         const treeIndex = new Map<string, {name: string; path: string; isDirectory: boolean}>();
-        treeIndex.set('/mock/path/.thumbnails/example.webp', {
-            name: 'example.webp',
-            path: '/mock/path/.thumbnails/example.webp',
-            isDirectory: false,
-        });
+        const thumbnails = new Map<string, string>([
+            ['/mock/path/example.jpg', '/mock/path/.thumbnails/example.webp'],
+        ]);
 
         (useTrokkFiles as Mock).mockReturnValue({
             state: {
@@ -170,6 +165,7 @@ describe('FilesContainer', () => {
                     isDirectory: false,
                 },
                 treeIndex,
+                thumbnails,
             },
             dispatch: vi.fn(),
         });
@@ -204,6 +200,7 @@ describe('FilesContainer', () => {
                 fileTrees: [parentFolder],
                 preview: undefined,
                 treeIndex: new Map<string, {name: string; path: string; isDirectory: boolean}>(),
+                thumbnails: new Map<string, string>(),
                 isEven: false,
                 basePath: '/root',
             },
@@ -216,6 +213,30 @@ describe('FilesContainer', () => {
         expect(screen.queryByText(/Mappe/)).not.toBeNull();
     });
 
+    it('shows thumbnail conversion progress for the active folder', () => {
+        const selectedFolder = new FileTree('Mappe', true, false, false, '/root/Mappe', false, [
+            new FileTree('example.jpg', false, true, false, '/root/Mappe/example.jpg'),
+        ]);
+
+        (useTrokkFiles as Mock).mockReturnValue({
+            state: {
+                current: selectedFolder,
+                fileTrees: [selectedFolder],
+                preview: undefined,
+                treeIndex: new Map<string, {name: string; path: string; isDirectory: boolean}>(),
+                thumbnails: new Map<string, string>(),
+                conversionProgress: {directory: '/root/Mappe', currentFile: 'example.jpg', done: 3, total: 10, failures: 1},
+                isEven: false,
+                basePath: '/root',
+            },
+            dispatch: vi.fn(),
+        });
+        renderWithContext();
+
+        expect(screen.queryByText(/Lager miniatyrbilder: 3 av 10/)).not.toBeNull();
+        expect(screen.queryByText(/1 feilet/)).not.toBeNull();
+    });
+
     it('does not create a pane-owned overflow-auto scroller inside the file grid content area', () => {
         renderWithContext();
 
@@ -237,6 +258,7 @@ describe('FilesContainer', () => {
                 },
                 preview: false,
                 treeIndex,
+                thumbnails: new Map<string, string>(),
                 basePath: '/mock',
             },
             dispatch: vi.fn(),
@@ -263,6 +285,7 @@ describe('FilesContainer', () => {
                 },
                 preview: false,
                 treeIndex: new Map(),
+                thumbnails: new Map<string, string>(),
                 basePath: '/mock',
             },
             dispatch: vi.fn(),
@@ -287,6 +310,7 @@ describe('FilesContainer', () => {
                 },
                 preview: false,
                 treeIndex,
+                thumbnails: new Map<string, string>(),
                 basePath: '/mock',
             },
             dispatch: vi.fn(),
@@ -320,6 +344,7 @@ describe('FilesContainer', () => {
                 },
                 preview: false,
                 treeIndex,
+                thumbnails: new Map<string, string>(),
                 basePath: '/mock',
             },
             dispatch: vi.fn(),
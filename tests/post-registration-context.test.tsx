@@ -1,7 +1,7 @@
-import { act, renderHook } from '@testing-library/react';
+import { act, renderHook, waitFor } from '@testing-library/react';
 import {deleteDirFromProgressState, usePostRegistration} from '../src/context/post-registration-context';
 import { settings } from '../src/tauri-store/setting-store';
-import { uploadToS3 } from '../src/features/registration/upload-to-s3';
+import { cancelUpload, uploadToS3 } from '../src/features/registration/upload-to-s3';
 import { MaterialType } from '../src/model/registration-enums';
 import { RegistrationFormProps } from '../src/features/registration/registration-form-props';
 import { vi, type Mock } from 'vitest';
@@ -10,7 +10,7 @@ import { invoke } from '@tauri-apps/api/core';
 import {AuthProvider} from '../src/context/auth-context';
 import {SecretProvider} from '../src/context/secret-context';
 import {SelectionProvider} from '../src/context/selection-context';
-import { groupFilesByCheckedItems } from '../src/context/post-registration-context';
+import { groupFilesByCheckedItems, itemsFromUploadSummary } from '../src/context/post-registration-context';
 import { TransferProgress } from '@/model/transfer-progress';
 
 const mockHandleError = vi.fn();
@@ -56,6 +56,7 @@ vi.mock('@tauri-apps/api/webviewWindow', () => ({
 
 vi.mock('../src/features/registration/upload-to-s3', () => ({
     uploadToS3: vi.fn(),
+    cancelUpload: vi.fn(),
 }));
 
 vi.mock('../src/context/trokk-files-context', () => ({
@@ -119,7 +120,7 @@ const registration: RegistrationFormProps = {
 
 const mockCommonSetup = () => {
     (settings.getAuthResponse as Mock).mockResolvedValue({ userInfo: { name: 'Test User' } });
-    (uploadToS3 as Mock).mockResolvedValue(3);
+    (uploadToS3 as Mock).mockResolvedValue({uploadedCount: 0, objects: [], uploadDates: {}});
     (invoke as Mock).mockImplementation((cmd: string) => {
         switch (cmd) {
             case 'get_papi_access_token':
@@ -179,6 +180,38 @@ describe('usePostRegistration', () => {
         await expect(result.current.postRegistration('TestMachine', registration)).rejects.toThrow('upload failed');
     });
 
+    it('keeps the job id of the upload in progress so it can be cancelled', async () => {
+        mockCommonSetup();
+        let rejectUpload: (error: unknown) => void = () => {};
+        (uploadToS3 as Mock).mockImplementation(() => new Promise((_, reject) => {
+            rejectUpload = reject;
+        }));
+        (cancelUpload as Mock).mockResolvedValue(true);
+
+        const { result } = renderHook(() => usePostRegistration(), { wrapper });
+
+        let posting: Promise<void> = Promise.resolve();
+        act(() => {
+            posting = result.current.postRegistration('TestMachine', registration);
+        });
+
+        await waitFor(() => expect(result.current.uploadJobId).not.toBeNull());
+        const jobId = result.current.uploadJobId;
+        expect(uploadToS3).toHaveBeenCalledWith(registration, expect.any(Map), jobId);
+
+        await act(async () => {
+            await result.current.cancelCurrentUpload();
+        });
+        expect(cancelUpload).toHaveBeenCalledWith(jobId);
+
+        const cancelled = {kind: 'cancelled', message: 'Upload was cancelled'};
+        rejectUpload(cancelled);
+        await act(async () => {
+            await expect(posting).rejects.toEqual(cancelled);
+        });
+        expect(result.current.uploadJobId).toBeNull();
+    });
+
     it('blocks posting when version is blocking', async () => {
         mockUploadVersionBlocking = true;
 
@@ -381,4 +414,38 @@ describe('usePostRegistration', () => {
         });
     });
 
+    it('includes the uploaded checksums of each item in the registration items', () => {
+        const batchMap = new Map([
+            ['item-1', {primary: ['/dir/1.tif', '/dir/2.tif'], access: ['/dir/merge/1.tif', '/dir/merge/2.tif']}],
+            ['item-2', {primary: ['/dir/3.tif'], access: []}],
+        ]);
+        const object = (key: string, path: string) => ({key, path, size: 1, sha256: `sha-${key}`});
+
+        const items = itemsFromUploadSummary(batchMap, {
+            uploadedCount: 5,
+            objects: [
+                object('item-1/primary/1.tif', '/dir/1.tif'),
+                object('item-1/access/1.tif', '/dir/merge/1.tif'),
+                object('item-2/primary/3.tif', '/dir/3.tif'),
+            ],
+            uploadDates: {},
+        });
+
+        expect(items).toEqual([
+            {
+                itemId: 'item-1',
+                pages: 2,
+                checksums: [
+                    {key: 'item-1/primary/1.tif', sha256: 'sha-item-1/primary/1.tif'},
+                    {key: 'item-1/access/1.tif', sha256: 'sha-item-1/access/1.tif'},
+                ],
+            },
+            {
+                itemId: 'item-2',
+                pages: 1,
+                checksums: [{key: 'item-2/primary/3.tif', sha256: 'sha-item-2/primary/3.tif'}],
+            },
+        ]);
+    });
+
 });
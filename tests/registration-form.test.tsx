@@ -83,8 +83,14 @@ vi.mock('@/context/auth-context.tsx', () => ({
 }));
 
 const mockPostRegistration = vi.fn();
+const mockCancelCurrentUpload = vi.fn();
+let mockUploadJobId: string | null = null;
 vi.mock('@/context/post-registration-context.tsx', () => ({
-    usePostRegistration: () => ({ postRegistration: mockPostRegistration })
+    usePostRegistration: () => ({
+        postRegistration: mockPostRegistration,
+        uploadJobId: mockUploadJobId,
+        cancelCurrentUpload: mockCancelCurrentUpload
+    })
 }));
 
 vi.mock('@/context/trokk-files-context.tsx', () => ({
@@ -202,6 +208,8 @@ describe('RegistrationForm', () => {
             name: 'Test File',
         };
         mockPostRegistration.mockReset();
+        mockCancelCurrentUpload.mockReset();
+        mockUploadJobId = null;
         mockLogin.mockReset();
         mockCheckUploadVersionGate.mockResolvedValue(false);
     });
@@ -321,6 +329,43 @@ describe('RegistrationForm', () => {
         });
     });
 
+    it('shows a cancelled upload separately from the upload-start error', async () => {
+        mockPostRegistration.mockRejectedValueOnce({kind: 'cancelled', message: 'Upload was cancelled'});
+
+        render(<RegistrationFormWrapper checkedItems={['id1']} />);
+
+        await waitFor(() => {
+            expect(screen.getByText(/1 forside valgt/i)).toBeDefined();
+        });
+
+        fireEvent.click(screen.getByRole('button', { name: /TRØKK!/i }));
+
+        await waitFor(() => {
+            expect(screen.getByText(/Opplastingen ble avbrutt\./i)).toBeDefined();
+        });
+        expect(screen.queryByText(/Kunne ikke starte opplasting\./i)).toBeNull();
+        expect(screen.queryByRole('dialog')).toBeNull();
+    });
+
+    it('cancels the upload in progress from the cancel button', async () => {
+        mockUploadJobId = 'job-1';
+        mockCancelCurrentUpload.mockResolvedValue(true);
+
+        render(<RegistrationFormWrapper checkedItems={['id1']} />);
+
+        fireEvent.click(screen.getByRole('button', { name: /Avbryt opplasting/i }));
+
+        await waitFor(() => {
+            expect(mockCancelCurrentUpload).toHaveBeenCalledTimes(1);
+        });
+    });
+
+    it('hides the cancel button when no upload is in progress', () => {
+        render(<RegistrationFormWrapper checkedItems={['id1']} />);
+
+        expect(screen.queryByRole('button', { name: /Avbryt opplasting/i })).toBeNull();
+    });
+
     it('clears a previous blocking error when the user corrects selection and retries the flow', async () => {
         const {rerender} = render(<RegistrationFormWrapper checkedItems={[]} />);
 
@@ -16,12 +16,28 @@ vi.mock('@/context/setting-context.tsx', () => ({
         version: {current: '1.2.3'},
         textSize: 100,
         setTextSize: vi.fn(),
-        thumbnailSizeFraction: 8,
-        previewSizeFraction: 4,
+        conversionProfiles: {
+            thumbnail: {filter: 'nearest', quality: 25, lossless: false, size: {mode: 'fraction', fraction: 8}},
+            preview: {filter: 'catmullRom', quality: 75, lossless: false, size: {mode: 'fraction', fraction: 4}},
+        },
         workspacePaneSizes: [22, 48, 30],
-        setThumbnailSizeFraction: vi.fn(),
-        setPreviewSizeFraction: vi.fn(),
+        setConversionProfile: vi.fn(),
         setWorkspacePaneSizes: vi.fn(),
+        derivativeCacheEnabled: false,
+        setDerivativeCacheEnabled: vi.fn(),
+        sharpnessThreshold: 100,
+        setSharpnessThreshold: vi.fn(),
+        uploadRetryPolicy: {maxAttempts: 5, initialBackoffMs: 500, maxBackoffMs: 30000, jitter: true},
+        setUploadRetryPolicy: vi.fn(),
+        uploadConcurrency: {maxConcurrentObjects: 4, maxConcurrentParts: 2},
+        setUploadConcurrency: vi.fn(),
+        keyLayout: {
+            objectIdTemplate: 'tekst_{batch_id}',
+            batchKeyTemplate: '{object_id}/representations/{representation}/data/{object_id}_{page:05}.{extension}',
+            directoryKeyTemplate: '{object_id}/{object_id}_{page:05}.{extension}',
+        },
+        keyLayoutError: undefined,
+        setKeyLayout: vi.fn(),
     }),
 }));
 
@@ -47,6 +47,7 @@ const mockTrokkFilesState = {
     basePath: '',
     fileTrees: [],
     treeIndex: new Map(),
+    thumbnails: new Map(),
     current: undefined,
     preview: undefined,
     isEven: true,